@@ -1,16 +1,33 @@
 // SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: MIT
 
+use std::cmp;
+use std::collections;
+use std::fmt;
 use std::fs;
 use std::path;
+use std::str;
 
 use merge::Merge;
 use serde::Deserialize;
 use structopt::StructOpt;
 
 use crate::doc;
+use crate::parser;
 use crate::viewer;
 
+/// The options that make the keyword optional.  If only `--source` is set, the keyword can only be
+/// omitted if one of the sources is a single HTML file, see `FileSource`.
+const KEYWORD_OPTIONAL_ARGS: &[&str] = &[
+    "source",
+    "clear-cache",
+    "list-crates",
+    "complete-members",
+    "completions",
+    "theme-preview",
+    "history",
+];
+
 /// Command-line viewer for rustdoc documentation
 ///
 /// rusty-man reads the HTML documentation generated by rustdoc and displays a documentation item.
@@ -27,19 +44,53 @@ use crate::viewer;
 #[serde(default)]
 pub struct Args {
     /// The keyword to open the documentation for, e. g. `rand_core::RngCore`
+    ///
+    /// The keyword can also be the path of an HTML file generated by rustdoc or a `file://` URL,
+    /// optionally with a member as the fragment, e. g. `target/doc/log/trait.Log.html#tymethod.log`.
+    ///
+    /// If the last segment is `*`, e. g. `rand_core::RngCore::*`, the members of the item are
+    /// listed.  Use `\*` to look up an item named `*`.  If a source is a single HTML file, the
+    /// keyword can be omitted to open the item documented in this file.
     #[merge(skip)]
+    #[structopt(required_unless_one = KEYWORD_OPTIONAL_ARGS)]
     #[serde(skip)]
-    pub keyword: doc::Name,
+    pub keyword: Option<doc::Name>,
 
     /// The sources to check for documentation generated by rustdoc
     ///
     /// Typically, this is the path of a directory containing the documentation for one or more
-    /// crates in subdirectories.
+    /// crates in subdirectories.  It can also be a single HTML file generated by rustdoc.  The name
+    /// of the item is then read from the file, and only this item and its members are available.
     #[merge(strategy = merge::vec::prepend)]
     #[structopt(name = "source", short, long, number_of_values = 1)]
     pub source_paths: Vec<String>,
 
-    /// The viewer for the rustdoc documentation (one of: plain, rich, tui)
+    /// The sources to use for specific crates
+    ///
+    /// This setting can only be set in the configuration file as a `[sources]` table that maps
+    /// crate names to source directories, for example `serde = "/usr/share/doc/rust/html"` or
+    /// `mycrate = "../mycrate/target/doc"`.  Relative paths are resolved against the directory
+    /// of the configuration file, and sources that do not exist are ignored.  A crate name can
+    /// end with `*` to match all crates starting with the given prefix.  The source for a crate
+    /// is searched before all other sources when looking up or searching items of that crate.
+    #[merge(strategy = overwrite_empty_map)]
+    #[structopt(skip)]
+    #[serde(rename = "sources")]
+    pub crate_sources: collections::BTreeMap<String, String>,
+
+    /// The search indexes to use for specific sources
+    ///
+    /// This setting can only be set in the configuration file as an `[indexes]` table that maps
+    /// source directories to search index files, for example `"/srv/doc" =
+    /// "static/search-index.js"`.  Relative index paths are resolved against the source
+    /// directory.  Per default, rusty-man uses the most recent `search-index*.js` file in the
+    /// source directory or one of its direct subdirectories.
+    #[merge(strategy = overwrite_empty_map)]
+    #[structopt(skip)]
+    #[serde(rename = "indexes")]
+    pub source_indexes: collections::BTreeMap<String, String>,
+
+    /// The viewer for the rustdoc documentation (one of: plain, rich, roff, tui)
     #[structopt(long, parse(try_from_str = viewer::get_viewer))]
     #[serde(deserialize_with = "deserialize_viewer")]
     pub viewer: Option<Box<dyn viewer::Viewer>>,
@@ -59,6 +110,16 @@ pub struct Args {
     #[structopt(long)]
     pub open: bool,
 
+    /// Open the source file of the item in $EDITOR
+    ///
+    /// This only works for documentation that has been generated locally, e. g. with cargo doc:
+    /// rusty-man follows the [src] link of the item and looks up the original source file in the
+    /// workspace that contains the documentation directory or in the current directory.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub edit: bool,
+
     /// Do not read the search index if there is no exact match
     ///
     /// Per default, rusty-man reads the search indexes of all sources and tries to find matching
@@ -68,11 +129,273 @@ pub struct Args {
     #[structopt(long)]
     pub no_search: bool,
 
+    /// Prefer the given crate when looking up and searching items
+    ///
+    /// If this option is set, the sources containing the crate are searched first and matches
+    /// from this crate are listed first in the search results.  This is useful if a local crate
+    /// has the same name as an item in a dependency.
+    #[structopt(long, value_name = "crate")]
+    pub prefer: Option<String>,
+
+    /// Show items that are marked with #[doc(hidden)]
+    ///
+    /// These items are only included in documentation generated with rustdoc’s
+    /// --document-hidden-items option.  Per default, rusty-man ignores them so that internal
+    /// implementation details are not opened accidentally.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub show_hidden: bool,
+
+    /// Ignore items that are marked with #[doc(hidden)], even if --show-hidden is set in the
+    /// configuration file.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub no_show_hidden: bool,
+
+    /// The maximum size of the HTML files that are parsed in MB
+    ///
+    /// Larger files, for example files that have been corrupted by a broken build script, are
+    /// skipped.  Default value: 50.
+    #[structopt(long, value_name = "mb")]
+    pub max_file_size: Option<u64>,
+
+    /// Only look up items of the given type, e. g. `macro`, `mod` or `fn`
+    ///
+    /// The type is the prefix that rustdoc uses for the file names of the items, for example
+    /// `struct`, `trait` or `type`.  Additionally, `assoc-fn` selects methods without a `self`
+    /// receiver like `Vec::new`.  If this option is not set and the keyword matches items of
+    /// different types, rusty-man opens the first match and prints a notice about the others.
+    #[merge(skip)]
+    #[structopt(name = "type", long = "type", value_name = "type")]
+    #[serde(skip)]
+    pub item_type: Option<TypeFilter>,
+
+    /// The picker for selecting an item if a search returns multiple matches (one of: prompt, tui)
+    ///
+    /// The prompt picker lists the matches and reads the number of the selected item from the
+    /// standard input.  The tui picker shows the matches in an interactive dialog with a preview
+    /// of the highlighted item.  Per default, rusty-man uses the tui picker for the tui viewer and
+    /// the prompt picker for all other viewers.
+    #[structopt(long)]
+    pub picker: Option<Picker>,
+
+    /// Fall back to the standard library documentation on doc.rust-lang.org
+    ///
+    /// If this option is set and none of the sources contains the documentation for the standard
+    /// library, rusty-man downloads it from doc.rust-lang.org using curl.  The downloaded files
+    /// are cached in the user cache directory.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub online: bool,
+
+    /// Never access the network, even if --online is set
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub offline: bool,
+
+    /// Cache the output of the plain and rich viewers
+    ///
+    /// If this option is set, the rendered documentation is stored in the user cache directory and
+    /// displayed without reading the HTML files again if rusty-man is called with the same keyword
+    /// and options.  The cache is invalidated if the HTML file of the item changes.  Only items
+    /// that exactly match the keyword are cached.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub cache_render: bool,
+
+    /// Do not use the render cache, even if --cache-render is set
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub no_cache: bool,
+
+    /// The directory for cached files
+    ///
+    /// rusty-man stores the render cache, see --cache-render, and the documentation downloaded
+    /// with --online in this directory.  Per default, it uses the rusty-man subdirectory of the
+    /// user cache directory, typically ~/.cache/rusty-man.
+    #[structopt(long, value_name = "path")]
+    pub cache_dir: Option<String>,
+
+    /// Delete all cached files and exit
+    ///
+    /// If this option is set, rusty-man removes the render cache and the downloaded
+    /// documentation from the cache directory, prints the number and the size of the removed
+    /// files and exits.  The keyword is not required.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub clear_cache: bool,
+
+    /// List the crates in all sources and exit
+    ///
+    /// If this option is set, rusty-man prints the names of the crates that are available in the
+    /// documentation sources and the URLs of their main pages, separated by a tab.  The keyword is
+    /// not required.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub list_crates: bool,
+
+    /// Print the full names of the members of the given item and exit
+    ///
+    /// This is intended for shell completion scripts and other tools.  It only reads the page of
+    /// the item, so it is faster than listing the members with the `::*` keyword.
+    #[merge(skip)]
+    #[structopt(long, hidden = true, value_name = "parent")]
+    #[serde(skip)]
+    pub complete_members: Option<doc::Name>,
+
+    /// Print a completion script for the given shell and exit
+    ///
+    /// For bash, the script also completes the crate names listed by --list-crates.  The list is
+    /// cached in a temporary file for one hour.
+    #[merge(skip)]
+    #[structopt(
+        long,
+        value_name = "shell",
+        possible_values = &structopt::clap::Shell::variants(),
+        case_insensitive = true
+    )]
+    #[serde(skip)]
+    pub completions: Option<structopt::clap::Shell>,
+
+    /// Print a code snippet highlighted with every syntax highlighting theme and exit
+    ///
+    /// This shows the colors of the themes that can be selected with --theme.  The keyword is not
+    /// required.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub theme_preview: bool,
+
+    /// List the recently opened items or open the item with the given number from this list
+    ///
+    /// rusty-man records every item that it finds in the history file in the user data directory,
+    /// typically ~/.local/share/rusty-man/history.  Without a value, this option lists the
+    /// entries of the history, starting with the most recent one, and asks which entry should be
+    /// opened if stdin is a TTY.  With a value, it opens the entry with the given number, e. g.
+    /// `--history 1` for the last item.  The keyword is not required.
+    #[merge(skip)]
+    #[structopt(long, value_name = "n")]
+    #[serde(skip)]
+    pub history: Option<Option<usize>>,
+
+    /// The maximum number of entries in the history, see --history.  Default value: 1000.
+    #[structopt(long, value_name = "n")]
+    pub history_size: Option<usize>,
+
+    /// Whether the items that are opened should be added to the history
+    ///
+    /// This setting can only be set in the configuration file as `history = false`.  Per default,
+    /// the history is recorded.
+    #[structopt(skip)]
+    #[serde(rename = "history")]
+    pub record_history: Option<bool>,
+
     /// Show all examples for the item instead of opening the full documentation.
     #[merge(strategy = merge::bool::overwrite_false)]
     #[structopt(short, long)]
     pub examples: bool,
 
+    /// Include the lines that are hidden by rustdoc in the examples shown by --examples
+    ///
+    /// The hidden lines can only be recovered if the documentation contains a link to the Rust
+    /// playground for the example.  Otherwise, the visible lines are shown.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub full_examples: bool,
+
+    /// Open the parent item if a member of an existing item could not be found
+    ///
+    /// If the keyword refers to a member that does not exist, for example because of a typo,
+    /// rusty-man lists similar members of the parent item.  Per default, it asks whether the
+    /// parent item should be opened instead if stdin is a TTY.  If this option is set, the parent
+    /// item is opened without asking.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub fallback_parent: bool,
+
+    /// Print the examples of all items in a crate instead of opening the documentation
+    ///
+    /// If this option is set, the keyword must be the name of a crate.  rusty-man reads the list
+    /// of items from the search index of the crate, collects the examples of all items and prints
+    /// them to the standard output.  This is useful to check that the examples of a crate exist
+    /// and are rendered correctly.  At most 1000 items are scanned.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub all_examples: bool,
+
+    /// List all types in a crate that implement the given trait
+    ///
+    /// If this option is set, the keyword must be the name of a crate.  rusty-man reads the list
+    /// of structs, enums and unions from the search index of the crate and checks their trait
+    /// implementations.  With the tui viewer, the implementors are displayed in a selection list.
+    /// Otherwise, they are printed to the standard output.  At most 1000 types are scanned.
+    #[merge(skip)]
+    #[structopt(long, value_name = "trait")]
+    #[serde(skip)]
+    pub implementors_of: Option<String>,
+
+    /// Only show the given section of the documentation, e. g. "Methods" or "Trait
+    /// Implementations".
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub section: Option<String>,
+
+    /// Print the HTML of the documentation instead of opening it in the viewer
+    ///
+    /// For items, this prints the main content of the documentation page.  For members, it prints
+    /// the heading and the description of the member.  If --section is set, only the given
+    /// section of the page is printed, e. g. "Description" or "Trait Implementations".
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub raw: bool,
+
+    /// Write a roff manpage for the item to the standard output
+    ///
+    /// This is a shortcut for `--viewer roff`.  The output can be displayed with `man -l -` or
+    /// saved to a directory in the `MANPATH`.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub emit_manpage: bool,
+
+    /// Write the documentation for the item to a file in the given format instead of opening it
+    ///
+    /// The argument has the form format:path, where the format is the name of a viewer that can
+    /// write to a file (plain, rich or roff), e. g. `--emit plain:log.txt`.  This option can be
+    /// repeated to write several files in one run.
+    #[merge(skip)]
+    #[structopt(long, value_name = "format:path", number_of_values = 1)]
+    #[serde(skip)]
+    pub emit: Vec<Emit>,
+
+    /// Print a summary of the parsed documentation instead of opening it
+    ///
+    /// This shows the version of rustdoc that generated the documentation if available, the name
+    /// and type of the item, the lengths of the description and the definition, and the tree of
+    /// member groups with the populated fields of all members.  It is useful for debugging the
+    /// parser and for bug reports.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub dump_doc: bool,
+
+    /// Print a short overview of the item instead of opening it
+    ///
+    /// The overview consists of the type and name of the item, the first sentence of its
+    /// description, the number of members per group, the number of examples and the location of
+    /// the documentation.  It is not displayed in a pager.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub summary: bool,
+
     /// The path to the configuration file to read
     ///
     /// Per default, rusty-man tries to read defaults for the command-line arguments from the
@@ -103,14 +426,37 @@ pub struct ViewerArgs {
     #[structopt(long)]
     pub no_syntax_highlight: bool,
 
+    /// Disable highlighting of code.
+    ///
+    /// If this option is set, rusty-man neither highlights Rust code snippets nor colors inline
+    /// code in the rich and tui viewers.  Other text styles like bold or italic text are still
+    /// displayed.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub no_code_highlight: bool,
+
     /// The color theme for syntax highlighting
     ///
     /// rusty-man includes these color themes: base16-ocean.dark, base16-eighties.dark,
     /// base16-mocha.dark, base16-ocean.light, InspiredGitHub, Solarized (dark), Solarized (light).
-    /// Default value: base16-eighties.dark.
+    /// Per default, rusty-man tries to detect whether the terminal has a light or a dark
+    /// background and uses the theme set with --theme-light or --theme-dark.
     #[structopt(long)]
     pub theme: Option<String>,
 
+    /// The color theme for syntax highlighting on terminals with a light background
+    ///
+    /// This option is only used if the --theme option is not set.  Default value: InspiredGitHub.
+    #[structopt(long, value_name = "theme")]
+    pub theme_light: Option<String>,
+
+    /// The color theme for syntax highlighting on terminals with a dark background
+    ///
+    /// This option is only used if the --theme option is not set.  Default value:
+    /// base16-eighties.dark.
+    #[structopt(long, value_name = "theme")]
+    pub theme_dark: Option<String>,
+
     /// The width of the text output
     ///
     /// Per default, rusty-man sets the width of the text output based on the width of the terminal
@@ -132,6 +478,350 @@ pub struct ViewerArgs {
     /// this environment variable is not set.
     #[structopt(long)]
     pub pager: Option<String>,
+
+    /// Show the notable traits of the return types of functions.
+    ///
+    /// rustdoc lists notable traits like Iterator or Future that are implemented by the return
+    /// type of a function.  Per default, rusty-man ignores this information.  If this option is
+    /// set, it is displayed below the function definition.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub notable_traits: bool,
+
+    /// Show the other items in the parent module at the end of the documentation page.
+    ///
+    /// This is the list of items that rustdoc displays in the sidebar.  The tui viewer always
+    /// makes this list available using the s key.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub show_siblings: bool,
+
+    /// Hide items that are not public.
+    ///
+    /// If the documentation has been generated with --document-private-items, it also contains
+    /// private items.  If this option is set, they are not listed on the documentation pages and
+    /// not shown in the search results.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub public_only: bool,
+
+    /// Show member groups in which no member has a definition or a description.
+    ///
+    /// Per default, these groups are hidden, except for the members of modules.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub show_empty_groups: bool,
+
+    /// Prefix the names of the members with their type, e. g. `[struct] Foo` or `[function] bar`.
+    ///
+    /// This makes it easier to scan the member lists, especially in the compact layout of modules.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub type_prefix: bool,
+
+    /// Do not break long definitions into multiple lines.
+    ///
+    /// Per default, rusty-man moves where clauses in definitions to separate lines with one bound
+    /// per line and puts the parameters of functions that are wider than the output on separate
+    /// lines.  If this option is set, the definitions are displayed as generated by rustdoc.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub no_format_signatures: bool,
+
+    /// Do not print separator lines between the sections of the documentation page.
+    ///
+    /// This is useful if the output of the plain or rich viewer is processed by other programs.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub no_separators: bool,
+
+    /// Only use ASCII characters for decorations like separators, quote gutters and warnings.
+    ///
+    /// This is useful for terminals or fonts that cannot display box-drawing characters.  In the
+    /// tui viewer, the borders of the panels and dialogs are hidden as cursive cannot draw ASCII
+    /// borders.  Dashes, ellipses and non-breaking spaces in the text are also replaced, but code
+    /// and definitions are not changed.  Other non-ASCII characters, for example in names, are not
+    /// changed either.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub ascii: bool,
+
+    /// The order of the sections of the documentation page
+    ///
+    /// A comma-separated list of these sections:  title, platform, synopsis, description, members,
+    /// examples, see-also, notes.  Sections that are not listed are not displayed.  Default value:
+    /// title,platform,synopsis,description,members,see-also.
+    #[merge(strategy = merge::vec::overwrite_empty)]
+    #[structopt(long, use_delimiter = true)]
+    pub section_order: Vec<SectionKind>,
+
+    /// The order of the trait implementations: grouped or source
+    ///
+    /// grouped lists the implementations of the same trait under a common heading (default).
+    /// source uses the order of the rustdoc output.
+    #[structopt(long)]
+    pub impl_order: Option<ImplOrder>,
+
+    /// The order of the members: source or alpha
+    ///
+    /// source uses the order of the rustdoc output (default).  alpha sorts the members of each
+    /// group, including the trait implementations, by their name.
+    #[structopt(long)]
+    pub sort: Option<SortOrder>,
+
+    /// The level of detail of the documentation page
+    ///
+    /// 0 only shows the title, 1 adds the synopsis, description and the other sections of the
+    /// item, 2 adds the names of the members and 3 adds the definitions and descriptions of the
+    /// members.  Per default, all details are shown.
+    #[structopt(long)]
+    pub depth: Option<u8>,
+
+    /// The maximum number of lines of the definitions of the item and its members
+    ///
+    /// Longer definitions are truncated and followed by a note with the number of lines of the
+    /// full definition.  Per default, the definitions are not truncated.
+    #[structopt(long, value_name = "n")]
+    pub max_definition_lines: Option<usize>,
+
+    /// Do not truncate the definitions, even if --max-definition-lines is set in the
+    /// configuration file.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub no_max_definition_lines: bool,
+
+    /// List the members of modules in compact rows
+    ///
+    /// In the compact layout, every member of a module is printed in a single row with its name
+    /// and the first line of its description, truncated to the line length.  Per default, the
+    /// plain and rich viewers use the compact layout for modules with more than 30 members.  The
+    /// other viewers ignore this option.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub compact: bool,
+
+    /// Never list the members of modules in compact rows, even if --compact is set in the
+    /// configuration file.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub no_compact: bool,
+
+    /// Remove comment-only lines and repeated blank lines from examples
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub compact_examples: bool,
+
+    /// Show a prominent warning if an item is not available on the current platform.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub platform_warnings: bool,
+
+    /// Prompt for a link to follow after the documentation page has been displayed.
+    ///
+    /// The links on the page are listed with a number after the pager exits.  Entering a number
+    /// opens the linked documentation page or the external link in the web browser.  An empty
+    /// input ends the prompt.  This option is only supported by the plain and rich viewers and only
+    /// if rusty-man is run in a terminal.
+    #[merge(strategy = merge::bool::overwrite_false)]
+    #[structopt(long)]
+    pub follow: bool,
+
+    /// Render the documentation again when it is rebuilt, e. g. with cargo doc
+    ///
+    /// If this option is set, rusty-man watches the HTML file of the item and the search index for
+    /// changes.  The plain and rich viewers print the documentation without a pager and clear the
+    /// screen before printing it again.  The tui viewer reloads the current page, see also the R
+    /// key.  This option only works for local documentation.
+    #[merge(skip)]
+    #[structopt(long)]
+    #[serde(skip)]
+    pub watch: bool,
+}
+
+/// The picker for search results with multiple matches, see the `--picker` option.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Picker {
+    Prompt,
+    Tui,
+}
+
+impl str::FromStr for Picker {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "prompt" => Ok(Picker::Prompt),
+            "tui" => Ok(Picker::Tui),
+            _ => Err(anyhow::anyhow!("Unsupported picker: {}", s)),
+        }
+    }
+}
+
+/// The type of the item to look up, see the `--type` option.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TypeFilter {
+    Item(doc::ItemType),
+    /// A method without a `self` receiver, see `doc::Doc::is_assoc_fn`.
+    AssocFn,
+}
+
+impl TypeFilter {
+    /// Returns the item type that is looked up for this filter, or `None` if it matches multiple
+    /// item types.
+    pub fn item_type(&self) -> Option<doc::ItemType> {
+        match self {
+            TypeFilter::Item(ty) => Some(*ty),
+            TypeFilter::AssocFn => None,
+        }
+    }
+
+    /// Whether items of the given type can match this filter.  Associated functions can be
+    /// methods of types or of traits.
+    pub fn matches_type(&self, ty: doc::ItemType) -> bool {
+        match self {
+            TypeFilter::Item(item_ty) => *item_ty == ty,
+            TypeFilter::AssocFn => ty == doc::ItemType::Method || ty == doc::ItemType::TyMethod,
+        }
+    }
+
+    pub fn matches(&self, doc: &doc::Doc) -> bool {
+        match self {
+            TypeFilter::Item(ty) => doc.ty == *ty,
+            TypeFilter::AssocFn => doc.is_assoc_fn(),
+        }
+    }
+}
+
+impl str::FromStr for TypeFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "assoc-fn" {
+            Ok(TypeFilter::AssocFn)
+        } else {
+            s.parse().map(TypeFilter::Item)
+        }
+    }
+}
+
+impl fmt::Display for TypeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeFilter::Item(ty) => write!(f, "{}", ty.short_name()),
+            TypeFilter::AssocFn => write!(f, "assoc-fn"),
+        }
+    }
+}
+
+/// An output file for the documentation, see the `--emit` option.
+#[derive(Debug)]
+pub struct Emit {
+    pub format: String,
+    pub viewer: Box<dyn viewer::Viewer>,
+    pub path: path::PathBuf,
+}
+
+impl str::FromStr for Emit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = s
+            .split_once(':')
+            .filter(|(format, path)| !format.is_empty() && !path.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Expected format:path, got {}", s))?;
+        Ok(Emit {
+            format: format.to_owned(),
+            viewer: viewer::get_viewer(format)?,
+            path: path.into(),
+        })
+    }
+}
+
+/// The order of the trait implementations, see the `--impl-order` option.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImplOrder {
+    Grouped,
+    Source,
+}
+
+impl str::FromStr for ImplOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "grouped" => Ok(ImplOrder::Grouped),
+            "source" => Ok(ImplOrder::Source),
+            _ => Err(anyhow::anyhow!("Unsupported implementation order: {}", s)),
+        }
+    }
+}
+
+/// The order of the members of an item, see the `--sort` option.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Source,
+    Alpha,
+}
+
+impl str::FromStr for SortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "source" => Ok(SortOrder::Source),
+            "alpha" => Ok(SortOrder::Alpha),
+            _ => Err(anyhow::anyhow!("Unsupported sort order: {}", s)),
+        }
+    }
+}
+
+/// A section of the documentation page, see the `--section-order` option.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+    Title,
+    Platform,
+    Synopsis,
+    Description,
+    Members,
+    Examples,
+    #[serde(rename = "see-also")]
+    SeeAlso,
+    Notes,
+}
+
+impl SectionKind {
+    pub const DEFAULT_ORDER: &'static [SectionKind] = &[
+        SectionKind::Title,
+        SectionKind::Platform,
+        SectionKind::Synopsis,
+        SectionKind::Description,
+        SectionKind::Members,
+        SectionKind::SeeAlso,
+    ];
+}
+
+impl str::FromStr for SectionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "title" => Ok(SectionKind::Title),
+            "platform" => Ok(SectionKind::Platform),
+            "synopsis" => Ok(SectionKind::Synopsis),
+            "description" => Ok(SectionKind::Description),
+            "members" => Ok(SectionKind::Members),
+            "examples" => Ok(SectionKind::Examples),
+            "see-also" => Ok(SectionKind::SeeAlso),
+            "notes" => Ok(SectionKind::Notes),
+            _ => Err(anyhow::anyhow!("Unsupported section: {}", s)),
+        }
+    }
 }
 
 impl Args {
@@ -142,9 +832,25 @@ impl Args {
             args.merge(config);
         }
 
+        if let Some(keyword) = args.keyword.take() {
+            let normalized = normalize_keyword(keyword.as_ref());
+            if normalized != keyword.as_ref() {
+                log::debug!("Normalized keyword '{}' to '{}'", keyword, normalized);
+            }
+            args.keyword = Some(normalized.into());
+        }
+
         Ok(args)
     }
 
+    /// Returns the maximum size of the HTML files that are parsed in bytes, see the
+    /// `--max-file-size` option.
+    pub fn max_file_size_bytes(&self) -> u64 {
+        self.max_file_size
+            .map(|mb| mb.saturating_mul(1 << 20))
+            .unwrap_or(parser::html::DEFAULT_MAX_FILE_SIZE)
+    }
+
     fn load_config(file: Option<&str>) -> anyhow::Result<Option<Args>> {
         let path = if let Some(file) = file {
             if file == "-" {
@@ -158,14 +864,64 @@ impl Args {
         };
         if let Some(path) = path {
             log::info!("Loading configuration file '{}'", path.display());
-            let s = fs::read_to_string(path)?;
-            toml::from_str(&s).map(Some).map_err(From::from)
+            let s = fs::read_to_string(&path)?;
+            let mut args: Args = toml::from_str(&s)?;
+            // Relative crate sources are resolved against the directory of the configuration
+            // file so that they do not depend on the working directory.
+            if let Some(dir) = path.parent() {
+                for source in args.crate_sources.values_mut() {
+                    if path::Path::new(source).is_relative() {
+                        *source = dir.join(&source).display().to_string();
+                    }
+                }
+            }
+            Ok(Some(args))
         } else {
             Ok(None)
         }
     }
 }
 
+/// Fixes common mistakes in the path separators of the keyword:  Single colons are replaced with
+/// `::`, e. g. `std:fs:File`, and trailing colons are removed, e. g. `std::io::`.  File paths and
+/// `file://` URLs are not changed, see `source::find_file_doc`.
+fn normalize_keyword(keyword: &str) -> String {
+    if keyword.starts_with("file:")
+        || keyword.contains(&['/', '\\'][..])
+        || keyword.contains(".html")
+    {
+        return keyword.to_owned();
+    }
+    let mut s = String::with_capacity(keyword.len());
+    let mut colons = 0;
+    // Trailing colons are never added to the string.
+    for c in keyword.chars() {
+        if c == ':' {
+            colons += 1;
+            continue;
+        }
+        if colons > 0 {
+            s.push_str(&":".repeat(cmp::max(colons, 2)));
+            colons = 0;
+        }
+        s.push(c);
+    }
+    if s.is_empty() {
+        keyword.to_owned()
+    } else {
+        s
+    }
+}
+
+fn overwrite_empty_map<K: Ord, V>(
+    left: &mut collections::BTreeMap<K, V>,
+    right: collections::BTreeMap<K, V>,
+) {
+    if left.is_empty() {
+        *left = right;
+    }
+}
+
 fn deserialize_viewer<'de, D>(d: D) -> Result<Option<Box<dyn viewer::Viewer>>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -176,3 +932,54 @@ where
     s.map(|s| viewer::get_viewer(s).map_err(D::Error::custom))
         .transpose()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_keyword, Args, TypeFilter};
+    use crate::doc;
+
+    #[test]
+    fn test_normalize_keyword() {
+        assert_eq!("std::fs::File", normalize_keyword("std::fs::File"));
+        assert_eq!("std::fs::File", normalize_keyword("std:fs:File"));
+        assert_eq!("std::fs::File", normalize_keyword("std::fs:File"));
+        assert_eq!("std::io", normalize_keyword("std::io::"));
+        assert_eq!("std::io", normalize_keyword("std:io:"));
+        assert_eq!("Vec::*", normalize_keyword("Vec::*"));
+        assert_eq!("File", normalize_keyword("File"));
+        assert_eq!("::", normalize_keyword("::"));
+        assert_eq!("file:///x", normalize_keyword("file:///x"));
+        assert_eq!(
+            "file:///doc/log/trait.Log.html#tymethod.log",
+            normalize_keyword("file:///doc/log/trait.Log.html#tymethod.log")
+        );
+        assert_eq!(
+            "C:\\doc\\index.html",
+            normalize_keyword("C:\\doc\\index.html")
+        );
+        assert_eq!("trait.Log.html", normalize_keyword("trait.Log.html"));
+    }
+    #[test]
+    fn test_type_filter_matches_type() {
+        let filter = TypeFilter::AssocFn;
+        assert!(filter.matches_type(doc::ItemType::Method));
+        assert!(filter.matches_type(doc::ItemType::TyMethod));
+        assert!(!filter.matches_type(doc::ItemType::Function));
+        let filter = TypeFilter::Item(doc::ItemType::Method);
+        assert!(filter.matches_type(doc::ItemType::Method));
+        assert!(!filter.matches_type(doc::ItemType::TyMethod));
+    }
+
+    #[test]
+    fn test_load_config_crate_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.toml");
+        std::fs::write(&file, "[sources]\nfoo = \"doc\"\nbar = \"/srv/doc\"\n").unwrap();
+        let args = Args::load_config(file.to_str()).unwrap().unwrap();
+        assert_eq!(
+            Some(&dir.path().join("doc").display().to_string()),
+            args.crate_sources.get("foo")
+        );
+        assert_eq!(Some(&"/srv/doc".to_owned()), args.crate_sources.get("bar"));
+    }
+}
@@ -0,0 +1,250 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! Cache for the rendered output of the text viewers, see the `--cache-render` option, and
+//! management of the user cache directory, see the `--cache-dir` and `--clear-cache` options.
+//!
+//! The cache entries are stored in the `render` subdirectory of the user cache directory.  The
+//! file name is the hash of the cache key that contains all inputs that affect the output, for
+//! example the keyword, the viewer and the line length.  Each entry starts with the path and the
+//! modification time of the HTML file the documentation was read from.  An entry is only used if
+//! the HTML file has not been modified since the entry was written.
+
+use std::collections::hash_map;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash as _, Hasher as _};
+use std::path;
+use std::time;
+
+use anyhow::Context as _;
+
+/// The subdirectory of the cache directory that contains the render cache.
+const RENDER_DIR: &str = "render";
+/// The subdirectory of the cache directory that contains the downloaded documentation of the
+/// standard library.
+pub const ONLINE_DIR: &str = "doc.rust-lang.org";
+/// The subdirectories of the cache directory that are created by rusty-man.  Only these
+/// directories are removed by `clear` so that a wrong `--cache-dir` option cannot delete
+/// unrelated files.
+const CACHE_DIRS: &[&str] = &[RENDER_DIR, ONLINE_DIR];
+
+#[derive(Clone, Debug)]
+pub struct RenderCache {
+    path: path::PathBuf,
+}
+
+/// The number and the total size of the files removed by `clear`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Removed {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+impl RenderCache {
+    /// Returns the cache entry for the given key in the given cache directory.
+    pub fn new(cache_dir: &path::Path, key: &str) -> anyhow::Result<Self> {
+        let dir = cache_dir.join(RENDER_DIR);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Could not create the cache directory {}", dir.display()))?;
+        Ok(Self::with_dir(&dir, key))
+    }
+
+    fn with_dir(dir: &path::Path, key: &str) -> Self {
+        // The hash is not stable across Rust versions, but this only leads to cache misses.
+        let mut hasher = hash_map::DefaultHasher::new();
+        key.hash(&mut hasher);
+        RenderCache {
+            path: dir.join(format!("{:016x}", hasher.finish())),
+        }
+    }
+
+    /// Returns the cached output if there is a valid cache entry.
+    pub fn load(&self) -> Option<String> {
+        match self.read() {
+            Ok(Some(output)) => {
+                log::info!("Render cache hit for '{}'", self.path.display());
+                Some(output)
+            }
+            Ok(None) => {
+                log::info!("Render cache miss for '{}'", self.path.display());
+                None
+            }
+            Err(err) => {
+                log::warn!(
+                    "Could not read render cache entry '{}': {}",
+                    self.path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    fn read(&self) -> anyhow::Result<Option<String>> {
+        if !self.path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path)?;
+        let mut parts = content.splitn(3, '\n');
+        let (doc_path, mtime, output) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(doc_path), Some(mtime), Some(output)) => (doc_path, mtime, output),
+            _ => anyhow::bail!("Invalid cache entry"),
+        };
+        let doc_path = path::Path::new(doc_path);
+        if doc_path.is_file() && get_mtime(doc_path)? == mtime {
+            Ok(Some(output.to_owned()))
+        } else {
+            log::info!("Render cache entry '{}' is outdated", self.path.display());
+            Ok(None)
+        }
+    }
+
+    /// Stores the output rendered for the documentation read from the given HTML file.
+    pub fn store(&self, doc_path: &path::Path, output: &str) -> anyhow::Result<()> {
+        let doc_path = doc_path
+            .to_str()
+            .context("The documentation path is not valid UTF-8")?;
+        let mtime = get_mtime(doc_path.as_ref())?;
+        fs::write(&self.path, format!("{}\n{}\n{}", doc_path, mtime, output))?;
+        log::info!("Stored render cache entry '{}'", self.path.display());
+        Ok(())
+    }
+}
+
+impl fmt::Display for Removed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: &[&str] = &["KiB", "MiB", "GiB"];
+
+        write!(f, "{} file", self.files)?;
+        if self.files != 1 {
+            write!(f, "s")?;
+        }
+        if self.bytes < 1024 {
+            return write!(f, " ({} bytes)", self.bytes);
+        }
+        let mut size = self.bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit + 1 < UNITS.len() {
+            size /= 1024.0;
+            unit += 1;
+        }
+        write!(f, " ({:.1} {})", size, UNITS[unit])
+    }
+}
+
+/// Returns the user cache directory of rusty-man: the given directory if it is set, see the
+/// `--cache-dir` option, or `$XDG_CACHE_HOME/rusty-man`.
+pub fn get_cache_dir(dir: Option<&str>) -> anyhow::Result<path::PathBuf> {
+    if let Some(dir) = dir {
+        Ok(dir.into())
+    } else {
+        let dirs = xdg::BaseDirectories::with_prefix("rusty-man")?;
+        Ok(dirs.get_cache_home())
+    }
+}
+
+/// Removes all caches from the given cache directory, see the `--clear-cache` option.  The cache
+/// directory itself is only removed if it is empty afterwards.
+pub fn clear(cache_dir: &path::Path) -> anyhow::Result<Removed> {
+    let mut removed = Removed::default();
+    for name in CACHE_DIRS {
+        let dir = cache_dir.join(name);
+        if !dir.is_dir() {
+            continue;
+        }
+        count_files(&dir, &mut removed)
+            .with_context(|| format!("Could not read {}", dir.display()))?;
+        log::info!("Removing cache directory '{}'", dir.display());
+        fs::remove_dir_all(&dir).with_context(|| format!("Could not remove {}", dir.display()))?;
+    }
+    // This fails if the directory is not empty or does not exist, which is fine.
+    let _ = fs::remove_dir(cache_dir);
+    Ok(removed)
+}
+
+fn count_files(dir: &path::Path, removed: &mut Removed) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            count_files(&entry.path(), removed)?;
+        } else {
+            removed.files += 1;
+            removed.bytes += metadata.len();
+        }
+    }
+    Ok(())
+}
+
+fn get_mtime(path: &path::Path) -> anyhow::Result<String> {
+    let mtime = fs::metadata(path)?
+        .modified()?
+        .duration_since(time::UNIX_EPOCH)?;
+    Ok(format!("{}.{:09}", mtime.as_secs(), mtime.subsec_nanos()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{clear, Removed, RenderCache};
+
+    #[test]
+    fn test_render_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let doc_path = dir.path().join("struct.Test.html");
+        fs::write(&doc_path, "<html></html>").unwrap();
+
+        let cache = RenderCache::with_dir(dir.path(), "test::Test");
+        assert_eq!(None, cache.load());
+        cache.store(&doc_path, "output\nwith lines\n").unwrap();
+        assert_eq!(Some("output\nwith lines\n".to_owned()), cache.load());
+
+        let other = RenderCache::with_dir(dir.path(), "test::Other");
+        assert_eq!(None, other.load());
+
+        fs::remove_file(&doc_path).unwrap();
+        assert_eq!(None, cache.load());
+    }
+
+    #[test]
+    fn test_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("rusty-man");
+        let write = |path: &str, len: usize| {
+            let path = cache_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x".repeat(len)).unwrap();
+        };
+        write("render/0123456789abcdef", 1000);
+        write("doc.rust-lang.org/stable/std/index.html", 2000);
+        write("doc.rust-lang.org/stable/search-index.js", 48);
+        write("unrelated.txt", 10);
+
+        assert_eq!(
+            Removed {
+                files: 3,
+                bytes: 3048
+            },
+            clear(&cache_dir).unwrap()
+        );
+        assert!(!cache_dir.join("render").exists());
+        assert!(!cache_dir.join("doc.rust-lang.org").exists());
+        assert!(cache_dir.join("unrelated.txt").exists());
+
+        fs::remove_file(cache_dir.join("unrelated.txt")).unwrap();
+        assert_eq!(Removed::default(), clear(&cache_dir).unwrap());
+        assert!(!cache_dir.exists());
+        assert_eq!(Removed::default(), clear(&cache_dir).unwrap());
+    }
+
+    #[test]
+    fn test_removed_display() {
+        let removed = |files, bytes| Removed { files, bytes }.to_string();
+        assert_eq!("0 files (0 bytes)", removed(0, 0));
+        assert_eq!("1 file (12 bytes)", removed(1, 12));
+        assert_eq!("3 files (3.0 KiB)", removed(3, 3048));
+        assert_eq!("120 files (5.0 MiB)", removed(120, 5 * 1024 * 1024));
+    }
+}
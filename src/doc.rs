@@ -4,11 +4,16 @@
 use std::collections;
 use std::convert;
 use std::fmt;
+use std::iter;
 use std::ops;
 use std::str;
 
+use crate::link;
 use crate::parser::html;
 
+/// The maximum number of items in the “See also” section, see `Doc::find_see_also`.
+const SEE_ALSO_LIMIT: usize = 15;
+
 #[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Name {
     // s[..first_end] == first
@@ -30,7 +35,7 @@ pub struct Text {
 #[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Code(String);
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ItemType {
     // module members
     ExternCrate,
@@ -69,6 +74,29 @@ pub enum ItemType {
     TraitAlias,
 }
 
+/// The visibility of an item, as documented with `--document-private-items`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Visibility {
+    /// `pub`
+    Public,
+    /// Visible in the crate or in a part of it:  `pub(crate)`, `pub(super)` or `pub(in path)`.
+    Crate,
+    /// No visibility qualifier or `pub(self)`.
+    Private,
+}
+
+/// The deprecation notice of an item, i. e. the arguments of its `#[deprecated]` attribute.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeprecatedInfo {
+    /// The version since which the item is deprecated, e. g. `1.42.0`.
+    pub since: Option<String>,
+    /// The note that explains the deprecation, e. g. `use X instead`.
+    pub note: Option<String>,
+    /// The item that should be used instead, if it is mentioned in the note, see
+    /// `DeprecatedInfo::find_replacement`.
+    pub replacement: Option<String>,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct ConvertError;
 
@@ -85,7 +113,7 @@ impl core::convert::TryFrom<char> for ItemType {
 
     fn try_from(value: char) -> Result<Self, Self::Error> {
         use core::convert::TryInto;
-        Ok((value as u8 - 'A' as u8).try_into()?)
+        (value as u8 - b'A').try_into()
     }
 }
 
@@ -130,13 +158,32 @@ pub struct Doc {
     pub ty: ItemType,
     pub description: Option<Text>,
     pub definition: Option<Code>,
+    pub notable_traits: Option<Code>,
+    /// The platforms the item is available on, e. g. `Unix`, if it is restricted.
+    pub platform: Option<String>,
+    /// The crate features that have to be enabled to use the item.
+    pub required_features: Vec<String>,
+    /// The deprecation notice of the item.
+    pub deprecated: Option<DeprecatedInfo>,
+    /// The visibility of the item, if it is known.  Items without a visibility are treated as
+    /// public.
+    pub visibility: Option<Visibility>,
+    /// The attributes of the declaration of a struct, enum or union, e. g. `#[repr(C)]`.
+    pub attributes: Vec<String>,
+    /// Whether the item is marked with `#[doc(hidden)]`.  Such items are only included in the
+    /// documentation if rustdoc is called with `--document-hidden-items`.
+    pub hidden: bool,
     pub groups: collections::BTreeMap<ItemType, Vec<MemberGroup>>,
+    pub siblings: Vec<Doc>,
     pub url: Option<String>,
+    pub crate_version: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct MemberGroup {
     pub title: Option<String>,
+    /// The description of the group, for example the doc comment of an impl block.
+    pub description: Option<Text>,
     pub members: Vec<Doc>,
 }
 
@@ -144,6 +191,10 @@ pub struct MemberGroup {
 pub struct Example {
     pub description: Option<Text>,
     pub code: Code,
+    /// The full code of the example including the lines that are hidden by rustdoc, if available.
+    pub full_code: Option<Code>,
+    /// The doctest attributes that rustdoc displays for the example, e. g. `should_panic`.
+    pub tags: Vec<String>,
 }
 
 impl Name {
@@ -179,15 +230,17 @@ impl Name {
         if self.is_singleton() {
             None
         } else {
-            Some((&self.s[..self.last_start - 2]).to_owned().into())
+            Some(self.s[..self.last_start - 2].to_owned().into())
         }
     }
 
     pub fn child(&self, s: &str) -> Self {
-        let mut name = self.s.clone();
-        name.push_str("::");
-        name.push_str(s);
-        name.into()
+        self.components().chain(iter::once(s)).collect()
+    }
+
+    /// Returns the path components of this name, i. e. the parts separated by `::`.
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.s.split("::")
     }
 
     pub fn ends_with(&self, name: &Name) -> bool {
@@ -203,7 +256,7 @@ impl AsRef<str> for Name {
 
 impl From<String> for Name {
     fn from(s: String) -> Self {
-        let first_end = s.find("::").unwrap_or_else(|| s.len());
+        let first_end = s.find("::").unwrap_or(s.len());
         let last_start = s.rfind("::").map(|i| i + 2).unwrap_or(0);
         Self {
             s,
@@ -213,6 +266,20 @@ impl From<String> for Name {
     }
 }
 
+impl<S: AsRef<str>> iter::FromIterator<S> for Name {
+    /// Joins the given path components with `::`.
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut s = String::new();
+        for (i, component) in iter.into_iter().enumerate() {
+            if i > 0 {
+                s.push_str("::");
+            }
+            s.push_str(component.as_ref());
+        }
+        s.into()
+    }
+}
+
 impl From<Name> for String {
     fn from(n: Name) -> Self {
         n.s
@@ -265,6 +332,12 @@ impl From<String> for Fqn {
     }
 }
 
+impl<S: AsRef<str>> iter::FromIterator<S> for Fqn {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
 impl fmt::Display for Fqn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", &self.0)
@@ -283,6 +356,178 @@ impl Code {
     pub fn new(s: String) -> Code {
         Code(s)
     }
+
+    /// Creates a code block and removes the leading whitespace that all non-blank lines have in
+    /// common, like Python’s `textwrap.dedent`.  Tabs and spaces are not considered equal, so
+    /// `"\t"` and `"  "` do not have a common prefix.  Lines that only contain whitespace are
+    /// replaced with empty lines.
+    pub fn dedented(s: String) -> Code {
+        let indent = s
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| &line[..line.len() - line.trim_start_matches(&[' ', '\t'][..]).len()])
+            .reduce(|prefix, indent| {
+                let n = prefix
+                    .bytes()
+                    .zip(indent.bytes())
+                    .take_while(|(b1, b2)| b1 == b2)
+                    .count();
+                &prefix[..n]
+            })
+            .unwrap_or_default();
+        let lines: Vec<_> = s
+            .split('\n')
+            .map(|line| {
+                if line.trim().is_empty() {
+                    ""
+                } else {
+                    line.strip_prefix(indent).unwrap_or(line)
+                }
+            })
+            .collect();
+        Code(lines.join("\n"))
+    }
+
+    /// Returns a copy of this code block without comment-only lines and with consecutive blank
+    /// lines collapsed into one, see the `--compact-examples` option.
+    pub fn compacted(&self) -> Code {
+        let mut lines: Vec<&str> = Vec::new();
+        for line in self.0.lines() {
+            if line.trim_start().starts_with("//") {
+                continue;
+            }
+            let is_blank = line.trim().is_empty();
+            if is_blank && lines.last().map(|l| l.trim().is_empty()).unwrap_or(true) {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines.last().map(|l| l.trim().is_empty()).unwrap_or(false) {
+            lines.pop();
+        }
+        Code(lines.join("\n"))
+    }
+
+    /// Returns a copy of this definition with the where clauses and long parameter lists broken
+    /// into multiple lines, see the `--no-format-signatures` option.
+    ///
+    /// A `where` that does not start a line is moved to its own line, and each bound is put on a
+    /// separate line.  If a line of a function definition is wider than `width`, its parameters are
+    /// put on separate lines.  Definitions that are already formatted by rustdoc are not changed.
+    pub fn formatted(&self, width: Option<usize>) -> Code {
+        let mut lines = Vec::new();
+        for line in self.0.split('\n') {
+            let content = line.trim_start();
+            let indent = &line[..line.len() - content.len()];
+            let (head, bounds) = split_where_clause(content);
+            break_params(&mut lines, indent, head, width);
+            if let Some(bounds) = bounds {
+                push_where_clause(&mut lines, indent, bounds);
+            }
+        }
+        Code(lines.join("\n"))
+    }
+
+    /// Whether this is the definition of a function that takes a `self` receiver, e. g.
+    /// `fn push(&mut self, value: T)`.
+    pub fn has_self_receiver(&self) -> bool {
+        let params = if let Some(params) = self.find_params() {
+            params
+        } else {
+            return false;
+        };
+        let mut depth = 0;
+        let end = params
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '(' | '<' | '[' => depth += 1,
+                    ')' | '>' | ']' => depth -= 1,
+                    _ => {}
+                }
+                depth < 0 || (depth == 0 && *c == ',')
+            })
+            .map(|(idx, _)| idx)
+            .unwrap_or_else(|| params.len());
+
+        let receiver = params[..end].trim();
+        let receiver = receiver
+            .strip_prefix("mut ")
+            .unwrap_or(receiver)
+            .trim_start();
+        let receiver = if let Some(reference) = receiver.strip_prefix('&') {
+            let reference = reference.trim_start();
+            let reference = if reference.starts_with('\'') {
+                reference
+                    .find(char::is_whitespace)
+                    .map(|idx| &reference[idx..])
+                    .unwrap_or_default()
+            } else {
+                reference
+            };
+            let reference = reference.trim_start();
+            reference
+                .strip_prefix("mut ")
+                .unwrap_or(reference)
+                .trim_start()
+        } else {
+            receiver
+        };
+        receiver == "self" || receiver.starts_with("self:") || receiver.starts_with("self :")
+    }
+
+    /// Returns the visibility qualifier of this definition.  Attributes before the definition,
+    /// e. g. `#[non_exhaustive]`, are skipped.
+    pub fn visibility(&self) -> Visibility {
+        let s = self
+            .0
+            .lines()
+            .map(str::trim_start)
+            .find(|line| !line.is_empty() && !line.starts_with("#["))
+            .unwrap_or_default();
+        if let Some(restriction) = s.strip_prefix("pub(") {
+            if restriction.starts_with("self)") {
+                Visibility::Private
+            } else {
+                Visibility::Crate
+            }
+        } else if s.starts_with("pub ") {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        }
+    }
+
+    /// Returns the attributes before this definition, e. g. `#[repr(C)]`.
+    pub fn attributes(&self) -> Vec<String> {
+        self.0
+            .lines()
+            .map(str::trim)
+            .take_while(|line| line.starts_with("#["))
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+
+    /// Returns the parameter list of the function definition, starting after the opening
+    /// parenthesis.  Generic parameters before the parameter list are skipped.
+    fn find_params(&self) -> Option<&str> {
+        let start = self.0.find("fn ")? + "fn ".len();
+        let mut depth = 0;
+        let mut chars = self.0[start..].char_indices().peekable();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                // skip the arrows of return types in generic bounds like Fn() -> T
+                '-' if chars.peek().map(|(_, c)| *c) == Some('>') => {
+                    chars.next();
+                }
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                '(' if depth == 0 => return Some(&self.0[start + idx + 1..]),
+                _ => {}
+            }
+        }
+        None
+    }
 }
 
 impl fmt::Display for Code {
@@ -291,6 +536,140 @@ impl fmt::Display for Code {
     }
 }
 
+/// Returns the byte indices of the characters in the given code that match the predicate and are
+/// not enclosed in brackets.
+fn find_top_level(s: &str, pred: impl Fn(char) -> bool) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut depth = 0usize;
+    let mut prev = None;
+    for (idx, c) in s.char_indices() {
+        if depth == 0 && pred(c) {
+            indices.push(idx);
+        }
+        match c {
+            '(' | '<' | '[' | '{' => depth += 1,
+            // skip the arrows of return types like Fn() -> T
+            '>' if prev == Some('-') || prev == Some('=') => {}
+            ')' | '>' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    indices
+}
+
+/// Splits the given code at the top-level commas and trims the parts.  Empty parts, e. g. after a
+/// trailing comma, are skipped.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for idx in find_top_level(s, |c| c == ',')
+        .into_iter()
+        .chain(Some(s.len()))
+    {
+        let part = s[start..idx].trim();
+        if !part.is_empty() {
+            parts.push(part);
+        }
+        start = idx + 1;
+    }
+    parts
+}
+
+/// Splits the given line at a top-level `where` keyword that does not start the line.  Returns
+/// the part before the keyword and the bounds after it, if any.
+fn split_where_clause(line: &str) -> (&str, Option<&str>) {
+    for idx in find_top_level(line, |c| c == 'w') {
+        let rest = &line[idx..];
+        let is_keyword = rest.starts_with("where")
+            && rest["where".len()..]
+                .chars()
+                .next()
+                .map(char::is_whitespace)
+                .unwrap_or(true);
+        let head = line[..idx].trim_end();
+        if is_keyword && !head.is_empty() && head.len() < idx {
+            return (head, Some(&rest["where".len()..]));
+        }
+    }
+    (line, None)
+}
+
+/// Adds the given where clause to the lines:  `where` on its own line followed by the bounds with
+/// an additional indentation of four spaces.  A trailing `;` is kept after the last bound, and a
+/// block starting with an opening brace is moved to its own line.
+fn push_where_clause(lines: &mut Vec<String>, indent: &str, bounds: &str) {
+    let bounds = bounds.trim();
+    let (bounds, block) = match find_top_level(bounds, |c| c == '{').first() {
+        Some(&idx) => (&bounds[..idx], Some(&bounds[idx..])),
+        None => (bounds, None),
+    };
+    let (bounds, semicolon) = match bounds.trim_end().strip_suffix(';') {
+        Some(bounds) => (bounds, true),
+        None => (bounds, false),
+    };
+    lines.push(format!("{}where", indent));
+    let bounds = split_top_level(bounds);
+    for (i, bound) in bounds.iter().enumerate() {
+        let separator = if semicolon && i + 1 == bounds.len() {
+            ';'
+        } else {
+            ','
+        };
+        lines.push(format!("{}    {}{}", indent, bound, separator));
+    }
+    if let Some(block) = block {
+        lines.push(format!("{}{}", indent, block));
+    }
+}
+
+/// Returns the byte index of the parenthesis that closes the parenthesis at the given index.
+fn find_closing_paren(s: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, c) in s[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Adds the given line of a definition to the lines.  If it is a function definition that is
+/// wider than the given width, the parameters are put on separate lines with an additional
+/// indentation of four spaces.
+fn break_params(lines: &mut Vec<String>, indent: &str, line: &str, width: Option<usize>) {
+    use unicode_width::UnicodeWidthStr as _;
+
+    let is_long = width
+        .map(|width| indent.width() + line.width() > width)
+        .unwrap_or(false);
+    let open = find_top_level(line, |c| c == '(').into_iter().next();
+    let is_fn = open
+        .map(|open| line[..open].starts_with("fn ") || line[..open].contains(" fn "))
+        .unwrap_or(false);
+    if let (true, true, Some(open)) = (is_long, is_fn, open) {
+        if let Some(close) = find_closing_paren(line, open) {
+            let params = split_top_level(&line[open + 1..close]);
+            if !params.is_empty() {
+                lines.push(format!("{}{}", indent, &line[..=open]));
+                for param in params {
+                    lines.push(format!("{}    {},", indent, param));
+                }
+                lines.push(format!("{}{}", indent, &line[close..]));
+                return;
+            }
+        }
+    }
+    lines.push(format!("{}{}", indent, line));
+}
+
 impl ops::Deref for Code {
     type Target = String;
 
@@ -325,12 +704,45 @@ impl ItemType {
             ItemType::ForeignType => "Foreign Type",
             ItemType::Keyword => "Keyword",
             ItemType::OpaqueTy => "Opaque Type",
-            ItemType::ProcAttribute => "Proc Attribute",
-            ItemType::ProcDerive => "Proc Derive",
+            ItemType::ProcAttribute => "Attribute Macro",
+            ItemType::ProcDerive => "Derive Macro",
             ItemType::TraitAlias => "Trait Alias",
         }
     }
 
+    /// Returns the short name of the item type as used in the rustdoc file names, see the
+    /// `FromStr` implementation.
+    pub fn short_name(&self) -> &str {
+        match self {
+            ItemType::Module => "mod",
+            ItemType::ExternCrate => "externcrate",
+            ItemType::Import => "import",
+            ItemType::Struct => "struct",
+            ItemType::Enum => "enum",
+            ItemType::Function => "fn",
+            ItemType::Typedef => "type",
+            ItemType::Static => "static",
+            ItemType::Trait => "trait",
+            ItemType::Impl => "impl",
+            ItemType::TyMethod => "tymethod",
+            ItemType::Method => "method",
+            ItemType::StructField => "structfield",
+            ItemType::Variant => "variant",
+            ItemType::Macro => "macro",
+            ItemType::Primitive => "primitive",
+            ItemType::AssocType => "associatedtype",
+            ItemType::Constant => "constant",
+            ItemType::AssocConst => "associatedconstant",
+            ItemType::Union => "union",
+            ItemType::ForeignType => "foreigntype",
+            ItemType::Keyword => "keyword",
+            ItemType::OpaqueTy => "opaque",
+            ItemType::ProcAttribute => "attr",
+            ItemType::ProcDerive => "derive",
+            ItemType::TraitAlias => "traitalias",
+        }
+    }
+
     pub fn group_name(&self) -> &str {
         match self {
             ItemType::Module => "Modules",
@@ -356,8 +768,8 @@ impl ItemType {
             ItemType::ForeignType => "Foreign Types",
             ItemType::Keyword => "Keywords",
             ItemType::OpaqueTy => "Opaque Types",
-            ItemType::ProcAttribute => "Proc Attributes",
-            ItemType::ProcDerive => "Proc Derives",
+            ItemType::ProcAttribute => "Attribute Macros",
+            ItemType::ProcDerive => "Derive Macros",
             ItemType::TraitAlias => "Trait Aliases",
         }
     }
@@ -406,8 +818,17 @@ impl Doc {
             ty,
             description: Default::default(),
             definition: Default::default(),
+            notable_traits: Default::default(),
+            platform: Default::default(),
+            required_features: Default::default(),
+            deprecated: None,
+            visibility: None,
+            attributes: Vec::new(),
+            hidden: false,
             groups: Default::default(),
+            siblings: Default::default(),
             url: None,
+            crate_version: None,
         }
     }
 
@@ -419,6 +840,47 @@ impl Doc {
         }
     }
 
+    /// Returns the first sentence of the description of this item, if any.
+    pub fn find_first_sentence(&self) -> anyhow::Result<Option<String>> {
+        if let Some(description) = &self.description {
+            html::Parser::from_string(&description.html)?.find_first_sentence()
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the items that are linked from the description of this item, in the order of
+    /// their first link and limited to the first few items.
+    pub fn find_see_also(&self) -> anyhow::Result<Vec<Doc>> {
+        let links = if let Some(description) = &self.description {
+            html::Parser::from_string(&description.html)?.find_links()?
+        } else {
+            Vec::new()
+        };
+
+        let mut items: Vec<Doc> = Vec::new();
+        for href in links {
+            if items.len() >= SEE_ALSO_LIMIT {
+                break;
+            }
+            // We only want links to other documentation pages, not to external sites or to the
+            // source code.
+            if href.contains(':') || href.contains("src/") {
+                continue;
+            }
+            match link::resolve_doc_link(&self.name, self.ty, &href) {
+                Ok((Some(ty), name)) => {
+                    if name != self.name && !items.iter().any(|d| d.name == name) {
+                        items.push(Doc::new(name, ty));
+                    }
+                }
+                Ok((None, _)) => {}
+                Err(err) => log::info!("Ignoring link '{}': {}", href, err),
+            }
+        }
+        Ok(items)
+    }
+
     pub fn set_url(&mut self, path: &std::path::Path, hash: Option<String>) {
         let mut path = path
             .canonicalize()
@@ -427,7 +889,7 @@ impl Doc {
             .to_string_lossy()
             .to_string();
         if path.starts_with("/") {
-            path = (&path[1..]).to_string();
+            path = path[1..].to_string();
         }
 
         if let Some(hash) = hash {
@@ -438,6 +900,249 @@ impl Doc {
     }
 }
 
+impl Doc {
+    /// Writes a summary of the parsed documentation item to `f`, including the lengths of the
+    /// texts and the tree of member groups with the populated fields of all members.  This is
+    /// intended for debugging the parser and for bug reports.
+    pub fn dump(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        let length = |n: Option<usize>| match n {
+            Some(n) => format!("yes ({} characters)", n),
+            None => "no".to_owned(),
+        };
+        writeln!(f, "name: {}", &self.name)?;
+        writeln!(f, "type: {}", self.ty.name())?;
+        writeln!(
+            f,
+            "crate version: {}",
+            self.crate_version.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            f,
+            "description: {}",
+            length(self.description.as_ref().map(|d| d.plain.chars().count()))
+        )?;
+        writeln!(
+            f,
+            "definition: {}",
+            length(self.definition.as_ref().map(|d| d.chars().count()))
+        )?;
+        writeln!(
+            f,
+            "notable traits: {}",
+            length(self.notable_traits.as_ref().map(|n| n.chars().count()))
+        )?;
+        writeln!(f, "platform: {}", self.platform.as_deref().unwrap_or("any"))?;
+        if !self.attributes.is_empty() {
+            writeln!(f, "attributes: {}", self.attributes.join(" "))?;
+        }
+        if self.required_features.is_empty() {
+            writeln!(f, "required features: none")?;
+        } else {
+            writeln!(
+                f,
+                "required features: {}",
+                self.required_features.join(", ")
+            )?;
+        }
+        writeln!(f, "groups: {}", self.groups.len())?;
+        for (ty, groups) in &self.groups {
+            let members: usize = groups.iter().map(|g| g.members.len()).sum();
+            writeln!(
+                f,
+                "  {}: {} group(s), {} member(s)",
+                ty.group_name(),
+                groups.len(),
+                members
+            )?;
+            for group in groups {
+                writeln!(
+                    f,
+                    "    {}: {} member(s)",
+                    group.title.as_deref().unwrap_or("(untitled)"),
+                    group.members.len()
+                )?;
+                for member in &group.members {
+                    writeln!(
+                        f,
+                        "      - {} ({}) [{}]",
+                        &member.name,
+                        member.ty.name(),
+                        member.dump_fields()
+                    )?;
+                }
+            }
+        }
+        writeln!(f, "siblings: {}", self.siblings.len())?;
+        for sibling in &self.siblings {
+            writeln!(f, "  - {} ({})", &sibling.name, sibling.ty.name())?;
+        }
+        Ok(())
+    }
+
+    /// Whether this item is an associated function, i. e. a method without a `self` receiver like
+    /// `Vec::new`.  Rustdoc lists both as methods.
+    pub fn is_assoc_fn(&self) -> bool {
+        match self.ty {
+            ItemType::Method | ItemType::TyMethod => !self
+                .definition
+                .as_ref()
+                .map(Code::has_self_receiver)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Whether this item is public or its visibility is unknown.
+    pub fn is_public(&self) -> bool {
+        self.visibility
+            .map(|visibility| visibility == Visibility::Public)
+            .unwrap_or(true)
+    }
+
+    /// Removes the members and siblings of this item that are not public.  Member groups that
+    /// become empty are removed too.
+    pub fn retain_public_members(&mut self) {
+        for groups in self.groups.values_mut() {
+            for group in groups.iter_mut() {
+                group.members.retain(Doc::is_public);
+            }
+            groups.retain(|group| !group.members.is_empty());
+        }
+        self.groups.retain(|_, groups| !groups.is_empty());
+        self.siblings.retain(Doc::is_public);
+    }
+
+    /// Sorts the members of this item alphabetically by their name within their groups, see the
+    /// `--sort` option.  The order of the groups is not changed.
+    pub fn sort_members(&mut self) {
+        for groups in self.groups.values_mut() {
+            for group in groups.iter_mut() {
+                group.sort_members();
+            }
+        }
+    }
+
+    /// Returns the populated fields of this item and the lengths of the texts in characters.
+    fn dump_fields(&self) -> String {
+        let mut fields = Vec::new();
+        if let Some(description) = &self.description {
+            fields.push(format!(
+                "description ({})",
+                description.plain.chars().count()
+            ));
+        }
+        if let Some(definition) = &self.definition {
+            fields.push(format!("definition ({})", definition.chars().count()));
+        }
+        if let Some(notable_traits) = &self.notable_traits {
+            fields.push(format!(
+                "notable traits ({})",
+                notable_traits.chars().count()
+            ));
+        }
+        if let Some(deprecated) = &self.deprecated {
+            fields.push(format!("deprecated ({})", deprecated));
+        }
+        if let Some(platform) = &self.platform {
+            fields.push(format!("platform ({})", platform));
+        }
+        if let Some(visibility) = &self.visibility {
+            fields.push(format!("visibility ({})", visibility));
+        }
+        if self.hidden {
+            fields.push("hidden".to_owned());
+        }
+        if !self.required_features.is_empty() {
+            fields.push(format!(
+                "required features ({})",
+                self.required_features.join(", ")
+            ));
+        }
+        if fields.is_empty() {
+            "none".to_owned()
+        } else {
+            fields.join(", ")
+        }
+    }
+}
+
+impl DeprecatedInfo {
+    /// The words that precede the replacement of a deprecated item in the deprecation note.
+    const REPLACEMENT_PREFIXES: &'static [&'static str] =
+        &["use ", "replaced by ", "superseded by "];
+
+    /// Returns the item that should be used instead of the deprecated item, if the deprecation
+    /// note follows a common pattern like “use `X` instead” or “replaced by X”.  Inline code in
+    /// the note must be enclosed in backticks.  Without backticks, the replacement must be a path
+    /// or be followed by “instead”, so that notes like “use with care” are ignored.
+    pub fn find_replacement(note: &str) -> Option<String> {
+        let lower = note.to_ascii_lowercase();
+        for prefix in Self::REPLACEMENT_PREFIXES {
+            for (i, _) in lower.match_indices(prefix) {
+                let is_word_start = lower[..i]
+                    .chars()
+                    .next_back()
+                    .map(|c| !c.is_alphanumeric())
+                    .unwrap_or(true);
+                if !is_word_start {
+                    continue;
+                }
+                let rest = &note[i + prefix.len()..];
+                let replacement = if let Some(code) = rest.strip_prefix('`') {
+                    code.find('`').map(|end| &code[..end])
+                } else {
+                    let word = rest
+                        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                        .next()
+                        .unwrap_or_default()
+                        .trim_end_matches('.');
+                    let is_instead = rest[word.len()..].trim_start().starts_with("instead");
+                    Some(word).filter(|word| word.contains("::") || is_instead)
+                };
+                let replacement = replacement
+                    .map(|s| s.trim_end_matches("()"))
+                    .filter(|s| is_path(s));
+                if let Some(replacement) = replacement {
+                    return Some(replacement.to_owned());
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Whether the given string is a path to an item, e. g. `std::error::Error::source`.
+fn is_path(s: &str) -> bool {
+    s.split("::").all(|segment| {
+        segment.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+impl fmt::Display for DeprecatedInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Deprecated")?;
+        if let Some(since) = &self.since {
+            write!(f, " since {}", since)?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, ": {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Visibility::Public => "pub",
+            Visibility::Crate => "crate",
+            Visibility::Private => "private",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 impl fmt::Display for Doc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(description) = &self.description {
@@ -452,20 +1157,50 @@ impl MemberGroup {
     pub fn new(title: Option<String>) -> Self {
         MemberGroup {
             title,
+            description: None,
             members: Vec::new(),
         }
     }
+
+    /// Whether a member of this group has a definition or a description.  Implementations always
+    /// have a definition, so they only count as documented if they have a description or items.
+    pub fn has_documented_members(&self) -> bool {
+        self.members.iter().any(|member| {
+            if member.ty == ItemType::Impl {
+                member.description.is_some()
+                    || member
+                        .groups
+                        .values()
+                        .flatten()
+                        .any(|g| !g.members.is_empty())
+            } else {
+                member.description.is_some() || member.definition.is_some()
+            }
+        })
+    }
+
+    /// Sorts the members of this group alphabetically, ignoring the case.  The sort is stable so
+    /// that members with the same name, e. g. implementations of the same trait, keep their order.
+    fn sort_members(&mut self) {
+        self.members
+            .sort_by_cached_key(|member| member.name.last().to_lowercase());
+    }
 }
 
 impl Example {
     pub fn new(description: Option<Text>, code: Code) -> Self {
-        Example { description, code }
+        Example {
+            description,
+            code,
+            full_code: None,
+            tags: Vec::new(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Name;
+    use super::{Code, DeprecatedInfo, Doc, ItemType, MemberGroup, Name, Text, Visibility};
 
     fn assert_name(input: &str, first: &str, last: &str, rest: &str) {
         let name: Name = input.to_owned().into();
@@ -516,4 +1251,305 @@ mod tests {
     fn test_colon() {
         assert_name("er:ror::Error", "er:ror", "Error", "Error");
     }
+
+    #[test]
+    fn test_components() {
+        let name: Name = "rand::error::Error".to_owned().into();
+        assert_eq!(
+            vec!["rand", "error", "Error"],
+            name.components().collect::<Vec<_>>()
+        );
+        assert_eq!(name, name.components().collect());
+        assert_eq!(
+            name,
+            vec!["rand".to_owned(), "error::Error".to_owned()]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(Name::default(), std::iter::empty::<&str>().collect());
+        assert_name(
+            name.child("source").full(),
+            "rand",
+            "source",
+            "error::Error::source",
+        );
+    }
+
+    #[test]
+    fn test_find_see_also() {
+        let mut doc = Doc::new("kuchiki::NodeRef".to_owned().into(), ItemType::Struct);
+        doc.description = Some(Text {
+            plain: String::new(),
+            html: r##"<p>See <a href="struct.NodeDataRef.html">NodeDataRef</a>,
+                <a href="struct.NodeDataRef.html#method.as_node">as_node</a>,
+                <a href="iter/index.html">iter</a>,
+                <a href="#method.select">select</a>,
+                <a href="../src/kuchiki/tree.rs.html#1">source</a> and
+                <a href="https://example.com">example</a>.</p>"##
+                .to_owned(),
+        });
+        let items: Vec<_> = doc
+            .find_see_also()
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.name.to_string(), d.ty))
+            .collect();
+        assert_eq!(
+            vec![
+                ("kuchiki::NodeDataRef".to_owned(), ItemType::Struct),
+                ("kuchiki::NodeDataRef::as_node".to_owned(), ItemType::Method),
+                ("kuchiki::iter".to_owned(), ItemType::Module),
+                ("kuchiki::NodeRef::select".to_owned(), ItemType::Method),
+            ],
+            items
+        );
+    }
+
+    #[test]
+    fn test_dedented() {
+        let assert_dedented = |expected: &str, s: &str| {
+            assert_eq!(expected, Code::dedented(s.to_owned()).as_str(), "{:?}", s);
+        };
+        assert_dedented("", "");
+        assert_dedented("fn foo() {}", "fn foo() {}");
+        assert_dedented("fn foo() {}", "    fn foo() {}");
+        assert_dedented(
+            "fn foo() {\n    bar();\n}",
+            "    fn foo() {\n        bar();\n    }",
+        );
+        assert_dedented("a\n\nb\n", "  a\n   \n  b\n");
+        assert_dedented("a\n  b", "\ta\n\t  b");
+        assert_dedented("\ta\n  b", "\ta\n  b");
+        assert_dedented(" a\nb", "\t a\n\tb");
+        assert_dedented("a\n b", "  \ta\n  \t b");
+    }
+
+    #[test]
+    fn test_visibility() {
+        let assert_visibility = |visibility, s: &str| {
+            assert_eq!(visibility, Code::new(s.to_owned()).visibility(), "{}", s);
+        };
+        assert_visibility(Visibility::Public, "pub struct Foo;");
+        assert_visibility(Visibility::Public, "#[non_exhaustive]\npub enum Foo {}");
+        assert_visibility(Visibility::Crate, "pub(crate) fn foo()");
+        assert_visibility(Visibility::Crate, "pub(in crate::foo) fn foo()");
+        assert_visibility(Visibility::Private, "pub(self) fn foo()");
+        assert_visibility(Visibility::Private, "fn foo()");
+        assert_visibility(Visibility::Private, "const FOO: u8");
+    }
+
+    #[test]
+    fn test_find_replacement() {
+        let find = |s: &str| DeprecatedInfo::find_replacement(s);
+        assert_eq!(
+            Some("other::path".to_owned()),
+            find("use `other::path` instead")
+        );
+        assert_eq!(Some("bar".to_owned()), find("Use `bar()`"));
+        assert_eq!(Some("foo".to_owned()), find("use foo instead"));
+        assert_eq!(
+            Some("Error::source".to_owned()),
+            find("replaced by Error::source, which can support downcasting")
+        );
+        assert_eq!(Some("Foo::bar".to_owned()), find("misuse; use Foo::bar."));
+        assert_eq!(None, find("use the Display impl or to_string()"));
+        assert_eq!(None, find("use with care"));
+        assert_eq!(None, find("misuse `foo`"));
+        assert_eq!(None, find("use `a + b` instead"));
+    }
+
+    #[test]
+    fn test_deprecated_info_display() {
+        let info = |since: Option<&str>, note: Option<&str>| {
+            DeprecatedInfo {
+                since: since.map(ToOwned::to_owned),
+                note: note.map(ToOwned::to_owned),
+                replacement: None,
+            }
+            .to_string()
+        };
+        assert_eq!(
+            "Deprecated since 1.42.0: use X",
+            info(Some("1.42.0"), Some("use X"))
+        );
+        assert_eq!("Deprecated since 1.42.0", info(Some("1.42.0"), None));
+        assert_eq!("Deprecated: use X", info(None, Some("use X")));
+        assert_eq!("Deprecated", info(None, None));
+    }
+
+    #[test]
+    fn test_formatted() {
+        let assert_formatted = |expected: &str, s: &str, width: Option<usize>| {
+            assert_eq!(
+                expected,
+                Code::new(s.to_owned()).formatted(width).to_string(),
+                "{:?}",
+                s
+            );
+        };
+
+        // short definitions without where clauses are not changed
+        assert_formatted(
+            "pub fn len(&self) -> usize",
+            "pub fn len(&self) -> usize",
+            Some(40),
+        );
+        assert_formatted(
+            "pub struct Vec<T, A: Allocator = Global> { /* fields omitted */ }",
+            "pub struct Vec<T, A: Allocator = Global> { /* fields omitted */ }",
+            Some(20),
+        );
+
+        assert_formatted(
+            "fn try_for_each<F, R>(&mut self, f: F) -> R\nwhere\n    Self: Sized,\n    F: FnMut(Self::Item) -> R,\n    R: Try<Output = ()>,",
+            "fn try_for_each<F, R>(&mut self, f: F) -> R where Self: Sized, F: FnMut(Self::Item) -> R, R: Try<Output = ()>,",
+            None,
+        );
+        assert_formatted(
+            "pub fn retain_mut<F>(&mut self, f: F)\nwhere\n    F: FnMut(&mut T) -> bool,",
+            "pub fn retain_mut<F>(&mut self, f: F) where F: FnMut(&mut T) -> bool",
+            None,
+        );
+        assert_formatted(
+            "impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>\nwhere\n    K: Eq + Hash,\n    S: BuildHasher + Default,\n{",
+            "impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S> where K: Eq + Hash, S: BuildHasher + Default {",
+            None,
+        );
+        assert_formatted(
+            "pub trait Context<T, E> {\n    fn context<C>(self, context: C) -> Result<T, Error>\n    where\n        C: Display + Send + Sync + 'static;\n}",
+            "pub trait Context<T, E> {\n    fn context<C>(self, context: C) -> Result<T, Error> where C: Display + Send + Sync + 'static;\n}",
+            None,
+        );
+
+        assert_formatted(
+            "pub struct Cow<'a, B: ?Sized + 'a>\nwhere\n    B: ToOwned,\n{ /* fields omitted */ }",
+            "pub struct Cow<'a, B: ?Sized + 'a> where B: ToOwned, { /* fields omitted */ }",
+            None,
+        );
+
+        // long parameter lists are broken after the commas
+        assert_formatted(
+            "pub fn copy_within<R>(\n    &mut self,\n    src: R,\n    dest: usize,\n)\nwhere\n    R: RangeBounds<usize>,\n    T: Copy,",
+            "pub fn copy_within<R>(&mut self, src: R, dest: usize) where R: RangeBounds<usize>, T: Copy",
+            Some(30),
+        );
+        assert_formatted(
+            "pub unsafe fn from_raw_parts_in(\n    ptr: *mut T,\n    length: usize,\n    capacity: usize,\n    alloc: A,\n) -> Vec<T, A>",
+            "pub unsafe fn from_raw_parts_in(ptr: *mut T, length: usize, capacity: usize, alloc: A,) -> Vec<T, A>",
+            Some(60),
+        );
+        assert_formatted(
+            "pub fn map<U, F: FnOnce(T) -> U>(\n    self,\n    f: F,\n) -> Option<U>",
+            "pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Option<U>",
+            Some(40),
+        );
+
+        // definitions that are already formatted are not changed
+        let formatted = "pub fn new_comment<T: Into<String>>(\n    value: T\n) -> NodeRef\nwhere\n    T: Into<String>,";
+        assert_formatted(formatted, formatted, Some(20));
+    }
+
+    #[test]
+    fn test_compacted() {
+        let assert_compacted = |expected: &str, s: &str| {
+            assert_eq!(
+                expected,
+                Code::new(s.to_owned()).compacted().to_string(),
+                "{:?}",
+                s
+            );
+        };
+        assert_compacted("let a = 1;", "let a = 1;");
+        assert_compacted("let a = 1;", "// A comment.\nlet a = 1;");
+        assert_compacted(
+            "let a = 1;\n\nlet b = 2;",
+            "let a = 1;\n\n\n  // b\n\nlet b = 2;\n\n",
+        );
+        assert_compacted(
+            "fn f() {\n    g(); // g\n}",
+            "fn f() {\n    /// f\n    g(); // g\n}",
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        let attributes = |s: &str| Code::new(s.to_owned()).attributes();
+        assert!(attributes("pub struct Foo;").is_empty());
+        assert_eq!(
+            vec!["#[repr(C)]"],
+            attributes("#[repr(C)]\npub struct Foo;")
+        );
+        assert_eq!(
+            vec!["#[repr(u8)]", "#[non_exhaustive]"],
+            attributes("#[repr(u8)]\n#[non_exhaustive]\npub enum Foo {}")
+        );
+    }
+
+    #[test]
+    fn test_is_assoc_fn() {
+        let method = |definition: &str| {
+            let mut doc = Doc::new("alloc::vec::Vec::f".to_owned().into(), ItemType::Method);
+            doc.definition = Some(Code::new(definition.to_owned()));
+            doc
+        };
+
+        assert!(method("pub const fn new() -> Vec<T, Global>").is_assoc_fn());
+        assert!(method("pub fn with_capacity(capacity: usize) -> Vec<T, Global>").is_assoc_fn());
+        assert!(
+            method("pub unsafe fn from_raw_parts(ptr: *mut T, length: usize, capacity: usize) -> Vec<T, Global>")
+                .is_assoc_fn()
+        );
+        assert!(!method("pub fn push(&mut self, value: T)").is_assoc_fn());
+        assert!(!method("pub fn len(&self) -> usize").is_assoc_fn());
+        assert!(!method("pub fn into_boxed_slice(self) -> Box<[T], A>").is_assoc_fn());
+        assert!(!method("pub fn leak<'a>(self) -> &'a mut [T]").is_assoc_fn());
+        assert!(!method("fn get<'a>(&'a mut self) -> &'a T").is_assoc_fn());
+        assert!(!method("pub fn pin(mut self: Box<Self>) -> Pin<Box<Self>>").is_assoc_fn());
+        assert!(!method(
+            "pub fn retain_mut<F>(&mut self, f: F)\nwhere\n    F: FnMut(&mut T) -> bool,"
+        )
+        .is_assoc_fn());
+        assert!(method("pub fn from_fn<F: Fn() -> T>(f: F) -> Self").is_assoc_fn());
+        assert!(!method("pub fn map<F: Fn(&T) -> U, U>(self, f: F) -> U").is_assoc_fn());
+
+        let mut function = method("pub fn new() -> Vec<T>");
+        function.ty = ItemType::Function;
+        assert!(!function.is_assoc_fn());
+    }
+
+    #[test]
+    fn test_dump() {
+        let mut doc = Doc::new("krate::Foo".to_owned().into(), ItemType::Struct);
+        doc.description = Some(Text {
+            plain: "A foo.".to_owned(),
+            html: "<p>A foo.</p>".to_owned(),
+        });
+        doc.definition = Some(Code::new("struct Foo;".to_owned()));
+
+        let mut method = Doc::new("krate::Foo::bar".to_owned().into(), ItemType::Method);
+        method.definition = Some(Code::new("fn bar()".to_owned()));
+        let mut group = MemberGroup::new(Some("impl Foo".to_owned()));
+        group.members.push(method);
+        doc.groups.insert(ItemType::Method, vec![group]);
+
+        let mut s = String::new();
+        doc.dump(&mut s).unwrap();
+        assert_eq!(
+            "name: krate::Foo
+type: Struct
+crate version: unknown
+description: yes (6 characters)
+definition: yes (11 characters)
+notable traits: no
+platform: any
+required features: none
+groups: 1
+  Methods: 1 group(s), 1 member(s)
+    impl Foo: 1 member(s)
+      - krate::Foo::bar (Method) [definition (8)]
+siblings: 0
+",
+            s
+        );
+    }
 }
@@ -0,0 +1,323 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! Opens the source code of an item in the user’s editor, see the `--edit` option.
+//!
+//! rustdoc adds a `[src]` link to the documentation of every item that points to a rendered copy
+//! of the source file, e. g. `../src/log/lib.rs.html#1185-1203`.  The path of this copy is the
+//! path of the source file relative to the directory of the crate root, so for crates that have
+//! been documented locally, we can find the original file in the workspace that contains the
+//! documentation directory.
+
+use std::env;
+use std::fs;
+use std::path;
+use std::process;
+
+use anyhow::Context as _;
+
+use crate::doc;
+use crate::parser;
+
+/// The location of an item in a source file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceLocation {
+    pub path: path::PathBuf,
+    pub line: Option<usize>,
+}
+
+/// A parsed `[src]` link.
+#[derive(Clone, Debug, PartialEq)]
+struct SourceLink {
+    /// The root directory of the documentation, i. e. the directory that contains the `src`
+    /// directory.
+    doc_root: path::PathBuf,
+    krate: String,
+    /// The path of the source file relative to the directory of the crate root.
+    path: path::PathBuf,
+    line: Option<usize>,
+}
+
+/// Opens the source file of the given item in `$EDITOR`.  The documentation file is not parsed if
+/// it is larger than `max_file_size` bytes.
+pub fn open_in_editor(doc: &doc::Doc, max_file_size: u64) -> anyhow::Result<()> {
+    let location = find_source_location(doc, max_file_size)?;
+    let editor = env::var("EDITOR")
+        .ok()
+        .context("Cannot open the source file: $EDITOR is not set")?;
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .context("The editor command must not be empty")?;
+    let mut command = process::Command::new(program);
+    command.args(parts);
+    if let Some(line) = location.line {
+        command.arg(format!("+{}", line));
+    }
+    command.arg(&location.path);
+    log::info!("Opening {} with {}", location.path.display(), editor);
+    let status = command
+        .status()
+        .with_context(|| format!("Could not start editor {}", editor))?;
+    anyhow::ensure!(status.success(), "The editor {} failed: {}", editor, status);
+    Ok(())
+}
+
+/// Returns the path and line of the source code of the given item if the documentation has been
+/// generated locally.
+pub fn find_source_location(doc: &doc::Doc, max_file_size: u64) -> anyhow::Result<SourceLocation> {
+    let url = doc
+        .url
+        .as_ref()
+        .and_then(|url| url::Url::parse(url).ok())
+        .with_context(|| format!("Cannot find the html document for {}", &doc.name))?;
+    let html_path = url
+        .to_file_path()
+        .ok()
+        .with_context(|| format!("The documentation for {} is not local", &doc.name))?;
+    let href = parser::html::Parser::from_file_with_limit(&html_path, max_file_size)?
+        .find_source_link(url.fragment())?
+        .with_context(|| format!("The documentation for {} has no source link", &doc.name))?;
+    let html_dir = html_path.parent().unwrap_or_else(|| path::Path::new("/"));
+    let link = parse_source_link(html_dir, &href)
+        .with_context(|| format!("Could not parse the source link {}", href))?;
+
+    let mut roots = Vec::new();
+    if let Some(root) = link.doc_root.parent().and_then(path::Path::parent) {
+        roots.push(root.to_owned());
+    }
+    if let Ok(dir) = env::current_dir() {
+        roots.push(dir);
+    }
+    for root in roots {
+        if let Some(crate_dir) = find_crate_dir(&root, &link.krate) {
+            let path = crate_dir.join(&link.path);
+            if path.is_file() {
+                return Ok(SourceLocation {
+                    path,
+                    line: link.line,
+                });
+            }
+        }
+    }
+    Err(anyhow::anyhow!(
+        "The source code of {} is not available locally",
+        &doc.name
+    ))
+}
+
+/// Returns the path of the manifest of the package that contains the given crate if its
+/// documentation has been generated locally in the `target/doc` directory of a workspace.
+pub fn find_manifest(doc: &doc::Doc) -> Option<path::PathBuf> {
+    let html_path = url::Url::parse(doc.url.as_ref()?)
+        .ok()?
+        .to_file_path()
+        .ok()?;
+    let target_dir = html_path
+        .ancestors()
+        .find(|dir| dir.ends_with("target/doc"))?
+        .parent()?;
+    let root = target_dir.parent()?;
+    find_package_dirs(root)
+        .into_iter()
+        .find(|dir| get_crate_dir(dir, doc.name.krate()).is_some())
+        .map(|dir| dir.join("Cargo.toml"))
+}
+
+/// Parses a `[src]` link like `../src/log/lib.rs.html#1185-1203` on a documentation page in the
+/// given directory.
+fn parse_source_link(html_dir: &path::Path, href: &str) -> Option<SourceLink> {
+    let (href, fragment) = match href.split_once('#') {
+        Some((href, fragment)) => (href, Some(fragment)),
+        None => (href, None),
+    };
+    let mut doc_root = html_dir.to_owned();
+    let mut parts = href.split('/').filter(|s| !s.is_empty() && *s != ".");
+    let mut part = parts.next()?;
+    while part == ".." {
+        doc_root = doc_root.parent()?.to_owned();
+        part = parts.next()?;
+    }
+    if part != "src" {
+        return None;
+    }
+    let krate = parts.next()?.to_owned();
+    let mut path: path::PathBuf = parts.collect();
+    let file_name = path
+        .file_name()?
+        .to_str()?
+        .strip_suffix(".html")?
+        .to_owned();
+    path.set_file_name(file_name);
+    let line = fragment
+        .and_then(|s| s.split('-').next())
+        .and_then(|s| s.parse().ok());
+    Some(SourceLink {
+        doc_root,
+        krate,
+        path,
+        line,
+    })
+}
+
+/// Returns the directory of the crate root of the package for the given crate in the given
+/// workspace.
+fn find_crate_dir(root: &path::Path, krate: &str) -> Option<path::PathBuf> {
+    find_package_dirs(root)
+        .into_iter()
+        .find_map(|dir| get_crate_dir(&dir, krate))
+}
+
+/// Returns the directories in the given workspace that could contain a package, i. e. the
+/// workspace directory and its subdirectories up to two levels below.
+fn find_package_dirs(root: &path::Path) -> Vec<path::PathBuf> {
+    let mut dirs = vec![root.to_owned()];
+    let mut parents = vec![root.to_owned()];
+    for _ in 0..2 {
+        let children: Vec<_> = parents
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir() && !is_ignored_dir(path))
+            .collect();
+        dirs.extend(children.iter().cloned());
+        parents = children;
+    }
+    dirs
+}
+
+/// Whether the given directory cannot contain a package, e. g. the target directory.
+fn is_ignored_dir(path: &path::Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    name.starts_with('.') || name == "target"
+}
+
+/// Returns the directory of the crate root if the manifest in the given directory belongs to a
+/// package with the given crate name.
+fn get_crate_dir(dir: &path::Path, krate: &str) -> Option<path::PathBuf> {
+    let manifest: toml::Value = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()?
+        .parse()
+        .ok()?;
+    let package_name = manifest.get("package")?.get("name")?.as_str()?;
+    let lib = manifest.get("lib");
+    let crate_name = lib
+        .and_then(|lib| lib.get("name"))
+        .and_then(toml::Value::as_str)
+        .unwrap_or(package_name);
+    if crate_name.replace('-', "_") != krate && package_name.replace('-', "_") != krate {
+        return None;
+    }
+    let crate_root_dir = lib
+        .and_then(|lib| lib.get("path"))
+        .and_then(toml::Value::as_str)
+        .and_then(|path| path::Path::new(path).parent())
+        .unwrap_or_else(|| path::Path::new("src"));
+    Some(dir.join(crate_root_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path;
+
+    use super::{find_crate_dir, find_manifest, parse_source_link, SourceLink};
+    use crate::doc;
+
+    #[test]
+    fn test_parse_source_link() {
+        let html_dir = path::Path::new("/ws/target/doc/log");
+        assert_eq!(
+            Some(SourceLink {
+                doc_root: "/ws/target/doc".into(),
+                krate: "log".to_owned(),
+                path: "lib.rs".into(),
+                line: Some(1185),
+            }),
+            parse_source_link(html_dir, "../src/log/lib.rs.html#1185-1203")
+        );
+        assert_eq!(
+            Some(SourceLink {
+                doc_root: "/ws/target/doc".into(),
+                krate: "log".to_owned(),
+                path: "kv/value.rs".into(),
+                line: None,
+            }),
+            parse_source_link(html_dir, "../src/log/kv/value.rs.html")
+        );
+        assert_eq!(None, parse_source_link(html_dir, "struct.Record.html"));
+        assert_eq!(
+            None,
+            parse_source_link(
+                html_dir,
+                "https://doc.rust-lang.org/src/core/fmt/mod.rs.html"
+            )
+        );
+    }
+
+    #[test]
+    fn test_find_crate_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(
+            "crates/foo-bar/Cargo.toml",
+            "[package]\nname = \"foo-bar\"\n",
+        );
+        write(
+            "crates/baz/Cargo.toml",
+            "[package]\nname = \"baz\"\n[lib]\nname = \"qux\"\npath = \"lib/qux.rs\"\n",
+        );
+
+        assert_eq!(
+            Some(root.join("crates/foo-bar/src")),
+            find_crate_dir(root, "foo_bar")
+        );
+        assert_eq!(
+            Some(root.join("crates/baz/lib")),
+            find_crate_dir(root, "qux")
+        );
+        assert_eq!(None, find_crate_dir(root, "log"));
+    }
+
+    #[test]
+    fn test_find_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("foo-bar/src")).unwrap();
+        fs::write(
+            root.join("foo-bar/Cargo.toml"),
+            "[package]\nname = \"foo-bar\"\n",
+        )
+        .unwrap();
+
+        let get_doc = |name: &str, path: path::PathBuf| {
+            let mut doc = doc::Doc::new(name.to_owned().into(), doc::ItemType::Struct);
+            doc.url = Some(url::Url::from_file_path(path).unwrap().to_string());
+            doc
+        };
+        let html_path = root.join("target/doc/foo_bar/struct.Foo.html");
+        assert_eq!(
+            Some(root.join("foo-bar/Cargo.toml")),
+            find_manifest(&get_doc("foo_bar::Foo", html_path.clone()))
+        );
+        assert_eq!(None, find_manifest(&get_doc("log::Foo", html_path)));
+        assert_eq!(
+            None,
+            find_manifest(&get_doc(
+                "foo_bar::Foo",
+                root.join("doc/foo_bar/struct.Foo.html")
+            ))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! History of the items opened with rusty-man, see the `--history` option.
+//!
+//! The history is stored in the `history` file in the `rusty-man` subdirectory of the user data
+//! directory.  Each line is an entry with the time of the lookup in seconds since the Unix epoch,
+//! the short name of the item type, the full name of the item and the keyword as typed by the
+//! user, separated by tabs.  The most recent entry is the last line.
+//!
+//! The file is never modified in place.  Instead, it is written to a temporary file that replaces
+//! the history file so that concurrent invocations cannot corrupt it.  If two invocations update
+//! the history at the same time, one of the new entries might get lost.
+
+use std::fmt;
+use std::fs;
+use std::io::Write as _;
+use std::path;
+use std::time;
+
+use anyhow::Context as _;
+
+use crate::doc;
+
+/// The default number of entries that are kept in the history, see the `--history-size` option.
+pub const DEFAULT_SIZE: usize = 1000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// The time of the lookup in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub ty: doc::ItemType,
+    pub name: doc::Fqn,
+    /// The keyword that was used to look up the item.
+    pub keyword: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct History {
+    path: path::PathBuf,
+    size: usize,
+}
+
+impl Entry {
+    fn parse(line: &str) -> anyhow::Result<Entry> {
+        let mut parts = line.splitn(4, '\t');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(timestamp), Some(ty), Some(name), Some(keyword)) => Ok(Entry {
+                timestamp: timestamp.parse()?,
+                ty: ty.parse()?,
+                name: name.to_owned().into(),
+                keyword: keyword.to_owned(),
+            }),
+            _ => anyhow::bail!("Invalid history entry"),
+        }
+    }
+
+    /// Formats the time of the lookup relative to the given time, e. g. `3 hours ago`.
+    pub fn format_age(&self, now: u64) -> String {
+        let age = now.saturating_sub(self.timestamp);
+        let (value, unit) = match age {
+            0..=59 => return "just now".to_owned(),
+            60..=3599 => (age / 60, "minute"),
+            3600..=86399 => (age / 3600, "hour"),
+            _ => (age / 86400, "day"),
+        };
+        if value == 1 {
+            format!("1 {} ago", unit)
+        } else {
+            format!("{} {}s ago", value, unit)
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t",
+            self.timestamp,
+            self.ty.short_name(),
+            self.name
+        )?;
+        // Tabs and line breaks would break the file format.
+        for c in self.keyword.chars() {
+            if c.is_control() {
+                write!(f, " ")?;
+            } else {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl History {
+    /// Returns the history stored in the user data directory that keeps at most `size` entries.
+    pub fn new(size: usize) -> anyhow::Result<History> {
+        let dirs = xdg::BaseDirectories::with_prefix("rusty-man")?;
+        Ok(History::with_path(dirs.place_data_file("history")?, size))
+    }
+
+    fn with_path(path: path::PathBuf, size: usize) -> History {
+        History { path, size }
+    }
+
+    /// Returns the entries of the history, starting with the most recent entry.
+    pub fn load(&self) -> anyhow::Result<Vec<Entry>> {
+        let mut entries = self.read()?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    fn read(&self) -> anyhow::Result<Vec<Entry>> {
+        if !self.path.is_file() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Could not read {}", self.path.display()))?;
+        let mut entries = Vec::new();
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            match Entry::parse(line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => log::warn!("Ignoring history entry '{}': {}", line, err),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Adds an entry for the given item to the history and removes the oldest entries if there
+    /// are more than `size` entries.
+    pub fn add(&self, keyword: &str, name: &doc::Fqn, ty: doc::ItemType) -> anyhow::Result<()> {
+        let timestamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)?
+            .as_secs();
+        let mut entries = self.read()?;
+        entries.push(Entry {
+            timestamp,
+            ty,
+            name: name.clone(),
+            keyword: keyword.to_owned(),
+        });
+        let skip = entries.len().saturating_sub(self.size);
+        self.write(&entries[skip..])
+    }
+
+    fn write(&self, entries: &[Entry]) -> anyhow::Result<()> {
+        let dir = self.path.parent().unwrap_or_else(|| path::Path::new("."));
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        for entry in entries {
+            writeln!(file, "{}", entry)?;
+        }
+        file.persist(&self.path)
+            .with_context(|| format!("Could not write {}", self.path.display()))?;
+        log::info!("Updated history '{}'", self.path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, History};
+    use crate::doc;
+
+    #[test]
+    fn test_add_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::with_path(dir.path().join("history"), 2);
+        assert!(history.load().unwrap().is_empty());
+
+        let level: doc::Fqn = "log::Level".to_owned().into();
+        let log: doc::Fqn = "log::Log".to_owned().into();
+        history.add("Level", &level, doc::ItemType::Enum).unwrap();
+        history.add("log::Log", &log, doc::ItemType::Trait).unwrap();
+        history
+            .add("level\twith\ntabs", &level, doc::ItemType::Enum)
+            .unwrap();
+
+        let entries = history.load().unwrap();
+        let names: Vec<_> = entries
+            .iter()
+            .map(|e| (e.ty, &e.name, e.keyword.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (doc::ItemType::Enum, &level, "level with tabs"),
+                (doc::ItemType::Trait, &log, "log::Log"),
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let entry = Entry::parse("1634000000\tstruct\tkuchiki::NodeRef\tNodeRef").unwrap();
+        assert_eq!(
+            Entry {
+                timestamp: 1634000000,
+                ty: doc::ItemType::Struct,
+                name: "kuchiki::NodeRef".to_owned().into(),
+                keyword: "NodeRef".to_owned(),
+            },
+            entry
+        );
+        assert_eq!(entry, Entry::parse(&entry.to_string()).unwrap());
+        assert!(Entry::parse("1634000000\tstruct\tkuchiki::NodeRef").is_err());
+        assert!(Entry::parse("now\tstruct\tkuchiki::NodeRef\tNodeRef").is_err());
+    }
+
+    #[test]
+    fn test_format_age() {
+        let entry = Entry::parse("1000000\tstruct\tkuchiki::NodeRef\tNodeRef").unwrap();
+        assert_eq!("just now", entry.format_age(1000000));
+        assert_eq!("just now", entry.format_age(999000));
+        assert_eq!("1 minute ago", entry.format_age(1000090));
+        assert_eq!("5 hours ago", entry.format_age(1000000 + 5 * 3600 + 10));
+        assert_eq!("2 days ago", entry.format_age(1000000 + 2 * 86400));
+    }
+}
@@ -13,6 +13,17 @@
 //!
 //! For details on the generation of the search index, see the `html/render/cache.rs` file in
 //! `librustdoc`.
+//!
+//! The JSON data can be wrapped in three different ways:
+//!
+//! - `var searchIndex = JSON.parse('{…}');`:  a JSON string in a JavaScript string literal, as
+//!   generated by rustdoc.  The literal may use single or double quotes and span multiple lines.
+//! - `initSearch({…});`:  a JavaScript object literal passed to `initSearch`, as generated for
+//!   the documentation in `pkg/doc` by `wasm-pack`.
+//! - `window.searchIndex = {…};`:  a JavaScript object literal assigned to `window.searchIndex`,
+//!   as used by some rustdoc outputs that are integrated into mdBook.
+//!
+//! In the last two cases, the object literal must be valid JSON.
 
 mod v1_44;
 mod v1_52;
@@ -21,7 +32,7 @@ mod v1_69;
 use std::collections;
 use std::fmt;
 use std::fs;
-use std::io;
+use std::iter;
 use std::path;
 
 use crate::doc;
@@ -37,20 +48,24 @@ pub struct IndexItem {
     pub name: doc::Fqn,
     pub ty: doc::ItemType,
     pub description: String,
+    /// The priority of the source of this item.  Sources that are searched first have a higher
+    /// priority.
+    pub source_priority: usize,
+    /// The `#[doc(alias)]` aliases of this item.  For the results of `Index::find`, this only
+    /// contains the aliases that matched the keyword.
+    pub aliases: Vec<String>,
 }
 
 impl fmt::Display for IndexItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}", &self.name, self.ty.name())?;
+        for alias in &self.aliases {
+            write!(f, ", alias {}", alias)?;
+        }
         if self.description.is_empty() {
-            write!(f, "{} ({})", &self.name, self.ty.name())
+            write!(f, ")")
         } else {
-            write!(
-                f,
-                "{} ({}): {}",
-                &self.name,
-                self.ty.name(),
-                &self.description
-            )
+            write!(f, "): {}", &self.description)
         }
     }
 }
@@ -65,6 +80,8 @@ struct Data {
 struct CrateData {
     items: Vec<ItemData>,
     paths: Vec<(usize, String)>,
+    /// The `#[doc(alias)]` aliases of the items, indexed by the item index.
+    aliases: collections::HashMap<usize, Vec<String>>,
 }
 
 impl<'de> serde::Deserialize<'de> for CrateData {
@@ -130,57 +147,60 @@ impl<'de> serde::Deserialize<'de> for ItemType {
 
 impl Index {
     pub fn load(path: impl AsRef<path::Path>) -> anyhow::Result<Option<Self>> {
-        use std::io::BufRead;
-
         anyhow::ensure!(
             path.as_ref().is_file(),
             "Search index '{}' must be a file",
             path.as_ref().display()
         );
 
-        let mut json: Option<String> = None;
-        let mut finished = false;
-
-        for line in io::BufReader::new(fs::File::open(path.as_ref())?).lines() {
-            let line = line?;
-            if let Some(json) = &mut json {
-                if line == "}');" {
-                    json.push('}');
-                    finished = true;
-                    break;
-                } else {
-                    json.push_str(line.trim_end_matches('\\'));
-                }
-            } else if line == "var searchIndex = JSON.parse('{\\" {
-                json = Some(String::from("{"));
+        let content = fs::read_to_string(path.as_ref())?;
+        let content = content.trim_start_matches('\u{feff}');
+
+        use anyhow::Context;
+
+        // The search index is either a JSON string embedded in a JavaScript string literal that
+        // might span multiple lines or be minified to a single line, or a JavaScript object
+        // literal.  For string literals, we extract the raw literal between its opening and
+        // closing quotes and unescape it afterwards.
+        let json = if let Some((start, quote)) = find_js_string_start(content) {
+            if let Some(end) = find_js_string_end(&content[start..], quote) {
+                unescape_js_string(&content[start..start + end]).with_context(|| {
+                    format!(
+                        "Could not unescape search index '{}'",
+                        path.as_ref().display()
+                    )
+                })?
+            } else {
+                log::info!(
+                    "Did not find JSON end in search index '{}'",
+                    path.as_ref().display()
+                );
+                return Ok(None);
             }
-        }
-
-        if let Some(json) = json {
-            if finished {
-                use anyhow::Context;
-                let json = json.replace("\\'", "'");
-                let data: Data = serde_json::from_str(&json)
-                    .context(format!("Could not parse search index of {}", &json))?;
-
-                Ok(Some(Index {
-                    data,
-                    path: path.as_ref().to_owned(),
-                }))
+        } else if let Some(start) = find_js_object_start(content) {
+            if let Some(end) = find_js_object_end(&content[start..]) {
+                content[start..=start + end].to_owned()
             } else {
                 log::info!(
-                    "Did not find JSON end line in search index '{}'",
+                    "Did not find JSON end in search index '{}'",
                     path.as_ref().display()
                 );
-                Ok(None)
+                return Ok(None);
             }
         } else {
             log::info!(
-                "Did not find JSON start line in search index '{}'",
+                "Did not find JSON start in search index '{}'",
                 path.as_ref().display()
             );
-            Ok(None)
-        }
+            return Ok(None);
+        };
+
+        let data: Data = serde_json::from_str(&json)
+            .context(format!("Could not parse search index of {}", &json))?;
+        Ok(Some(Index {
+            data,
+            path: path.as_ref().to_owned(),
+        }))
     }
 
     pub fn find(&self, name: &doc::Name) -> Vec<IndexItem> {
@@ -189,36 +209,62 @@ impl Index {
             name,
             self.path.display()
         );
+        // If the keyword starts with the name of a crate in the index, e. g. `log::Log`, it is
+        // anchored at this crate:  Only items whose full name is the keyword match, so `log::Log`
+        // matches `log::Log`, but neither `log::sub::Log` nor `foo::log::Log`.
+        let anchor = name
+            .rest()
+            .filter(|_| self.data.crates.contains_key(name.first()))
+            .map(|rest| (name.first(), doc::Name::from(rest.to_owned())));
+        if let Some((krate, _)) = &anchor {
+            log::info!("Restricting the search to the crate '{}'", krate);
+        }
         let mut matches: Vec<IndexItem> = Vec::new();
         for (krate, data) in &self.data.crates {
-            let mut path = krate;
-            for item in &data.items {
-                path = if item.path.is_empty() {
-                    path
+            if anchor.as_ref().map(|(k, _)| k != krate).unwrap_or(false) {
+                continue;
+            }
+            for mut item in get_items(krate, data) {
+                let is_match = if anchor.is_some() {
+                    item.name.full() == name.full()
                 } else {
-                    &item.path
+                    item.name.ends_with(name)
                 };
-
-                let ty = doc::ItemType::from(item.ty);
-                if ty == doc::ItemType::AssocType {
-                    continue;
-                }
-
-                let full_path = match item.parent {
-                    Some(idx) => {
-                        let parent = &data.paths[idx].1;
-                        format!("{}::{}", path, parent)
+                // Items can also be found using their #[doc(alias)] aliases.  The alias replaces
+                // the last segment of the keyword, so the other segments must match the parent.
+                let keyword = anchor.as_ref().map(|(_, rest)| rest).unwrap_or(name);
+                let parent_matches = match (keyword.parent(), item.name.parent()) {
+                    (None, _) => true,
+                    (Some(parent), Some(item_parent)) if anchor.is_some() => {
+                        item_parent.rest() == Some(parent.full())
                     }
-                    None => path.to_owned(),
+                    (Some(parent), Some(item_parent)) => item_parent.ends_with(&parent),
+                    (Some(_), None) => false,
                 };
-                let full_name: doc::Fqn = format!("{}::{}", &full_path, &item.name).into();
-                if full_name.ends_with(name) {
-                    log::info!("Found index match '{}'", full_name);
-                    matches.push(IndexItem {
-                        name: full_name,
-                        ty,
-                        description: item.desc.clone(),
-                    });
+                item.aliases
+                    .retain(|alias| parent_matches && keyword.last().eq_ignore_ascii_case(alias));
+                if is_match || !item.aliases.is_empty() {
+                    log::info!("Found index match '{}'", item.name);
+                    matches.push(item);
+                }
+            }
+        }
+        matches.sort_unstable();
+        matches.dedup();
+        matches
+    }
+
+    /// Returns the items whose name starts with the given prefix, either at the start of the full
+    /// name or after a path separator, see `match_prefix`.
+    pub fn find_prefix(&self, prefix: &str) -> Vec<IndexItem> {
+        let mut matches: Vec<IndexItem> = Vec::new();
+        if prefix.is_empty() {
+            return matches;
+        }
+        for (krate, data) in &self.data.crates {
+            for item in get_items(krate, data) {
+                if match_prefix(item.name.as_ref(), prefix).is_some() {
+                    matches.push(item);
                 }
             }
         }
@@ -226,11 +272,236 @@ impl Index {
         matches.dedup();
         matches
     }
+
+    /// Returns all items of the given crate in the search index.
+    pub fn crate_items(&self, krate: &str) -> Vec<IndexItem> {
+        let mut items = self
+            .data
+            .crates
+            .get(krate)
+            .map(|data| get_items(krate, data))
+            .unwrap_or_default();
+        items.sort_unstable();
+        items.dedup();
+        items
+    }
+}
+
+/// Returns the part of the given name that starts with the given prefix, either at the start of
+/// the name or after a path separator, e. g. `Vec::new` for `std::vec::Vec::new` and `Vec::ne`.
+pub fn match_prefix<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    iter::once(0)
+        .chain(name.match_indices("::").map(|(idx, sep)| idx + sep.len()))
+        .map(|idx| &name[idx..])
+        .find(|s| s.starts_with(prefix))
+}
+
+fn get_items(krate: &str, data: &CrateData) -> Vec<IndexItem> {
+    let mut items = Vec::new();
+    let mut path = krate;
+    for (index, item) in data.items.iter().enumerate() {
+        path = if item.path.is_empty() {
+            path
+        } else {
+            &item.path
+        };
+
+        let ty = doc::ItemType::from(item.ty);
+        if ty == doc::ItemType::AssocType {
+            continue;
+        }
+
+        let parent = item.parent.map(|idx| data.paths[idx].1.as_str());
+        let name: doc::Fqn = iter::once(path)
+            .chain(parent)
+            .chain(iter::once(item.name.as_str()))
+            .collect();
+        items.push(IndexItem {
+            name,
+            ty,
+            description: item.desc.clone(),
+            source_priority: 0,
+            aliases: data.aliases.get(&index).cloned().unwrap_or_default(),
+        });
+    }
+    items
+}
+
+const SEARCH_INDEX_START: &str = "JSON.parse(";
+
+/// Returns the index after the opening quote of the string literal passed to `JSON.parse` and the
+/// quote character.
+fn find_js_string_start(s: &str) -> Option<(usize, char)> {
+    let start = s.find(SEARCH_INDEX_START)? + SEARCH_INDEX_START.len();
+    let literal = s[start..].trim_start();
+    let quote = literal.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    Some((s.len() - literal.len() + quote.len_utf8(), quote))
+}
+
+/// The tokens that precede the object literal in the search index formats that don't use
+/// `JSON.parse`, see the module documentation.
+const SEARCH_INDEX_OBJECT_STARTS: &[&[&str]] =
+    &[&["initSearch", "(", "{"], &["window.searchIndex", "=", "{"]];
+
+/// Returns the index of the opening brace of the object literal in the search index.
+fn find_js_object_start(s: &str) -> Option<usize> {
+    SEARCH_INDEX_OBJECT_STARTS
+        .iter()
+        .find_map(|tokens| find_tokens(s, tokens))
+}
+
+/// Returns the index of the last token of the first occurrence of the given tokens, which may be
+/// separated by whitespace.
+fn find_tokens(s: &str, tokens: &[&str]) -> Option<usize> {
+    let (first, rest) = tokens.split_first()?;
+    let mut offset = 0;
+    while let Some(idx) = s[offset..].find(first) {
+        let mut start = offset + idx;
+        let mut end = start + first.len();
+        let matches = rest.iter().all(|token| {
+            let remaining = s[end..].trim_start();
+            if remaining.starts_with(token) {
+                start = s.len() - remaining.len();
+                end = start + token.len();
+                true
+            } else {
+                false
+            }
+        });
+        if matches {
+            return Some(start);
+        }
+        offset += idx + first.len();
+    }
+    None
+}
+
+/// Returns the index of the closing brace of a JavaScript object literal, given the content
+/// starting with the opening brace.  Braces in string literals are ignored.
+fn find_js_object_end(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut idx = 0;
+    while let Some(c) = s[idx..].chars().next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            // skip to the closing quote
+            '"' | '\'' => idx += 1 + find_js_string_end(&s[idx + 1..], c)?,
+            _ => {}
+        }
+        idx += c.len_utf8();
+    }
+    None
+}
+
+/// Returns the index of the closing quote of a JavaScript string literal, given the content after
+/// the opening quote.
+fn find_js_string_end(s: &str, quote: char) -> Option<usize> {
+    let mut chars = s.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => return Some(idx),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Unescapes the content of a JavaScript string literal.
+///
+/// Besides the single-character escape sequences, this handles `\xXX`, `\uXXXX` and `\u{X…}`
+/// escapes (including UTF-16 surrogate pairs) and line continuations.
+fn unescape_js_string(s: &str) -> anyhow::Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let c = chars
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Unterminated escape sequence"))?;
+        match c {
+            // line continuation
+            '\n' => {}
+            '\r' => {
+                if chars.as_str().starts_with('\n') {
+                    chars.next();
+                }
+            }
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            'v' => result.push('\u{b}'),
+            '0' => result.push('\0'),
+            'x' => {
+                let code = parse_hex(&mut chars, 2)?;
+                result.push(char_from_u32(code)?);
+            }
+            'u' => {
+                let code = if chars.as_str().starts_with('{') {
+                    chars.next();
+                    let end = chars
+                        .as_str()
+                        .find('}')
+                        .ok_or_else(|| anyhow::anyhow!("Unterminated unicode escape sequence"))?;
+                    let code = parse_hex(&mut chars, end)?;
+                    chars.next();
+                    code
+                } else {
+                    let code = parse_hex(&mut chars, 4)?;
+                    if (0xd800..0xdc00).contains(&code) && chars.as_str().starts_with("\\u") {
+                        let mut lookahead = chars.clone();
+                        lookahead.nth(1);
+                        let low = parse_hex(&mut lookahead, 4)?;
+                        if (0xdc00..0xe000).contains(&low) {
+                            chars = lookahead;
+                            0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                        } else {
+                            code
+                        }
+                    } else {
+                        code
+                    }
+                };
+                result.push(char_from_u32(code)?);
+            }
+            // this includes \\, \' and \"
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+fn parse_hex(chars: &mut std::str::Chars<'_>, n: usize) -> anyhow::Result<u32> {
+    let digits: String = chars.take(n).collect();
+    anyhow::ensure!(
+        digits.chars().count() == n,
+        "Incomplete escape sequence: {}",
+        digits
+    );
+    u32::from_str_radix(&digits, 16)
+        .map_err(|_| anyhow::anyhow!("Invalid escape sequence: {}", digits))
+}
+
+fn char_from_u32(code: u32) -> anyhow::Result<char> {
+    std::char::from_u32(code).ok_or_else(|| anyhow::anyhow!("Invalid code point: {:x}", code))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{CrateData, Data, Index, IndexItem, ItemData};
+    use super::{match_prefix, unescape_js_string, CrateData, Data, Index, IndexItem, ItemData};
     use crate::doc::ItemType;
     use crate::test_utils::{with_rustdoc, Format};
 
@@ -271,6 +542,90 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_v1_69_path_inheritance() {
+        let json = r#"{"rand_core":{"t":"AAIKLIAFF","n":["rand_core","block","RngCore","next_u32","fill_bytes","BlockRngCore","le","read_u32_into","fill_via_u32_chunks"],"q":[[1,"rand_core"],[5,"rand_core::block"],[7,"rand_core::le"],[8,"rand_core::impls"]],"d":["","","","","","","","",""],"i":[0,0,0,1,1,0,0,0,0],"p":[[8,"RngCore"]]}}"#;
+        let data: Data = serde_json::from_str(json).unwrap();
+        let paths: Vec<_> = data.crates["rand_core"]
+            .items
+            .iter()
+            .map(|i| i.path.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "",
+                "rand_core",
+                "rand_core",
+                "rand_core",
+                "rand_core",
+                "rand_core::block",
+                "rand_core::block",
+                "rand_core::le",
+                "rand_core::impls",
+            ],
+            paths
+        );
+
+        let index = Index {
+            path: Default::default(),
+            data,
+        };
+        let find = |s: &str| {
+            index
+                .find(&s.to_owned().into())
+                .into_iter()
+                .map(|i| i.name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["rand_core::block::BlockRngCore"], find("BlockRngCore"));
+        assert_eq!(vec!["rand_core::le::read_u32_into"], find("read_u32_into"));
+        assert_eq!(
+            vec!["rand_core::impls::fill_via_u32_chunks"],
+            find("fill_via_u32_chunks")
+        );
+        assert_eq!(vec!["rand_core::RngCore::fill_bytes"], find("fill_bytes"));
+    }
+
+    #[test]
+    fn test_v1_69_aliases() {
+        let json = r#"{"rand_core":{"t":"AIK","n":["rand_core","RngCore","next_u32"],"q":[[1,"rand_core"]],"d":["","","Returns the next u32."],"i":[0,0,1],"p":[[8,"RngCore"]],"a":{"next_int":[2],"rng":[1]}}}"#;
+        let index = Index {
+            path: Default::default(),
+            data: serde_json::from_str(json).unwrap(),
+        };
+        let find = |s: &str| {
+            index
+                .find(&s.to_owned().into())
+                .into_iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["rand_core::RngCore::next_u32 (Required Method, alias next_int): Returns the next u32."],
+            find("next_int")
+        );
+        assert_eq!(
+            vec!["rand_core::RngCore::next_u32 (Required Method, alias next_int): Returns the next u32."],
+            find("rand_core::NEXT_INT")
+        );
+        assert_eq!(
+            vec!["rand_core::RngCore::next_u32 (Required Method): Returns the next u32."],
+            find("next_u32")
+        );
+        assert_eq!(
+            vec!["rand_core::RngCore::next_u32 (Required Method, alias next_int): Returns the next u32."],
+            find("RngCore::next_int")
+        );
+        assert_eq!(
+            vec!["rand_core::RngCore::next_u32 (Required Method, alias next_int): Returns the next u32."],
+            find("rand_core::RngCore::next_int")
+        );
+        assert!(find("Foo::next_int").is_empty());
+        assert!(find("rand_core::Foo::next_int").is_empty());
+        assert_eq!(vec!["rand_core::RngCore (Trait, alias rng)"], find("rng"));
+        assert!(find("next").is_empty());
+    }
+
     #[test]
     fn test_index() {
         with_rustdoc(">=1.44.0, <1.50.0", Format::all(), |_, _, path| {
@@ -282,6 +637,8 @@ mod tests {
                 name: "kuchiki::NodeDataRef".to_owned().into(),
                 ty: ItemType::Struct,
                 description: "Holds a strong reference to a node, but dereferences to…".to_owned(),
+                source_priority: 0,
+                aliases: Vec::new(),
             }];
             assert_eq!(node_data_ref, index.find(&"NodeDataRef".to_owned().into()));
             assert_eq!(
@@ -301,6 +658,8 @@ mod tests {
                 name: "kuchiki::NodeDataRef".to_owned().into(),
                 ty: ItemType::Struct,
                 description: "Holds a strong reference to a node, but dereferences to …".to_owned(),
+                source_priority: 0,
+                aliases: Vec::new(),
             }];
             assert_eq!(node_data_ref, index.find(&"NodeDataRef".to_owned().into()));
             assert_eq!(
@@ -320,6 +679,8 @@ mod tests {
                 name: "kuchiki::NodeDataRef::as_node".to_owned().into(),
                 ty: ItemType::Method,
                 description: "Access the corresponding node.".to_owned(),
+                source_priority: 0,
+                aliases: Vec::new(),
             }];
             assert_eq!(as_node, index.find(&"as_node".to_owned().into()));
             assert_eq!(
@@ -333,4 +694,226 @@ mod tests {
             assert_eq!(empty, index.find(&"DataRef::as_node".to_owned().into()));
         });
     }
+
+    #[test]
+    fn test_crate_items() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let index = Index::load(path.join("search-index.js")).unwrap().unwrap();
+
+            let items = index.crate_items("log");
+            assert!(items.iter().all(|item| item.name.krate() == "log"));
+            let has_item = |name: &str, ty: ItemType| {
+                items
+                    .iter()
+                    .any(|item| item.name.as_ref() == name && item.ty == ty)
+            };
+            assert!(has_item("log::Log", ItemType::Trait));
+            assert!(has_item("log::Log::enabled", ItemType::TyMethod));
+            assert!(has_item("log::error", ItemType::Macro));
+
+            assert!(index.crate_items("rusty_man").is_empty());
+        });
+    }
+
+    #[test]
+    fn test_find_anchored() {
+        let json = r#"{
+            "foo": {"i": [
+                [0, "Bar", "foo", "", null, null],
+                [0, "Bar", "foo::sub", "", null, null]
+            ], "p": []},
+            "baz": {"i": [
+                [0, "Bar", "baz::foo", "", null, null],
+                [0, "Bar", "baz::sub", "", null, null]
+            ], "p": []}
+        }"#;
+        let index = Index {
+            data: serde_json::from_str(json).unwrap(),
+            path: Default::default(),
+        };
+        let find = |name: &str| -> Vec<String> {
+            index
+                .find(&name.to_owned().into())
+                .into_iter()
+                .map(|item| item.name.as_ref().to_owned())
+                .collect()
+        };
+
+        // suffix matches
+        assert_eq!(
+            vec![
+                "baz::foo::Bar",
+                "baz::sub::Bar",
+                "foo::Bar",
+                "foo::sub::Bar"
+            ],
+            find("Bar")
+        );
+        assert_eq!(vec!["baz::sub::Bar", "foo::sub::Bar"], find("sub::Bar"));
+        // anchored matches
+        assert_eq!(vec!["foo::Bar"], find("foo::Bar"));
+        assert_eq!(vec!["foo::sub::Bar"], find("foo::sub::Bar"));
+        assert_eq!(vec!["baz::sub::Bar"], find("baz::sub::Bar"));
+        assert!(find("foo::foo").is_empty());
+        assert!(find("foo::sub").is_empty());
+        assert!(find("foo::baz::Bar").is_empty());
+    }
+
+    #[test]
+    fn test_find_prefix() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let index = Index::load(path.join("search-index.js")).unwrap().unwrap();
+
+            let items: Vec<_> = index
+                .find_prefix("Log::fl")
+                .into_iter()
+                .map(|item| (item.name.as_ref().to_owned(), item.ty))
+                .collect();
+            assert_eq!(
+                vec![("log::Log::flush".to_owned(), ItemType::TyMethod)],
+                items
+            );
+            assert!(index.find_prefix("log::set_").len() > 1);
+            assert!(index.find_prefix("").is_empty());
+            assert!(index.find_prefix("og::Log").is_empty());
+        });
+    }
+
+    #[test]
+    fn test_match_prefix() {
+        assert_eq!(Some("std::vec"), match_prefix("std::vec", "std"));
+        assert_eq!(
+            Some("Vec::new"),
+            match_prefix("std::vec::Vec::new", "Vec::n")
+        );
+        assert_eq!(Some("new"), match_prefix("std::vec::Vec::new", "ne"));
+        assert_eq!(None, match_prefix("std::vec::Vec::new", "ec"));
+    }
+
+    #[test]
+    fn test_unescape_js_string() {
+        let unescape = |s| unescape_js_string(s).unwrap();
+        assert_eq!("abc", unescape("abc"));
+        assert_eq!(r"a\b", unescape(r"a\\b"));
+        assert_eq!("'\"", unescape(r#"\'\""#));
+        assert_eq!("a\nb\tc", unescape(r"a\nb\tc"));
+        assert_eq!("ab", unescape("a\\\nb"));
+        assert_eq!("ab", unescape("a\\\r\nb"));
+        assert_eq!("é€", unescape(r"\u00e9\u20AC"));
+        assert_eq!("é😀", unescape(r"\xe9\u{1f600}"));
+        assert_eq!("😀", unescape(r"\ud83d\ude00"));
+        assert!(unescape_js_string(r"abc\").is_err());
+        assert!(unescape_js_string(r"\u12").is_err());
+        assert!(unescape_js_string(r"\uzzzz").is_err());
+    }
+
+    #[test]
+    fn test_load_escapes() {
+        // The search index is a JSON string in a single-quoted JavaScript string, so backslashes
+        // are escaped twice.
+        let desc = r#"Matches \\\\d+ and \'quoted\' \\"text\\" with \\u00e9, é and \\\\"#;
+        let expected = r#"Matches \d+ and 'quoted' "text" with é, é and \"#;
+        let crates = [
+            format!(
+                r#""test":{{"doc":"","i":[[3,"Item","test","{}",null,null]],"p":[]}}"#,
+                desc
+            ),
+            format!(
+                r#""test":{{"doc":"","t":[3],"n":["Item"],"q":["test"],"d":["{}"],"i":[0],"p":[]}}"#,
+                desc
+            ),
+            format!(
+                r#""test":{{"doc":"","t":"D","n":["Item"],"q":[[0,"test"]],"d":["{}"],"i":[0],"p":[]}}"#,
+                desc
+            ),
+        ];
+
+        for krate in &crates {
+            let index = load_index(&format!(
+                "var searchIndex = JSON.parse('{{\\\n{}\\\n}}');\ninitSearch(searchIndex);\n",
+                krate
+            ))
+            .unwrap();
+            let items = index.find(&"Item".to_owned().into());
+            assert_eq!(1, items.len());
+            assert_eq!("test::Item", items[0].name.as_ref());
+            assert_eq!(expected, items[0].description);
+        }
+    }
+
+    #[test]
+    fn test_load_framings() {
+        let krate =
+            r#""test":{"doc":"","i":[[3,"Item","test","It\'s an item.",null,null]],"p":[]}"#;
+        let framings = [
+            // multiple lines, as generated by rustdoc
+            format!("var searchIndex = JSON.parse('{{\\\n{}\\\n}}');\n", krate),
+            // byte order mark
+            format!("\u{feff}var searchIndex = JSON.parse('{{\\\n{}\\\n}}');\n", krate),
+            // CRLF line endings
+            format!(
+                "var searchIndex = JSON.parse('{{\\\r\n{}\\\r\n}}');\r\ninitSearch(searchIndex);\r\n",
+                krate
+            ),
+            // minified
+            format!(
+                "var searchIndex=JSON.parse('{{{}}}');initSearch(searchIndex);",
+                krate
+            ),
+            format!(
+                "\u{feff}var N=null;var searchIndex = JSON.parse('{{{}}}');if(window.initSearch){{window.initSearch(searchIndex)}};",
+                krate
+            ),
+            // single line with whitespace around the literal
+            format!("var searchIndex = JSON.parse( '{{{}}}' );\n", krate),
+            // double-quoted literal
+            format!(
+                "var searchIndex = JSON.parse(\"{{{}}}\");\n",
+                krate.replace('"', "\\\"")
+            ),
+        ];
+
+        for js in &framings {
+            let index = load_index(js).unwrap();
+            let items = index.find(&"Item".to_owned().into());
+            assert_eq!(1, items.len());
+            assert_eq!("It's an item.", items[0].description);
+        }
+
+        let object_framings = [
+            // wasm-pack
+            format!("initSearch({{{}}});\n", krate),
+            format!("initSearch(\n  {{\n{}\n}}\n);\n", krate),
+            // mdBook
+            format!("window.searchIndex = {{{}}};\n", krate),
+            format!("window.searchIndex={{{}}};if(window.initSearch){{window.initSearch(window.searchIndex)}};", krate),
+        ];
+
+        for js in &object_framings {
+            // \' is not a valid escape sequence in JSON
+            let index = load_index(&js.replace("\\'", "'")).unwrap();
+            let items = index.find(&"Item".to_owned().into());
+            assert_eq!(1, items.len());
+            assert_eq!("It's an item.", items[0].description);
+        }
+
+        let index = load_index(
+            r#"initSearch({"test":{"doc":"}","i":[[3,"Item","test","{é}",null,null]],"p":[]}});"#,
+        )
+        .unwrap();
+        assert_eq!("{é}", index.find(&"Item".to_owned().into())[0].description);
+
+        assert!(load_index("var searchIndex = {};").is_none());
+        assert!(load_index("initSearch(searchIndex);").is_none());
+        assert!(load_index("window.searchIndex = {\"test\":{").is_none());
+        assert!(load_index("var searchIndex = JSON.parse('{\\\n").is_none());
+        assert!(load_index("var searchIndex = JSON.parse(data);").is_none());
+    }
+
+    fn load_index(js: &str) -> Option<Index> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("search-index.js");
+        std::fs::write(&path, js).unwrap();
+        Index::load(&path).unwrap()
+    }
 }
@@ -21,6 +21,7 @@ impl From<CrateData> for super::CrateData {
         Self {
             items: data.items,
             paths: data.paths,
+            aliases: Default::default(),
         }
     }
 }
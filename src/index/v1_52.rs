@@ -29,10 +29,10 @@ impl From<CrateData> for super::CrateData {
         let items = data
             .item_types
             .into_iter()
-            .zip(data.item_names.into_iter())
-            .zip(data.item_paths.into_iter())
-            .zip(data.item_descs.into_iter())
-            .zip(data.item_parents.into_iter())
+            .zip(data.item_names)
+            .zip(data.item_paths)
+            .zip(data.item_descs)
+            .zip(data.item_parents)
             .map(|((((ty, name), path), desc), parent)| super::ItemData {
                 ty,
                 name,
@@ -48,6 +48,7 @@ impl From<CrateData> for super::CrateData {
         Self {
             items,
             paths: data.paths,
+            aliases: Default::default(),
         }
     }
 }
@@ -23,6 +23,8 @@ pub struct CrateData {
     item_parents: Vec<usize>,
     #[serde(rename = "p")]
     paths: Vec<(usize, String)>,
+    #[serde(rename = "a", default)]
+    aliases: HashMap<String, Vec<usize>>,
 }
 
 #[derive(Debug, PartialEq, serde::Deserialize)]
@@ -46,22 +48,30 @@ impl From<CrateData> for super::CrateData {
                 .iter()
                 .cloned()
                 .enumerate()
-                .filter(|(_, s)| s.len() > 0)
+                .filter(|(_, s)| !s.is_empty())
                 .collect(),
             ItemPaths::Indexed(v) => v.iter().cloned().collect(),
         };
+        // The item paths are sparse:  If there is no path for an item, it has the same path as the
+        // previous item.
+        let mut last_path = String::new();
         let items = data
             .item_types
             .chars()
             .map(|c| crate::doc::ItemType::try_from(c).unwrap().into())
-            .zip(data.item_names.into_iter())
-            .zip(data.item_descs.into_iter())
-            .zip(data.item_parents.into_iter())
+            .zip(data.item_names)
+            .zip(data.item_descs)
+            .zip(data.item_parents)
             .enumerate()
             .map(|(index, (((ty, name), desc), parent))| super::ItemData {
                 ty,
                 name,
-                path: path_map.get(&index).cloned().unwrap_or(String::new()),
+                path: {
+                    if let Some(path) = path_map.get(&index) {
+                        last_path = path.clone();
+                    }
+                    last_path.clone()
+                },
                 desc,
                 parent: match parent {
                     0 => None,
@@ -70,9 +80,21 @@ impl From<CrateData> for super::CrateData {
                 _ignored: Default::default(),
             })
             .collect();
+        // The aliases map an alias to the indices of the items with this alias, but we need the
+        // aliases of an item.
+        let mut aliases: HashMap<usize, Vec<String>> = HashMap::new();
+        for (alias, indices) in data.aliases {
+            for index in indices {
+                aliases.entry(index).or_default().push(alias.clone());
+            }
+        }
+        for item_aliases in aliases.values_mut() {
+            item_aliases.sort_unstable();
+        }
         Self {
             items,
             paths: data.paths,
+            aliases,
         }
     }
 }
@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: 2020-2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! Resolves relative links between documentation pages generated by rustdoc.
+
+use anyhow::Context as _;
+
+use crate::doc;
+
+/// Resolves a relative link on the documentation page for the given item to the name and (if
+/// known) the type of the linked item.
+pub fn resolve_doc_link(
+    doc_name: &doc::Fqn,
+    doc_ty: doc::ItemType,
+    link: &str,
+) -> anyhow::Result<(Option<doc::ItemType>, doc::Fqn)> {
+    // TODO: use a proper URL parser instead of manually parsing the URL
+    let (link, fragment) = {
+        let parts: Vec<_> = link.splitn(2, '#').collect();
+        if parts.len() > 1 {
+            (parts[0], Some(parts[1]))
+        } else {
+            (parts[0], None)
+        }
+    };
+    let parts: Vec<_> = link
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .filter(|s| *s != ".")
+        .collect();
+
+    let mut components: Vec<&str> = doc_name.components().collect();
+    let mut ty = Some(doc_ty);
+    if doc_ty != doc::ItemType::Module && !parts.is_empty() {
+        ty = None;
+        components.pop();
+    }
+
+    for part in parts {
+        // We support "..", "index.html", "<module>" and "<type>.<name>.html".
+        match part {
+            ".." => {
+                ty = None;
+                components.pop().context("Exceeded root level")?;
+            }
+            "index.html" => {}
+            _ => {
+                if let Some((part_ty, part_name)) = parse_url_part(part, Some(".html")) {
+                    // part == "type.name.html"
+                    ty = Some(part_ty.parse()?);
+                    components.push(part_name);
+                } else {
+                    // part == "<module>"
+                    ty = Some(doc::ItemType::Module);
+                    components.push(part);
+                }
+            }
+        }
+    }
+
+    if let Some(fragment) = fragment {
+        // If the fragment is "<type>.:name>", we add it to the name, otherwise we ignore it
+        // because it just points to some other element on the page.
+        if let Some((fragment_ty, fragment_name)) = parse_url_part(fragment, None) {
+            ty = Some(fragment_ty.parse()?);
+            components.push(fragment_name);
+        }
+    }
+
+    anyhow::ensure!(!components.is_empty(), "Cannot handle link to root");
+    Ok((ty, components.into_iter().collect()))
+}
+
+fn parse_url_part<'s>(s: &'s str, suffix: Option<&str>) -> Option<(&'s str, &'s str)> {
+    let s = if let Some(suffix) = suffix {
+        if s.ends_with(suffix) {
+            &s[..s.len() - suffix.len()]
+        } else {
+            return None;
+        }
+    } else {
+        s
+    };
+    let parts: Vec<_> = s.split('.').collect();
+    if parts.len() == 2 {
+        Some((parts[0], parts[1]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_doc_link;
+    use crate::doc::ItemType;
+
+    fn assert_link(base: &str, base_ty: ItemType, link: &str, ty: Option<ItemType>, name: &str) {
+        let (actual_ty, actual_name) = resolve_doc_link(&base.to_owned().into(), base_ty, link)
+            .unwrap_or_else(|e| panic!("Could not resolve link {}: {}", link, e));
+        assert_eq!(ty, actual_ty, "type for link '{}'", link);
+        assert_eq!(name, actual_name.full(), "name for link '{}'", link);
+    }
+
+    #[test]
+    fn test_resolve_module_link() {
+        assert_link(
+            "kuchiki",
+            ItemType::Module,
+            "struct.NodeRef.html",
+            Some(ItemType::Struct),
+            "kuchiki::NodeRef",
+        );
+        assert_link(
+            "kuchiki",
+            ItemType::Module,
+            "traits/index.html",
+            Some(ItemType::Module),
+            "kuchiki::traits",
+        );
+    }
+
+    #[test]
+    fn test_resolve_item_link() {
+        assert_link(
+            "kuchiki::NodeRef",
+            ItemType::Struct,
+            "struct.NodeDataRef.html",
+            Some(ItemType::Struct),
+            "kuchiki::NodeDataRef",
+        );
+        assert_link(
+            "kuchiki::iter::Siblings",
+            ItemType::Struct,
+            "../struct.NodeRef.html#method.children",
+            Some(ItemType::Method),
+            "kuchiki::NodeRef::children",
+        );
+        assert_link(
+            "kuchiki::NodeRef",
+            ItemType::Struct,
+            "#method.children",
+            Some(ItemType::Method),
+            "kuchiki::NodeRef::children",
+        );
+    }
+
+    #[test]
+    fn test_resolve_root_link() {
+        assert!(resolve_doc_link(&"kuchiki".to_owned().into(), ItemType::Module, "..").is_err());
+    }
+}
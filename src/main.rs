@@ -18,10 +18,10 @@
 //!    `index` module.
 //!
 //! If we found a documentation item, we use a viewer to open it – see the `viewer` module.
-//! Currently, there are three viewer implementations:  `plain` converts the documentaion to plain
+//! Currently, there are four viewer implementations:  `plain` converts the documentaion to plain
 //! text, `rich` adds some formatting to it.  Both viewers pipe their output through a pager, if
-//! available.  The third viewer, `tui`, provides an interactive interface for browsing the
-//! documentation.
+//! available.  `roff` generates a manpage that can be displayed with `man`.  The fourth viewer,
+//! `tui`, provides an interactive interface for browsing the documentation.
 //!
 //! The documentation is scraped from the HTML files generated by `rustdoc`.  See the `parser`
 //! module for the scraping and the `doc::Doc` struct for the structure of the documentation items.
@@ -39,51 +39,243 @@
 )]
 
 mod args;
+mod cache;
 mod doc;
+mod edit;
+mod history;
 mod index;
+mod link;
 mod parser;
 mod source;
 #[cfg(test)]
 mod test_utils;
 mod viewer;
 
+use std::collections;
 use std::env;
+use std::fmt;
 use std::io;
 use std::path;
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
 
-    let args = args::Args::load()?;
-    let sources = load_sources(&args.source_paths, !args.no_default_sources)?;
-    let doc = if let Some(doc) = sources.find(&args.keyword, None)? {
-        Some(doc)
+    let mut args = args::Args::load()?;
+    if let Some(shell) = args.completions {
+        return print_completions(shell);
+    }
+    if args.theme_preview {
+        return viewer::print_theme_preview(args.viewer_args);
+    }
+    let cache_dir = cache::get_cache_dir(args.cache_dir.as_deref())?;
+    if args.clear_cache {
+        let removed = cache::clear(&cache_dir)?;
+        println!("Removed {} from {}", removed, cache_dir.display());
+        return Ok(());
+    }
+    if args.list_crates {
+        let sources = get_sources(&args, &cache_dir)?;
+        for (krate, url) in sources.crate_root_urls()? {
+            println!("{}\t{}", krate, url);
+        }
+        return Ok(());
+    }
+    if let Some(parent) = &args.complete_members {
+        let sources = get_sources(&args, &cache_dir)?;
+        return complete_members(&sources, parent);
+    }
+    let entry = if let Some(n) = args.history {
+        match select_history_entry(&args, n)? {
+            Some(entry) => Some(entry),
+            // history listed or selection cancelled by user
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+    // If the keyword is omitted, the sources are loaded here to read the item of a single file
+    // source and reused below.
+    let mut sources = None;
+    let keyword = match (&entry, args.keyword.take()) {
+        (Some(entry), _) => entry.name.to_string().into(),
+        (None, Some(keyword)) => keyword,
+        (None, None) => {
+            let file_sources = get_sources(&args, &cache_dir)?;
+            let keyword = get_file_source_keyword(&file_sources)?;
+            sources = Some(file_sources);
+            keyword
+        }
+    };
+    if let Some(entry) = &entry {
+        args.item_type = Some(args::TypeFilter::Item(entry.ty));
+    }
+    let typed_keyword = entry
+        .map(|e| e.keyword)
+        .unwrap_or_else(|| keyword.to_string());
+    let (keyword, wildcard) = parse_wildcard(keyword)?;
+
+    let viewer = if args.emit_manpage {
+        viewer::get_viewer("roff")?
+    } else {
+        args.viewer.take().unwrap_or_else(viewer::get_default)
+    };
+    let picker = args.picker.unwrap_or_else(|| viewer.default_picker());
+
+    if wildcard {
+        let sources = match sources {
+            Some(sources) => sources,
+            None => get_sources(&args, &cache_dir)?,
+        };
+        return list_members(sources, &keyword, viewer, args.viewer_args, picker);
+    }
+
+    let cache = if use_render_cache(&args) {
+        let key = get_cache_key(&args, &keyword, viewer.as_ref())?;
+        let cache = cache::RenderCache::new(&cache_dir, &key)?;
+        if let Some(output) = cache.load() {
+            return viewer.open_rendered(args.viewer_args, &output);
+        }
+        Some(cache)
+    } else {
+        None
+    };
+
+    let sources = match sources {
+        Some(sources) => sources,
+        None => get_sources(&args, &cache_dir)?,
+    };
+
+    if args.all_examples {
+        return print_all_examples(&sources, &keyword, args.full_examples);
+    }
+    if let Some(trait_name) = &args.implementors_of {
+        use anyhow::Context;
+
+        let items = find_implementors(&sources, &keyword, trait_name)?;
+        if picker != args::Picker::Tui {
+            return print_implementors(&items, trait_name);
+        }
+        anyhow::ensure!(
+            !items.is_empty(),
+            "Could not find implementors of {} in {}",
+            trait_name,
+            &keyword
+        );
+        let item = viewer::select_item(&sources, &keyword, &items)?;
+        return if let Some(item) = item {
+            let doc = sources
+                .find(&item.name, Some(item.ty))?
+                .with_context(|| format!("Could not find documentation for {}", &item.name))?;
+            viewer.open(sources, args.viewer_args, &doc)
+        } else {
+            // item selection cancelled by user
+            Ok(())
+        };
+    }
+
+    let doc =
+        if let Some(doc) = source::find_file_doc(keyword.as_ref(), args.max_file_size_bytes())? {
+            Some(doc)
+        } else {
+            sources
+                .find(
+                    &keyword,
+                    args.item_type.and_then(|filter| filter.item_type()),
+                )?
+                .filter(|doc| {
+                    args.item_type
+                        .map(|filter| filter.matches(doc))
+                        .unwrap_or(true)
+                })
+        };
+    let (doc, exact_match) = if let Some(doc) = doc {
+        if args.item_type.is_none() {
+            print_alternatives(&sources, &doc);
+        }
+        (
+            select_duplicate_member(&sources, &keyword, doc, picker)?,
+            true,
+        )
     } else if !args.no_search {
-        search_doc(&sources, &args.keyword)?
+        (
+            search_doc(
+                &sources,
+                &keyword,
+                args.item_type,
+                picker,
+                args.viewer_args.public_only,
+                args.fallback_parent,
+            )?,
+            false,
+        )
     } else {
-        anyhow::bail!("Could not find documentation for {}", &args.keyword);
+        return Err(get_not_found_error(&sources, &keyword));
     };
 
     if let Some(doc) = doc {
-        if args.open {
+        record_history(&args, &typed_keyword, &doc);
+        if args.raw {
+            print!(
+                "{}",
+                get_raw_html(&doc, args.section.as_deref(), args.max_file_size_bytes())?
+            );
+            Ok(())
+        } else if args.dump_doc {
+            use std::fmt::Write as _;
+
+            let mut s = String::new();
+            writeln!(
+                s,
+                "rustdoc version: {}",
+                get_rustdoc_version(&doc, args.max_file_size_bytes())
+                    .unwrap_or_else(|| "unknown".to_owned())
+            )?;
+            doc.dump(&mut s)?;
+            print!("{}", s);
+            Ok(())
+        } else if args.summary {
+            print!("{}", get_summary(&doc)?);
+            Ok(())
+        } else if !args.emit.is_empty() {
+            emit_doc(&args.emit, &args.viewer_args, &doc)
+        } else if args.open {
             if let Some(url) = doc.url.as_ref() {
                 Ok(open::that(url)?)
             } else {
                 anyhow::bail!("Cannot find html document");
             }
+        } else if args.edit {
+            edit::open_in_editor(&doc, args.max_file_size_bytes())
+        } else if args.examples {
+            let mut examples = doc.find_examples()?;
+            if args.full_examples {
+                for example in &mut examples {
+                    if let Some(code) = example.full_code.take() {
+                        example.code = code;
+                    }
+                }
+            }
+            anyhow::ensure!(
+                !examples.is_empty(),
+                "Could not find examples for {}",
+                &keyword
+            );
+            viewer.open_examples(sources, args.viewer_args, &doc, examples)
+        } else if let Some(section) = &args.section {
+            viewer.open_with_section(sources, args.viewer_args, &doc, section)
         } else {
-            let viewer = args.viewer.unwrap_or_else(viewer::get_default);
-            if args.examples {
-                let examples = doc.find_examples()?;
-                anyhow::ensure!(
-                    !examples.is_empty(),
-                    "Could not find examples for {}",
-                    &args.keyword
-                );
-                viewer.open_examples(sources, args.viewer_args, &doc, examples)
-            } else {
-                viewer.open(sources, args.viewer_args, &doc)
+            anyhow::ensure!(
+                !args.viewer_args.watch || sources.can_watch(doc.name.krate()),
+                "The documentation for {} cannot be watched as it is not read from a local \
+                 directory",
+                &doc.name
+            );
+            if let Some(cache) = cache.filter(|_| exact_match) {
+                if let Some(output) = render_cached(&cache, viewer.as_ref(), &args, &doc)? {
+                    return viewer.open_rendered(args.viewer_args, &output);
+                }
             }
+            viewer.open(sources, args.viewer_args, &doc)
         }
     } else {
         // item selection cancelled by user
@@ -91,14 +283,367 @@ fn main() -> anyhow::Result<()> {
     }
 }
 
-/// Load all sources given as a command-line argument and, if enabled, the default sources.
-fn load_sources(sources: &[String], load_default_sources: bool) -> anyhow::Result<source::Sources> {
+/// Prints the full names of the members of the given item, see the `--complete-members` option.
+fn complete_members(sources: &source::Sources, parent: &doc::Name) -> anyhow::Result<()> {
+    use anyhow::Context as _;
+    use std::io::Write as _;
+
+    let members = sources
+        .list_members(&parent.clone().into())?
+        .with_context(|| format!("Could not find documentation for {}", parent))?;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for member in members {
+        writeln!(stdout, "{}", member)?;
+    }
+    Ok(())
+}
+
+/// Returns the version of rustdoc that generated the page of the given item, if it is a local file
+/// and contains the version.
+fn get_rustdoc_version(doc: &doc::Doc, max_file_size: u64) -> Option<String> {
+    let path = url::Url::parse(doc.url.as_ref()?)
+        .ok()?
+        .to_file_path()
+        .ok()?;
+    match parser::html::Parser::from_file_with_limit(path, max_file_size)
+        .and_then(|p| p.find_rustdoc_version())
+    {
+        Ok(version) => version,
+        Err(err) => {
+            log::warn!("Could not read the rustdoc version: {}", err);
+            None
+        }
+    }
+}
+
+/// Returns the HTML of the documentation for the given item or of the given section, see the
+/// `--raw` option.
+fn get_raw_html(
+    doc: &doc::Doc,
+    section: Option<&str>,
+    max_file_size: u64,
+) -> anyhow::Result<String> {
+    use anyhow::Context;
+
+    if let Some(section) = section {
+        if section.eq_ignore_ascii_case("description") {
+            return doc
+                .description
+                .as_ref()
+                .map(|text| text.html.clone())
+                .with_context(|| {
+                    format!("The documentation for {} has no description", &doc.name)
+                });
+        }
+    }
+    let url = doc
+        .url
+        .as_ref()
+        .and_then(|url| url::Url::parse(url).ok())
+        .with_context(|| format!("Cannot find the html document for {}", &doc.name))?;
+    let path = url
+        .to_file_path()
+        .ok()
+        .with_context(|| format!("The documentation for {} is not local", &doc.name))?;
+    let parser = parser::html::Parser::from_file_with_limit(path, max_file_size)?;
+    if let Some(section) = section {
+        parser.find_raw_section(section)?.with_context(|| {
+            format!(
+                "Could not find section {} in the documentation for {}",
+                section, &doc.name
+            )
+        })
+    } else {
+        parser
+            .find_raw_html(url.fragment())?
+            .with_context(|| format!("Could not find the HTML for {}", &doc.name))
+    }
+}
+
+/// Returns a short overview of the given item for the `--summary` option.
+fn get_summary(doc: &doc::Doc) -> anyhow::Result<String> {
+    use std::fmt::Write as _;
+
+    let mut members = Vec::new();
+    for (ty, groups) in &doc.groups {
+        let count: usize = groups.iter().map(|group| group.members.len()).sum();
+        let name = match (ty, count) {
+            (doc::ItemType::Impl, 1) => "trait implementation".to_owned(),
+            (doc::ItemType::Impl, _) => "trait implementations".to_owned(),
+            (_, 1) => ty.name().to_lowercase(),
+            (_, _) => ty.group_name().to_lowercase(),
+        };
+        if count > 0 {
+            members.push(format!("{} {}", count, name));
+        }
+    }
+    let examples = doc.find_examples()?.len();
+
+    let mut s = String::new();
+    writeln!(s, "{} {}", doc.ty.name(), &doc.name)?;
+    writeln!(
+        s,
+        "{}",
+        doc.find_first_sentence()?
+            .as_deref()
+            .unwrap_or("No description")
+    )?;
+    if members.is_empty() {
+        writeln!(s, "No members")?;
+    } else {
+        writeln!(s, "{}", members.join(", "))?;
+    }
+    match examples {
+        1 => writeln!(s, "1 example")?,
+        n => writeln!(s, "{} examples", n)?,
+    }
+    writeln!(s, "Source: {}", doc.url.as_deref().unwrap_or("unknown"))?;
+    Ok(s)
+}
+
+/// Writes the documentation for the given item to the files set with the `--emit` option.
+fn emit_doc(emits: &[args::Emit], args: &args::ViewerArgs, doc: &doc::Doc) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    for emit in emits {
+        let mut output = Vec::new();
+        anyhow::ensure!(
+            emit.viewer.render_to(args, doc, &mut output)?,
+            "The {} viewer cannot write to a file",
+            &emit.format
+        );
+        std::fs::write(&emit.path, output)
+            .with_context(|| format!("Could not write {}", emit.path.display()))?;
+    }
+    Ok(())
+}
+
+/// Whether the render cache should be used for the given arguments, see the `--cache-render`
+/// option.  Only the full documentation page can be cached.
+fn use_render_cache(args: &args::Args) -> bool {
+    args.cache_render
+        && !args.no_cache
+        && !args.dump_doc
+        && !args.summary
+        && args.emit.is_empty()
+        && !args.open
+        && !args.edit
+        && !args.raw
+        && !args.examples
+        && !args.all_examples
+        && args.implementors_of.is_none()
+        && args.section.is_none()
+        && !args.viewer_args.follow
+        && !args.viewer_args.watch
+}
+
+/// Returns the key for the render cache.  It contains the arguments that affect which item is
+/// displayed and how it is rendered, but not the arguments that only affect how the output is
+/// displayed, for example the pager.
+fn get_cache_key(
+    args: &args::Args,
+    keyword: &doc::Name,
+    viewer: &dyn viewer::Viewer,
+) -> anyhow::Result<String> {
+    use std::fmt::Write as _;
+
+    let current_dir = env::current_dir()?;
+    let viewer_args = &args.viewer_args;
+    let fields: &[(&str, &dyn fmt::Debug)] = &[
+        ("version", &env!("CARGO_PKG_VERSION")),
+        ("keyword", &keyword.as_ref()),
+        ("current dir", &current_dir),
+        ("sources", &args.source_paths),
+        ("crate sources", &args.crate_sources),
+        ("indexes", &args.source_indexes),
+        ("no default sources", &args.no_default_sources),
+        ("online", &(args.online && !args.offline)),
+        ("prefer", &args.prefer),
+        ("show hidden", &(args.show_hidden && !args.no_show_hidden)),
+        ("type", &args.item_type),
+        ("viewer", &viewer),
+        ("line length", &viewer::get_line_length(viewer_args)),
+        ("no syntax highlight", &viewer_args.no_syntax_highlight),
+        ("no code highlight", &viewer_args.no_code_highlight),
+        ("theme", &viewer::get_theme_name(viewer_args)),
+        ("notable traits", &viewer_args.notable_traits),
+        ("show siblings", &viewer_args.show_siblings),
+        ("public only", &viewer_args.public_only),
+        ("show empty groups", &viewer_args.show_empty_groups),
+        ("type prefix", &viewer_args.type_prefix),
+        ("no format signatures", &viewer_args.no_format_signatures),
+        ("no separators", &viewer_args.no_separators),
+        ("ascii", &viewer_args.ascii),
+        ("section order", &viewer_args.section_order),
+        ("impl order", &viewer_args.impl_order),
+        ("sort", &viewer_args.sort),
+        ("depth", &viewer_args.depth),
+        ("max definition lines", &viewer_args.max_definition_lines),
+        (
+            "no max definition lines",
+            &viewer_args.no_max_definition_lines,
+        ),
+        ("compact", &viewer_args.compact),
+        ("no compact", &viewer_args.no_compact),
+        ("compact examples", &viewer_args.compact_examples),
+        ("platform warnings", &viewer_args.platform_warnings),
+    ];
+    let mut key = String::new();
+    for (name, value) in fields {
+        writeln!(key, "{}: {:?}", name, value)?;
+    }
+    Ok(key)
+}
+
+/// Prints a notice for every other item with the same name as the given item, for example a
+/// macro with the same name as a module.
+/// Prints a note for every item with the same name but a different type.  The notes are only
+/// hints, so errors are logged instead of aborting.
+fn print_alternatives(sources: &source::Sources, doc: &doc::Doc) {
+    match sources.find_alternatives(doc) {
+        Ok(types) => {
+            for ty in types {
+                eprintln!("{}", format_alternative(&doc.name, ty));
+            }
+        }
+        Err(err) => log::warn!("Could not look up alternatives for {}: {}", &doc.name, err),
+    }
+}
+
+fn format_alternative(name: &doc::Fqn, ty: doc::ItemType) -> String {
+    let ty_name = ty.name().to_lowercase();
+    let article = if ty_name.starts_with(|c| "aeiou".contains(c)) {
+        "an"
+    } else {
+        "a"
+    };
+    format!(
+        "note: {} {} {} also exists, use --type {}",
+        article,
+        ty_name,
+        name,
+        ty.short_name()
+    )
+}
+
+/// Renders the documentation with the given viewer and stores the output in the render cache.
+/// Returns `None` if the viewer does not support rendering to a string.
+fn render_cached(
+    cache: &cache::RenderCache,
+    viewer: &dyn viewer::Viewer,
+    args: &args::Args,
+    doc: &doc::Doc,
+) -> anyhow::Result<Option<String>> {
+    let output = viewer.render_to_string(&args.viewer_args, doc)?;
+    if let Some(output) = &output {
+        let path = doc
+            .url
+            .as_ref()
+            .and_then(|url| url::Url::parse(url).ok())
+            .and_then(|url| url.to_file_path().ok());
+        if let Some(path) = path {
+            if let Err(err) = cache.store(&path, output) {
+                log::warn!("Could not store render cache entry: {}", err);
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Loads the sources set in the arguments, including the per-crate sources and preferences.
+fn get_sources(args: &args::Args, cache_dir: &path::Path) -> anyhow::Result<source::Sources> {
+    let max_file_size = args.max_file_size_bytes();
+    let mut sources = load_sources(
+        &args.source_paths,
+        &args.source_indexes,
+        !args.no_default_sources,
+        args.online && !args.offline,
+        max_file_size,
+        cache_dir,
+    )?;
+    for (pattern, path) in &args.crate_sources {
+        if !path::Path::new(path).exists() {
+            log::info!(
+                "Ignoring source '{}' for '{}' because it does not exist",
+                path,
+                pattern
+            );
+            continue;
+        }
+        let index = get_source_index(&args.source_indexes, path.as_ref());
+        sources.route(pattern, source::get_source(path, index, max_file_size)?);
+    }
+    if let Some(krate) = &args.prefer {
+        sources.prefer(krate);
+    }
+    sources.show_hidden(args.show_hidden && !args.no_show_hidden);
+    Ok(sources)
+}
+
+/// Completes the crate names for the keyword in bash, see `print_completions`.  The crate list is
+/// cached for an hour because loading the sources can take a while.
+const BASH_CRATE_COMPLETION: &str = r#"
+_rusty_man_crates() {
+    local cache="${TMPDIR:-/tmp}/rusty-man-crates-${UID}"
+    if [[ ! -s "$cache" || -n "$(find "$cache" -mmin +60 2>/dev/null)" ]]; then
+        rusty-man --list-crates 2>/dev/null | cut -f1 > "$cache"
+    fi
+    cat "$cache" 2>/dev/null
+}
+
+_rusty_man_keyword() {
+    _rusty-man "$@"
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ "$cur" != -* ]]; then
+        COMPREPLY+=( $(compgen -W "$(_rusty_man_crates)" -- "$cur") )
+    fi
+}
+
+complete -F _rusty_man_keyword -o bashdefault -o default rusty-man
+"#;
+
+/// Prints the completion script for the given shell, see the `--completions` option.
+fn print_completions(shell: structopt::clap::Shell) -> anyhow::Result<()> {
+    use std::io::Write as _;
+    use structopt::StructOpt as _;
+
+    let mut stdout = io::stdout();
+    args::Args::clap().gen_completions_to(env!("CARGO_PKG_NAME"), shell, &mut stdout);
+    if let structopt::clap::Shell::Bash = shell {
+        stdout.write_all(BASH_CRATE_COMPLETION.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Returns the name of the item documented in the first source that is a single HTML file.  This
+/// is used if the keyword is not set.
+fn get_file_source_keyword(sources: &source::Sources) -> anyhow::Result<doc::Name> {
+    use anyhow::Context;
+
+    sources
+        .file_item()
+        .map(|name| (**name).clone())
+        .context("The keyword is required unless a source is a single HTML file")
+}
+
+/// Load all sources given as a command-line argument and, if enabled, the default sources and the
+/// online documentation of the standard library.
+fn load_sources(
+    sources: &[String],
+    indexes: &collections::BTreeMap<String, String>,
+    load_default_sources: bool,
+    online: bool,
+    max_file_size: u64,
+    cache_dir: &path::Path,
+) -> anyhow::Result<source::Sources> {
     let mut vec = Vec::new();
 
     if load_default_sources {
         for path in get_default_sources() {
             if path.is_dir() {
-                vec.push(source::get_source(&path)?);
+                let index = get_source_index(indexes, &path);
+                vec.push(source::get_source(&path, index, max_file_size)?);
             } else {
                 log::info!(
                     "Ignoring default source '{}' because it does not exist",
@@ -109,15 +654,45 @@ fn load_sources(sources: &[String], load_default_sources: bool) -> anyhow::Resul
     }
 
     for s in sources {
-        vec.push(source::get_source(s)?);
+        let index = get_source_index(indexes, s.as_ref());
+        vec.push(source::get_source(s, index, max_file_size)?);
     }
 
     // The last source should be searched first --> reverse source vector
     vec.reverse();
 
+    // The online source is only used if there is no local documentation for std
+    if online && !vec.iter().any(|s| s.has_crate("std")) {
+        vec.push(source::get_std_online_source(cache_dir, max_file_size)?);
+    }
+
     Ok(source::Sources::new(vec))
 }
 
+/// Returns the search index set for the given source in the `[indexes]` table of the
+/// configuration file, if any.
+fn get_source_index<'a>(
+    indexes: &'a collections::BTreeMap<String, String>,
+    source: &path::Path,
+) -> Option<&'a path::Path> {
+    let source = normalize_source_path(source);
+    indexes
+        .iter()
+        .find(|(path, _)| normalize_source_path(path::Path::new(path)) == source)
+        .map(|(_, index)| path::Path::new(index))
+}
+
+/// Expands a leading `~` to the home directory and canonicalizes the given source path so that
+/// differently spelled paths for the same source compare equal.  Paths that cannot be
+/// canonicalized, for example because they do not exist, are only expanded.
+fn normalize_source_path(path: &path::Path) -> path::PathBuf {
+    let path = match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => path::Path::new(&home).join(rest),
+        _ => path.to_owned(),
+    };
+    path.canonicalize().unwrap_or(path)
+}
+
 fn get_default_sources() -> Vec<path::PathBuf> {
     let mut default_sources = Vec::new();
 
@@ -172,15 +747,400 @@ fn get_target_dir() -> path::PathBuf {
         .unwrap_or_else(|| "./target".into())
 }
 
+/// The maximum number of items that are scanned for the `--all-examples` and `--implementors-of`
+/// options.
+const SCAN_LIMIT: usize = 1000;
+
+/// Displays the progress of a crate scan on the standard error if it is a terminal.
+struct Progress {
+    enabled: bool,
+    total: usize,
+}
+
+impl Progress {
+    fn new(total: usize) -> Self {
+        Progress {
+            enabled: termion::is_tty(&io::stderr()),
+            total,
+        }
+    }
+
+    fn update(&self, i: usize, name: &doc::Fqn) {
+        if self.enabled {
+            eprint!(
+                "\r{}[{}/{}] {}",
+                termion::clear::CurrentLine,
+                i + 1,
+                self.total,
+                name
+            );
+        }
+    }
+
+    fn clear(&self) {
+        if self.enabled {
+            eprint!("\r{}", termion::clear::CurrentLine);
+        }
+    }
+}
+
+/// Returns the items of the given crate that match the given filter, limited to `SCAN_LIMIT`
+/// items.  `option` is the name of the option that triggered the scan.
+fn get_scan_items(
+    sources: &source::Sources,
+    krate: &doc::Name,
+    option: &str,
+    filter: impl Fn(&index::IndexItem) -> bool,
+) -> anyhow::Result<Vec<index::IndexItem>> {
+    anyhow::ensure!(
+        krate.is_singleton(),
+        "{} expects a crate name, not {}",
+        option,
+        krate
+    );
+    let items = sources.crate_items(krate.first())?;
+    anyhow::ensure!(
+        !items.is_empty(),
+        "Could not find the search index for the crate {}",
+        krate
+    );
+    let mut items: Vec<_> = items.into_iter().filter(filter).collect();
+    if items.len() > SCAN_LIMIT {
+        eprintln!(
+            "note: only scanning the first {} of {} items",
+            SCAN_LIMIT,
+            items.len()
+        );
+        items.truncate(SCAN_LIMIT);
+    }
+    Ok(items)
+}
+
+/// Prints the examples of all items in the given crate, see the `--all-examples` option.
+///
+/// If the standard error is a terminal, the progress is displayed there.
+fn print_all_examples(
+    sources: &source::Sources,
+    krate: &doc::Name,
+    full_examples: bool,
+) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    let items = get_scan_items(sources, krate, "--all-examples", |_| true)?;
+    let progress = Progress::new(items.len());
+    let mut stdout = io::stdout();
+    let mut example_count = 0;
+    let mut item_count = 0;
+    for (i, item) in items.iter().enumerate() {
+        progress.update(i, &item.name);
+        let doc = match sources.find(&item.name, Some(item.ty)) {
+            Ok(Some(doc)) => doc,
+            Ok(None) => {
+                log::warn!("Could not find documentation for {}", &item.name);
+                continue;
+            }
+            Err(err) => {
+                log::warn!("Could not load documentation for {}: {}", &item.name, err);
+                continue;
+            }
+        };
+        let examples = match doc.find_examples() {
+            Ok(examples) if !examples.is_empty() => examples,
+            Ok(_) => continue,
+            Err(err) => {
+                log::warn!("Could not find examples for {}: {}", &item.name, err);
+                continue;
+            }
+        };
+
+        progress.clear();
+        writeln!(stdout, "{} ({})", &doc.name, doc.ty.name())?;
+        writeln!(stdout)?;
+        for example in examples {
+            if let Some(description) = &example.description {
+                for line in description.plain.trim().lines() {
+                    writeln!(stdout, "    {}", line)?;
+                }
+                writeln!(stdout)?;
+            }
+            if !example.tags.is_empty() {
+                writeln!(stdout, "        ({})", example.tags.join(", "))?;
+            }
+            let code = match example.full_code {
+                Some(code) if full_examples => code,
+                _ => example.code,
+            };
+            for line in code.lines() {
+                writeln!(stdout, "        {}", line)?;
+            }
+            writeln!(stdout)?;
+            example_count += 1;
+        }
+        item_count += 1;
+    }
+    progress.clear();
+
+    writeln!(
+        stdout,
+        "Found {} examples in {} of {} items",
+        example_count,
+        item_count,
+        items.len()
+    )?;
+    Ok(())
+}
+
+/// Returns the structs, enums and unions in the given crate that implement the given trait, see the
+/// `--implementors-of` option.
+///
+/// The trait is matched by its last path segment, so `Display` and `std::fmt::Display` are
+/// equivalent.  The description of the returned items is the header of the matching impl block.
+/// If the standard error is a terminal, the progress is displayed there.
+fn find_implementors(
+    sources: &source::Sources,
+    krate: &doc::Name,
+    trait_name: &str,
+) -> anyhow::Result<Vec<index::IndexItem>> {
+    let trait_name = trait_name.rsplit("::").next().unwrap_or(trait_name);
+    let items = get_scan_items(sources, krate, "--implementors-of", |item| {
+        matches!(
+            item.ty,
+            doc::ItemType::Struct | doc::ItemType::Enum | doc::ItemType::Union
+        )
+    })?;
+    let progress = Progress::new(items.len());
+    let mut implementors = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        progress.update(i, &item.name);
+        let doc = match sources.find(&item.name, Some(item.ty)) {
+            Ok(Some(doc)) => doc,
+            Ok(None) => {
+                log::warn!("Could not find documentation for {}", &item.name);
+                continue;
+            }
+            Err(err) => {
+                log::warn!("Could not load documentation for {}: {}", &item.name, err);
+                continue;
+            }
+        };
+        let header = doc
+            .groups
+            .get(&doc::ItemType::Impl)
+            .into_iter()
+            .flatten()
+            .flat_map(|group| &group.members)
+            .filter_map(|member| member.definition.as_ref())
+            .map(|definition| definition.lines().next().unwrap_or_default().trim())
+            .find(|header| {
+                !is_negative_impl(header) && viewer::get_trait_name(header) == Some(trait_name)
+            });
+        if let Some(header) = header {
+            let mut item = item.clone();
+            item.description = header.to_owned();
+            implementors.push(item);
+        }
+    }
+    progress.clear();
+    Ok(implementors)
+}
+
+/// Whether the given impl header is a negative implementation like `impl !Send for Foo`.
+fn is_negative_impl(header: &str) -> bool {
+    header
+        .split_once("impl")
+        .map(|(_, s)| s.split(" for ").next().unwrap_or_default().contains('!'))
+        .unwrap_or_default()
+}
+
+/// Prints the given implementors of a trait, see the `--implementors-of` option.
+fn print_implementors(items: &[index::IndexItem], trait_name: &str) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    let mut stdout = io::stdout();
+    for item in items {
+        writeln!(stdout, "{} ({})", &item.name, item.ty.name())?;
+        writeln!(stdout, "    {}", &item.description)?;
+        writeln!(stdout)?;
+    }
+    writeln!(
+        stdout,
+        "Found {} implementors of {}",
+        items.len(),
+        trait_name
+    )?;
+    Ok(())
+}
+
+/// Checks whether the last segment of the given keyword is the wildcard `*`, e. g.
+/// `std::vec::Vec::*`, and returns the name of the parent item in that case.  A literal `*` can
+/// be looked up by escaping it as `\*`.
+fn parse_wildcard(keyword: doc::Name) -> anyhow::Result<(doc::Name, bool)> {
+    use anyhow::Context;
+
+    match keyword.last() {
+        "*" => {
+            let parent = keyword.parent().with_context(|| {
+                format!(
+                    "Expected an item before the wildcard, e. g. std::vec::Vec::*, got {}",
+                    keyword
+                )
+            })?;
+            Ok((parent, true))
+        }
+        "\\*" => {
+            let name = keyword
+                .parent()
+                .map(|parent| parent.child("*"))
+                .unwrap_or_else(|| "*".to_owned().into());
+            Ok((name, false))
+        }
+        _ => Ok((keyword, false)),
+    }
+}
+
+/// Lists the members of the given item, see `parse_wildcard`.  With the tui picker, the user can
+/// select a member to open.
+fn list_members(
+    sources: source::Sources,
+    name: &doc::Name,
+    viewer: Box<dyn viewer::Viewer>,
+    viewer_args: args::ViewerArgs,
+    picker: args::Picker,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    let doc = sources
+        .find(name, None)?
+        .with_context(|| format!("Could not find documentation for {}", name))?;
+    let items = get_member_items(&doc, viewer_args.public_only);
+    anyhow::ensure!(!items.is_empty(), "{} does not have any members", name);
+
+    if picker != args::Picker::Tui {
+        for item in &items {
+            println!("{}", item);
+        }
+        return Ok(());
+    }
+    if let Some(item) = viewer::select_item(&sources, name, &items)? {
+        let doc = sources
+            .find(&item.name, Some(item.ty))?
+            .with_context(|| format!("Could not find documentation for {}", &item.name))?;
+        viewer.open(sources, viewer_args, &doc)
+    } else {
+        // item selection cancelled by user
+        Ok(())
+    }
+}
+
+/// Returns the members of the given item except for the trait implementations as index items
+/// with a short description.  If `public_only` is set, only public members are
+/// returned.
+fn get_member_items(doc: &doc::Doc, public_only: bool) -> Vec<index::IndexItem> {
+    doc.groups
+        .iter()
+        .filter(|(ty, _)| **ty != doc::ItemType::Impl)
+        .flat_map(|(ty, groups)| groups.iter().map(move |group| (*ty, group)))
+        .flat_map(|(ty, group)| group.members.iter().map(move |member| (ty, member)))
+        .filter(|(_, member)| !public_only || member.is_public())
+        .map(|(ty, member)| index::IndexItem {
+            name: member.name.clone(),
+            ty,
+            description: get_short_description(member),
+            source_priority: 0,
+            aliases: Vec::new(),
+        })
+        .collect()
+}
+
+/// Returns the first sentence of the description of the given item, or the full description
+/// without line breaks if it does not consist of paragraphs, e. g. for module members.
+fn get_short_description(doc: &doc::Doc) -> String {
+    doc.find_first_sentence()
+        .ok()
+        .flatten()
+        .or_else(|| {
+            doc.description
+                .as_ref()
+                .map(|d| d.plain.split_whitespace().collect::<Vec<_>>().join(" "))
+        })
+        .unwrap_or_default()
+}
+
+/// Lets the user select a member if the parent of the given member has multiple members with the
+/// same name, for example methods from different impl blocks.
+///
+/// The member is returned unchanged if it has no duplicates, if the keyword already contains the
+/// suffix that rustdoc adds to colliding ids (e. g. `foo-1`) or if we are not on a TTY.
+fn select_duplicate_member(
+    sources: &source::Sources,
+    keyword: &doc::Name,
+    doc: doc::Doc,
+    picker: args::Picker,
+) -> anyhow::Result<Option<doc::Doc>> {
+    use doc::ItemType;
+
+    let is_member = matches!(
+        doc.ty,
+        ItemType::StructField
+            | ItemType::Variant
+            | ItemType::AssocType
+            | ItemType::AssocConst
+            | ItemType::Method
+    );
+    if !is_member || keyword.last() != doc.name.last() || !termion::is_tty(&io::stdin()) {
+        return Ok(Some(doc));
+    }
+    let members = sources.find_duplicate_members(&doc.name)?;
+    if members.len() < 2 {
+        return Ok(Some(doc));
+    }
+    log::info!(
+        "Found {} members with the name '{}'",
+        members.len(),
+        &doc.name
+    );
+    let item = match picker {
+        args::Picker::Prompt => select_item(&members, keyword)?,
+        args::Picker::Tui => viewer::select_item(sources, keyword, &members)?,
+    };
+    if let Some(item) = item {
+        use anyhow::Context;
+
+        sources
+            .find(&item.name, Some(item.ty))?
+            .with_context(|| format!("Could not find documentation for {}", &item.name))
+            .map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 /// Use the search index to find the documentation for an item that partially matches the given
 /// keyword.
-fn search_doc(sources: &source::Sources, name: &doc::Name) -> anyhow::Result<Option<doc::Doc>> {
-    if let Some(item) = search_item(sources, name)? {
+fn search_doc(
+    sources: &source::Sources,
+    name: &doc::Name,
+    filter: Option<args::TypeFilter>,
+    picker: args::Picker,
+    public_only: bool,
+    fallback_parent: bool,
+) -> anyhow::Result<Option<doc::Doc>> {
+    if let Some(item) = search_item(sources, name, filter, picker, public_only, fallback_parent)? {
         use anyhow::Context;
 
         let doc = sources
             .find(&item.name, Some(item.ty))?
             .with_context(|| format!("Could not find documentation for {}", &item.name))?;
+        if let Some(filter) = filter {
+            // Candidates whose documentation could not be loaded are not filtered by
+            // `search_item`, so we check the filter again.
+            anyhow::ensure!(
+                filter.matches(&doc),
+                "{} does not match the type {}",
+                &item.name,
+                filter
+            );
+        }
         Ok(Some(doc))
     } else {
         log::info!(
@@ -191,22 +1151,286 @@ fn search_doc(sources: &source::Sources, name: &doc::Name) -> anyhow::Result<Opt
     }
 }
 
-/// Use the search index to find an item that partially matches the given keyword.
+/// Use the search index to find an item matching the given type filter, if set, that partially
+/// matches the given keyword.
 fn search_item(
     sources: &source::Sources,
     name: &doc::Name,
+    filter: Option<args::TypeFilter>,
+    picker: args::Picker,
+    public_only: bool,
+    fallback_parent: bool,
 ) -> anyhow::Result<Option<index::IndexItem>> {
-    let items = sources.search(name)?;
+    let mut items = sources.search(name)?;
+    if let Some(filter) = filter {
+        items.retain(|item| filter.matches_type(item.ty));
+        if filter == args::TypeFilter::AssocFn {
+            retain_assoc_fns(sources, &mut items);
+        }
+    }
+    if public_only {
+        sources.retain_public(&mut items);
+    }
     if items.is_empty() {
-        Err(anyhow::anyhow!(
-            "Could not find documentation for {}",
-            &name
-        ))
+        if filter.is_none() {
+            if let Some(parent) = find_member_parent(sources, name, fallback_parent)? {
+                return Ok(Some(parent));
+            }
+        }
+        Err(get_not_found_error(sources, name))
     } else if items.len() == 1 {
         log::info!("Search returned a single item: '{}'", &items[0].name);
         Ok(Some(items[0].clone()))
     } else {
-        select_item(&items, name)
+        match picker {
+            args::Picker::Prompt => select_item(&items, name),
+            args::Picker::Tui => viewer::select_item(sources, name, &items),
+        }
+    }
+}
+
+/// Removes the items that are not associated functions from the given search results.  The search
+/// index does not contain the definitions, so the documentation of every item is loaded.  Items
+/// whose documentation cannot be loaded are kept.
+fn retain_assoc_fns(sources: &source::Sources, items: &mut Vec<index::IndexItem>) {
+    items.retain(|item| match sources.find(&item.name, Some(item.ty)) {
+        Ok(doc) => doc.map(|doc| doc.is_assoc_fn()).unwrap_or(false),
+        Err(err) => {
+            log::warn!(
+                "Could not load the documentation for {}: {}",
+                &item.name,
+                err
+            );
+            true
+        }
+    });
+}
+
+/// The maximum number of similar items that are listed if an item could not be found.
+const MAX_SIMILAR_ITEMS: usize = 5;
+
+/// Returns the error for an item that could not be found.  If the keyword contains a crate name,
+/// the error explains whether the crate is missing or lists similar items of the crate.
+fn get_not_found_error(sources: &source::Sources, name: &doc::Name) -> anyhow::Error {
+    let mut msg = format!("Could not find documentation for {}", name);
+    if name.is_singleton() {
+        return anyhow::anyhow!(msg);
+    }
+
+    let krate = name.first();
+    if !sources.has_crate(krate) {
+        msg.push_str(&format!(
+            "\n\nThe crate {} is not documented in any of the sources.  ",
+            krate
+        ));
+        if source::is_std_crate(krate) {
+            msg.push_str("Use the --online option to download its documentation.");
+        } else {
+            msg.push_str(&format!(
+                "Run `cargo doc -p {}` to generate its documentation.",
+                krate
+            ));
+        }
+        return anyhow::anyhow!(msg);
+    }
+
+    let similar = match sources.crate_items(krate) {
+        Ok(items) => get_similar_items(items, name.last()),
+        Err(err) => {
+            log::warn!("Could not load the items of the crate {}: {}", krate, err);
+            Vec::new()
+        }
+    };
+    msg.push_str(&format!(
+        "\n\nThe crate {} does not contain this item.",
+        krate
+    ));
+    if !similar.is_empty() {
+        msg.push_str("  Similar items:");
+        for item in similar {
+            msg.push_str(&format!("\n    {} ({})", &item.name, item.ty.name()));
+        }
+    }
+    anyhow::anyhow!(msg)
+}
+
+/// If the keyword refers to a member of an existing item that could not be found, e. g. because of
+/// a typo in the member name, returns the parent item if it should be opened instead.  The parent
+/// is opened without asking if `fallback_parent` is set.  Otherwise, the user is asked if stdin is
+/// a TTY.  Returns `None` if the parent item does not exist or if it is a module.
+fn find_member_parent(
+    sources: &source::Sources,
+    name: &doc::Name,
+    fallback_parent: bool,
+) -> anyhow::Result<Option<index::IndexItem>> {
+    use std::io::Write;
+
+    let parent = match name.parent() {
+        Some(parent) if !parent.is_singleton() => parent,
+        _ => return Ok(None),
+    };
+    let doc = match sources.find(&parent, None)? {
+        Some(doc) if doc.ty != doc::ItemType::Module => doc,
+        _ => return Ok(None),
+    };
+    let msg = get_missing_member_message(&doc, name);
+    let item = index::IndexItem {
+        name: doc.name.clone(),
+        ty: doc.ty,
+        description: String::new(),
+        source_priority: 0,
+        aliases: Vec::new(),
+    };
+
+    if fallback_parent {
+        eprintln!("{}\n\nOpening {} instead.", msg, &doc.name);
+        return Ok(Some(item));
+    }
+    anyhow::ensure!(
+        termion::is_tty(&io::stdin()),
+        "{}\n\nUse the --fallback-parent option to open {} instead.",
+        msg,
+        &doc.name
+    );
+
+    eprint!("{}\n\nOpen {} instead? [Y/n] ", msg, &doc.name);
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if matches!(input.trim().to_lowercase().as_str(), "" | "y" | "yes") {
+        Ok(Some(item))
+    } else {
+        Err(anyhow::anyhow!(msg))
+    }
+}
+
+/// Returns the message for a member that could not be found on the page of its parent item,
+/// including the similar members of the parent.
+fn get_missing_member_message(parent: &doc::Doc, name: &doc::Name) -> String {
+    let mut msg = format!(
+        "Could not find documentation for {}\n\n{} {} does not have a member named {}.",
+        name,
+        parent.ty.name(),
+        &parent.name,
+        name.last()
+    );
+    let similar = get_similar_items(get_member_items(parent, false), name.last());
+    if !similar.is_empty() {
+        msg.push_str("  Similar members:");
+        for item in similar {
+            msg.push_str(&format!("\n    {} ({})", &item.name, item.ty.name()));
+        }
+    }
+    msg
+}
+
+/// Returns the items whose last name segment is closest to the given name, ignoring the case, with
+/// at most one edit per three characters.
+fn get_similar_items(items: Vec<index::IndexItem>, name: &str) -> Vec<index::IndexItem> {
+    let name = name.to_lowercase();
+    let max_distance = name.chars().count() / 3 + 1;
+    let mut items: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            (
+                get_edit_distance(&item.name.last().to_lowercase(), &name),
+                item,
+            )
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    items.sort_by(|(d1, i1), (d2, i2)| (d1, &i1.name).cmp(&(d2, &i2.name)));
+    items
+        .into_iter()
+        .map(|(_, item)| item)
+        .take(MAX_SIMILAR_ITEMS)
+        .collect()
+}
+
+/// Returns the Levenshtein distance between the given strings.
+fn get_edit_distance(s1: &str, s2: &str) -> usize {
+    let s2: Vec<_> = s2.chars().collect();
+    let mut row: Vec<_> = (0..=s2.len()).collect();
+    for (i, c1) in s1.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, c2) in s2.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if c1 == *c2 {
+                previous
+            } else {
+                1 + previous.min(current).min(row[j])
+            };
+            previous = current;
+        }
+    }
+    row[s2.len()]
+}
+
+/// Adds the given item to the history unless this is disabled in the configuration file.  Errors
+/// are only logged as the history is not required for opening the documentation.
+fn record_history(args: &args::Args, keyword: &str, doc: &doc::Doc) {
+    // Only items that are opened for reading are recorded, not the output of the dump and export
+    // options.
+    let is_export =
+        args.raw || args.dump_doc || args.summary || args.emit_manpage || !args.emit.is_empty();
+    if args.record_history == Some(false) || is_export {
+        return;
+    }
+    let size = args.history_size.unwrap_or(history::DEFAULT_SIZE);
+    if let Err(err) = history::History::new(size).and_then(|h| h.add(keyword, &doc.name, doc.ty)) {
+        log::warn!("Could not update the history: {}", err);
+    }
+}
+
+/// Returns the history entry with the given number, or lists the history and lets the user select
+/// an entry if the number is not set, see the `--history` option.
+fn select_history_entry(
+    args: &args::Args,
+    n: Option<usize>,
+) -> anyhow::Result<Option<history::Entry>> {
+    use std::io::Write;
+    use std::str::FromStr;
+
+    let size = args.history_size.unwrap_or(history::DEFAULT_SIZE);
+    let mut entries = history::History::new(size)?.load()?;
+    if let Some(n) = n {
+        anyhow::ensure!(
+            n > 0 && n <= entries.len(),
+            "The history does not contain entry {} (found {} entries)",
+            n,
+            entries.len()
+        );
+        return Ok(Some(entries.swap_remove(n - 1)));
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let width = entries.len().to_string().len();
+    for (i, entry) in entries.iter().enumerate() {
+        println!(
+            "[ {:width$} ] {} {}\t{}",
+            i + 1,
+            entry.ty.name(),
+            entry.name,
+            entry.format_age(now),
+            width = width
+        );
+    }
+    if entries.is_empty() || !termion::is_tty(&io::stdin()) {
+        return Ok(None);
+    }
+
+    println!();
+    print!("> ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    match usize::from_str(input.trim()) {
+        Ok(i) if i > 0 && i <= entries.len() => Ok(Some(entries.swap_remove(i - 1))),
+        _ => Ok(None),
     }
 }
 
@@ -238,7 +1462,7 @@ fn select_item(
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
     if let Ok(i) = usize::from_str(input.trim()) {
-        Ok(items.get(i).map(Clone::clone))
+        Ok(items.get(i).cloned())
     } else {
         Ok(None)
     }
@@ -246,13 +1470,136 @@ fn select_item(
 
 #[cfg(test)]
 mod tests {
+    use crate::doc;
+    use crate::index;
+    use crate::parser::html;
     use crate::source;
     use crate::test_utils::{with_rustdoc, Format};
 
+    fn get_source(path: &std::path::Path) -> Box<dyn source::Source> {
+        source::get_source(path, None, html::DEFAULT_MAX_FILE_SIZE).unwrap()
+    }
+
+    fn get_sources(path: &std::path::Path) -> source::Sources {
+        source::Sources::new(vec![get_source(path)])
+    }
+
+    #[test]
+    fn test_parse_wildcard() {
+        let parse = |s: &str| {
+            super::parse_wildcard(s.to_owned().into())
+                .map(|(name, wildcard)| (name.to_string(), wildcard))
+                .ok()
+        };
+        assert_eq!(
+            Some(("std::vec::Vec".to_owned(), true)),
+            parse("std::vec::Vec::*")
+        );
+        assert_eq!(Some(("log".to_owned(), true)), parse("log::*"));
+        assert_eq!(
+            Some(("std::ops::*".to_owned(), false)),
+            parse("std::ops::\\*")
+        );
+        assert_eq!(Some(("log::Log".to_owned(), false)), parse("log::Log"));
+        assert_eq!(None, parse("*"));
+    }
+
+    #[test]
+    fn test_get_edit_distance() {
+        assert_eq!(0, super::get_edit_distance("level", "level"));
+        assert_eq!(1, super::get_edit_distance("level", "levl"));
+        assert_eq!(2, super::get_edit_distance("record", "rceord"));
+        assert_eq!(3, super::get_edit_distance("", "log"));
+    }
+
+    #[test]
+    fn test_get_not_found_error() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let sources = get_sources(path);
+            let get_error =
+                |s: &str| super::get_not_found_error(&sources, &s.to_owned().into()).to_string();
+            // The similar items are only available if the search index can be parsed.
+            let mut expected = "Could not find documentation for log::Recrd\n\nThe crate log does \
+                                not contain this item."
+                .to_owned();
+            if !sources.crate_items("log").unwrap().is_empty() {
+                expected.push_str("  Similar items:\n    log::Record (Struct)");
+            }
+            assert_eq!(expected, get_error("log::Recrd"));
+            assert_eq!(
+                "Could not find documentation for log::zzzzzz\n\nThe crate log does not contain \
+                 this item.",
+                get_error("log::zzzzzz")
+            );
+            assert_eq!(
+                "Could not find documentation for foo::Bar\n\nThe crate foo is not documented in \
+                 any of the sources.  Run `cargo doc -p foo` to generate its documentation.",
+                get_error("foo::Bar")
+            );
+            assert_eq!("Could not find documentation for Recrd", get_error("Recrd"));
+        });
+    }
+
+    #[test]
+    fn test_find_member_parent() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let sources = get_sources(path);
+            let find = |s: &str| {
+                super::find_member_parent(&sources, &s.to_owned().into(), true)
+                    .unwrap()
+                    .map(|item| (item.name.to_string(), item.ty))
+            };
+            assert_eq!(
+                Some(("anyhow::Error".to_owned(), doc::ItemType::Struct)),
+                find("anyhow::Error::contxt")
+            );
+            assert_eq!(None, find("anyhow::Eror::context"));
+            assert_eq!(None, find("anyhow::Contxt"));
+            assert_eq!(None, find("anyhow"));
+
+            let parent = sources
+                .find(&"anyhow::Error".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                "Could not find documentation for anyhow::Error::contxt\n\nStruct anyhow::Error \
+                 does not have a member named contxt.  Similar members:\n    \
+                 anyhow::Error::context (Method)",
+                super::get_missing_member_message(
+                    &parent,
+                    &"anyhow::Error::contxt".to_owned().into()
+                )
+            );
+        });
+    }
+
+    #[test]
+    fn test_get_summary() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let sources = get_sources(path);
+            let doc = sources
+                .find(&"anyhow::Error".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            let summary = super::get_summary(&doc).unwrap();
+            let lines: Vec<_> = summary.lines().collect();
+            assert_eq!(5, lines.len());
+            assert_eq!("Struct anyhow::Error", lines[0]);
+            assert_eq!(
+                "The Error type, a wrapper around a dynamic error type.",
+                lines[1]
+            );
+            assert!(lines[2].contains(" methods, "), "{}", lines[2]);
+            assert!(lines[2].ends_with(" trait implementations"), "{}", lines[2]);
+            assert_eq!("1 example", lines[3]);
+            assert!(lines[4].starts_with("Source: file://"), "{}", lines[4]);
+        });
+    }
+
     #[test]
     fn test_find_doc() {
         with_rustdoc("*", Format::all(), |_, _, path| {
-            let sources = source::Sources::new(vec![source::get_source(path).unwrap()]);
+            let sources = get_sources(path);
 
             assert!(sources
                 .find(&"kuchiki".to_owned().into(), None)
@@ -276,4 +1623,151 @@ mod tests {
                 .is_none());
         });
     }
+
+    #[test]
+    fn test_find_file_doc() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let file = path.join("kuchiki").join("struct.NodeRef.html");
+            let doc = source::find_file_doc(file.to_str().unwrap(), html::DEFAULT_MAX_FILE_SIZE)
+                .unwrap()
+                .unwrap();
+            assert_eq!("kuchiki::NodeRef", doc.name.as_ref());
+            assert_eq!(doc::ItemType::Struct, doc.ty);
+
+            let url = url::Url::from_file_path(file.canonicalize().unwrap()).unwrap();
+            let doc = source::find_file_doc(
+                &format!("{}#method.children", url),
+                html::DEFAULT_MAX_FILE_SIZE,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!("kuchiki::NodeRef::children", doc.name.as_ref());
+            assert_eq!(doc::ItemType::Method, doc.ty);
+
+            let file = path.join("kuchiki").join("traits").join("index.html");
+            let doc = source::find_file_doc(file.to_str().unwrap(), html::DEFAULT_MAX_FILE_SIZE)
+                .unwrap()
+                .unwrap();
+            assert_eq!("kuchiki::traits", doc.name.as_ref());
+            assert_eq!(doc::ItemType::Module, doc.ty);
+
+            assert!(
+                source::find_file_doc("kuchiki::NodeRef", html::DEFAULT_MAX_FILE_SIZE)
+                    .unwrap()
+                    .is_none()
+            );
+            let file = path.join("kuchiki").join("struct.Missing.html");
+            let err = source::find_file_doc(file.to_str().unwrap(), html::DEFAULT_MAX_FILE_SIZE)
+                .unwrap_err();
+            assert!(err.to_string().starts_with("File not found"));
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("page.html");
+        std::fs::write(&file, "<html></html>").unwrap();
+        let err =
+            source::find_file_doc(file.to_str().unwrap(), html::DEFAULT_MAX_FILE_SIZE).unwrap_err();
+        assert!(err.to_string().starts_with("Not a rustdoc page"));
+    }
+
+    #[test]
+    fn test_prefer() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let name = "Error".to_owned().into();
+            let mut sources = get_sources(path);
+            let items = sources.search(&name).unwrap();
+            assert_eq!("anyhow", items[0].name.krate());
+            assert!(items.iter().any(|item| item.name.krate() == "rand_core"));
+
+            sources.prefer("rand_core");
+            let items = sources.search(&name).unwrap();
+            assert_eq!("rand_core", items[0].name.krate());
+            assert!(items.iter().any(|item| item.name.krate() == "anyhow"));
+        });
+    }
+
+    #[test]
+    fn test_search_dedup() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let sources = source::Sources::new(vec![get_source(path), get_source(path)]);
+            let items = sources.search(&"NodeDataRef".to_owned().into()).unwrap();
+            assert_eq!(1, items.len());
+            assert_eq!(2, items[0].source_priority);
+        });
+    }
+
+    #[test]
+    fn test_format_alternative() {
+        let name = doc::Name::from("log::error".to_owned()).into();
+        assert_eq!(
+            "note: a module log::error also exists, use --type mod",
+            super::format_alternative(&name, doc::ItemType::Module)
+        );
+        assert_eq!(
+            "note: an enum log::error also exists, use --type enum",
+            super::format_alternative(&name, doc::ItemType::Enum)
+        );
+    }
+
+    #[test]
+    fn test_is_negative_impl() {
+        assert!(super::is_negative_impl("impl !Send for NodeRef"));
+        assert!(super::is_negative_impl("impl<T> !Sync for Foo<T>"));
+        assert!(!super::is_negative_impl("impl Send for Error"));
+        assert!(!super::is_negative_impl("impl<T: ?Sized> Borrow<T> for T"));
+    }
+
+    #[test]
+    fn test_retain_assoc_fns() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let sources = get_sources(path);
+            let item = |name: &str, ty| index::IndexItem {
+                name: name.to_owned().into(),
+                ty,
+                description: String::new(),
+                source_priority: 0,
+                aliases: Vec::new(),
+            };
+            let mut items = vec![
+                item("kuchiki::NodeRef::new_element", doc::ItemType::Method),
+                item("kuchiki::NodeRef::children", doc::ItemType::Method),
+                item("rand_core::SeedableRng::from_seed", doc::ItemType::TyMethod),
+                item("rand_core::RngCore::next_u32", doc::ItemType::TyMethod),
+            ];
+            super::retain_assoc_fns(&sources, &mut items);
+            let names: Vec<_> = items.iter().map(|item| item.name.as_ref()).collect();
+            assert_eq!(
+                vec![
+                    "kuchiki::NodeRef::new_element",
+                    "rand_core::SeedableRng::from_seed"
+                ],
+                names
+            );
+        });
+    }
+
+    #[test]
+    fn test_get_source_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc");
+        std::fs::create_dir(&path).unwrap();
+        let mut indexes = std::collections::BTreeMap::new();
+        indexes.insert(
+            dir.path().join(".").join("doc").display().to_string(),
+            "search-index.js".to_owned(),
+        );
+        assert_eq!(
+            Some(std::path::Path::new("search-index.js")),
+            super::get_source_index(&indexes, &path)
+        );
+        assert_eq!(None, super::get_source_index(&indexes, dir.path()));
+
+        if let Some(home) = std::env::var_os("HOME") {
+            indexes.insert("~/doc".to_owned(), "home-index.js".to_owned());
+            assert_eq!(
+                Some(std::path::Path::new("home-index.js")),
+                super::get_source_index(&indexes, &std::path::Path::new(&home).join("doc"))
+            );
+        }
+    }
 }
@@ -13,12 +13,15 @@
 
 mod util;
 
+use std::fs;
 use std::path;
+use std::time;
 
 use anyhow::Context;
 use markup5ever::local_name;
 
 use crate::doc;
+use crate::link;
 
 use util::NodeRefExt;
 
@@ -27,15 +30,46 @@ pub struct Parser {
     path: Option<path::PathBuf>,
 }
 
+/// The default maximum size of the HTML files that are parsed in bytes, see
+/// `Parser::from_file_with_limit`.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 50 << 20;
+
+/// The duration of parsing a file after which a warning is logged.
+const SLOW_PARSE_DURATION: time::Duration = time::Duration::from_secs(2);
+
+/// Returns an error if the given file is larger than the given number of bytes.
+fn check_file_size(path: &path::Path, max_size: u64) -> anyhow::Result<()> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("Could not read HTML file {}", path.display()))?
+        .len();
+    anyhow::ensure!(
+        size <= max_size,
+        "The HTML file {} is too large ({} MB, the limit is {} MB, see --max-file-size)",
+        path.display(),
+        size >> 20,
+        max_size >> 20
+    );
+    Ok(())
+}
+
 impl Parser {
     pub fn from_file(path: impl AsRef<path::Path>) -> anyhow::Result<Parser> {
         use kuchiki::traits::TendrilSink;
 
         log::info!("Reading HTML from file '{}'", path.as_ref().display());
+        let start = time::Instant::now();
         let document = kuchiki::parse_html()
             .from_utf8()
             .from_file(path.as_ref())
             .context("Could not read HTML file")?;
+        let duration = start.elapsed();
+        if duration > SLOW_PARSE_DURATION {
+            log::warn!(
+                "Parsing the HTML file '{}' took {:.1} s",
+                path.as_ref().display(),
+                duration.as_secs_f64()
+            );
+        }
         log::info!("HTML file parsed successfully");
 
         Ok(Parser {
@@ -44,6 +78,17 @@ impl Parser {
         })
     }
 
+    /// Parses the given file if it is not larger than the given number of bytes, see the
+    /// `--max-file-size` option.  Larger files are rejected as kuchiki would take a long time and
+    /// a lot of memory to parse them.
+    pub fn from_file_with_limit(
+        path: impl AsRef<path::Path>,
+        max_size: u64,
+    ) -> anyhow::Result<Parser> {
+        check_file_size(path.as_ref(), max_size)?;
+        Parser::from_file(path)
+    }
+
     pub fn from_string(s: impl Into<String>) -> anyhow::Result<Parser> {
         use kuchiki::traits::TendrilSink;
 
@@ -61,9 +106,8 @@ impl Parser {
     }
 
     pub fn find_item(&self, item: &str) -> anyhow::Result<Option<String>> {
-        let block = select(&self.document, "ul.all-items li a")?;
-        let mut items = if block.iter.clone().count() > 0 {
-            block
+        let mut items = if select_first(&self.document, "ul.all-items li a")?.is_some() {
+            select(&self.document, "ul.all-items li a")?
         } else {
             select(&self.document, "ul.docblock li a")?
         };
@@ -73,40 +117,110 @@ impl Parser {
         Ok(item)
     }
 
+    /// Returns the paths of all items in the item list with the given name.
+    pub fn find_items(&self, item: &str) -> anyhow::Result<Vec<String>> {
+        let items = if select_first(&self.document, "ul.all-items li a")?.is_some() {
+            select(&self.document, "ul.all-items li a")?
+        } else {
+            select(&self.document, "ul.docblock li a")?
+        };
+        Ok(items
+            .filter(|e| e.text_contents() == item)
+            .filter_map(|e| e.get_attribute("href"))
+            .collect())
+    }
+
     pub fn find_member(&self, name: &doc::Fqn) -> anyhow::Result<Option<doc::ItemType>> {
         let member = get_member(&self.document, name.last())?;
         if let Some(member) = member {
             let id = member
                 .get_attribute("id")
                 .with_context(|| format!("The member {} does not have an ID", name))?;
-            let ty = id.splitn(2, '.').next().unwrap().parse()?;
+            let (ty, _) = parse_member_id(&id)
+                .with_context(|| format!("The member {} has an invalid ID: {}", name, id))?;
             Ok(Some(ty))
         } else {
             Ok(None)
         }
     }
 
+    /// Returns the members of the item on this page that have the same name as the given member,
+    /// together with their type and the title of the impl block that contains them.
+    ///
+    /// If multiple members have the same name, for example methods from different impl blocks,
+    /// rustdoc disambiguates their ids with a numeric suffix, e. g. `method.foo-1`.  The names of
+    /// the returned members keep this suffix so that they can be passed to `parse_member_doc`.
+    pub fn find_duplicate_members(
+        &self,
+        name: &doc::Fqn,
+    ) -> anyhow::Result<Vec<(doc::Fqn, doc::ItemType, Option<String>)>> {
+        let parent = name
+            .parent()
+            .with_context(|| format!("{} is not a member", name))?;
+        let base_name = strip_id_suffix(name.last());
+        let mut members = Vec::new();
+        for element in select(&self.document, "[id]")? {
+            let id = element.get_attribute("id").unwrap_or_default();
+            let (ty, member_name) = match parse_member_id(&id) {
+                Some(member) => member,
+                None => continue,
+            };
+            if strip_id_suffix(member_name) != base_name {
+                continue;
+            }
+            members.push((
+                parent.child(member_name),
+                ty,
+                get_impl_title(element.as_node()),
+            ));
+        }
+        Ok(members)
+    }
+
+    /// Returns the names and types of the members of the item on this page, e. g. its methods and
+    /// fields, in the order of their appearance.  Members with the same name are only listed once,
+    /// and the suffix that rustdoc adds to colliding ids is removed.
+    pub fn list_members(&self) -> anyhow::Result<Vec<(String, doc::ItemType)>> {
+        let mut members: Vec<(String, doc::ItemType)> = Vec::new();
+        for element in select(&self.document, "[id]")? {
+            let id = element.get_attribute("id").unwrap_or_default();
+            if let Some((ty, name)) = parse_member_id(&id) {
+                let name = strip_id_suffix(name);
+                if !members.iter().any(|(n, _)| n == name) {
+                    members.push((name.to_owned(), ty));
+                }
+            }
+        }
+        Ok(members)
+    }
+
     pub fn parse_item_doc(&self, name: &doc::Fqn, ty: doc::ItemType) -> anyhow::Result<doc::Doc> {
         log::info!("Parsing item documentation for '{}'", name);
-        let definition_selector = match ty {
-            doc::ItemType::Constant => "pre.const",
-            doc::ItemType::Function => "pre.fn",
-            doc::ItemType::Typedef => "pre.typedef",
-            _ => ".docblock.type-decl",
-        };
-        let definition = select_first(&self.document, definition_selector)?;
-        // Since Rust 1.54.0, the main description is wrapped in a details element
-        let mut description = select_first(
-            &self.document,
-            "#main > details.top-doc > .docblock:not(.type-decl)",
-        )?;
-        if description.is_none() {
-            description = select_first(&self.document, "#main > .docblock:not(.type-decl)")?;
-        }
+        let definition = find_with_selector_sets(&self.document, "definition", |set| {
+            vec![(set.definition)(ty)]
+        })?;
+        let description = find_with_selector_sets(&self.document, "description", |set| {
+            set.descriptions.to_vec()
+        })?;
 
         let mut doc = doc::Doc::new(name.clone(), ty);
         doc.description = description.map(From::from);
         doc.definition = definition.map(From::from);
+        if VISIBILITY_TYPES.contains(&ty) {
+            doc.visibility = doc.definition.as_ref().map(doc::Code::visibility);
+        }
+        if ATTRIBUTE_TYPES.contains(&ty) {
+            doc.attributes = doc
+                .definition
+                .as_ref()
+                .map(doc::Code::attributes)
+                .unwrap_or_default();
+        }
+        let (platform, required_features) = get_portability(&self.document)?;
+        doc.platform = platform;
+        doc.required_features = required_features;
+        doc.hidden = is_hidden(&self.document)?;
+        doc.deprecated = get_deprecation(&self.document)?;
         if let Some(path) = self.path.as_ref() {
             doc.set_url(path, None);
         }
@@ -123,6 +237,7 @@ impl Parser {
                 doc.groups.insert(ty, groups);
             }
         }
+        doc.siblings = self.find_siblings(name, ty)?;
 
         Ok(doc)
     }
@@ -133,10 +248,12 @@ impl Parser {
         let heading = select_first(&self.document, &member_selector)?
             .with_context(|| format!("Could not find member {}", name))?;
 
-        // Since Rust 1.54.0, the <code> element is replaced with a <h4 class="code-header">
-        let code = if let Some(code) = select_first(heading.as_node(), "code")? {
+        // Since Rust 1.54.0, the <code> element is replaced with a <h4 class="code-header">.  The
+        // notable traits of the return type are also listed in a <code class="content"> element
+        // inside the heading, so it is skipped.
+        let code = if let Some(code) = select_first(heading.as_node(), "h4.code-header")? {
             Ok(code)
-        } else if let Some(code) = select_first(heading.as_node(), "h4.code-header")? {
+        } else if let Some(code) = select_first(heading.as_node(), "code:not(.content)")? {
             Ok(code)
         } else {
             Err(anyhow::anyhow!(
@@ -153,11 +270,20 @@ impl Parser {
                 .and_then(|parent| parent.next_sibling())
         });
 
-        let mut doc = doc::Doc::new(name.clone(), ty);
+        // The suffix that rustdoc adds to colliding ids is not part of the name of the member.
+        let doc_name = name
+            .parent()
+            .map(|parent| parent.child(strip_id_suffix(name.last())))
+            .unwrap_or_else(|| name.clone());
+        let mut doc = doc::Doc::new(doc_name, ty);
         doc.definition = Some(code.into());
+        if is_inherent_impl_member(heading.as_node()) {
+            doc.visibility = doc.definition.as_ref().map(doc::Code::visibility);
+        }
+        doc.notable_traits = get_notable_traits(heading.as_node())?;
         doc.description = docblock.map(From::from);
         if let Some(path) = self.path.as_ref() {
-            doc.set_url(path, Some(member_selector));
+            doc.set_url(path, heading.get_attribute("id"));
         }
         Ok(doc)
     }
@@ -168,6 +294,10 @@ impl Parser {
 
         let mut doc = doc::Doc::new(name.clone(), doc::ItemType::Module);
         doc.description = description.map(From::from);
+        let (platform, required_features) = get_portability(&self.document)?;
+        doc.platform = platform;
+        doc.required_features = required_features;
+        doc.hidden = is_hidden(&self.document)?;
         if let Some(path) = self.path.as_ref() {
             doc.set_url(path, None);
         }
@@ -178,13 +308,239 @@ impl Parser {
                 doc.groups.insert(*item_type, vec![group]);
             }
         }
+        doc.siblings = self.find_siblings(name, doc::ItemType::Module)?;
         Ok(doc)
     }
 
+    /// Returns the items that are listed in the sidebar next to the given item, i. e. the other
+    /// items in its parent module.
+    ///
+    /// rustdoc only renders some of the sidebar links into the HTML file.  The list of the other
+    /// items in the module is loaded from the `sidebar-items.js` file in the module directory, so
+    /// we read this file too if we know the path of the HTML file.
+    pub fn find_siblings(
+        &self,
+        name: &doc::Fqn,
+        ty: doc::ItemType,
+    ) -> anyhow::Result<Vec<doc::Doc>> {
+        let parent = if let Some(parent) = name.parent() {
+            parent
+        } else {
+            return Ok(Vec::new());
+        };
+
+        let mut links: Vec<_> = select(&self.document, ".sidebar a")?
+            .filter_map(|a| a.get_attribute("href"))
+            .collect();
+        if let Some(path) = &self.path {
+            // The sidebar items of the parent module are stored in the directory of the parent
+            // module.  For modules, this is the parent directory of their index.html file.
+            let (dir, prefix) = if ty == doc::ItemType::Module {
+                (path.parent().and_then(path::Path::parent), "../")
+            } else {
+                (path.parent(), "")
+            };
+            if let Some(dir) = dir {
+                let items = read_sidebar_items(&dir.join("sidebar-items.js"))?;
+                links.extend(items.into_iter().map(|s| format!("{}{}", prefix, s)));
+            }
+        }
+
+        let mut siblings: Vec<doc::Doc> = Vec::new();
+        for href in links {
+            if href.starts_with('#') || href.contains("://") {
+                continue;
+            }
+            match link::resolve_doc_link(name, ty, &href) {
+                Ok((Some(sibling_ty), sibling_name)) => {
+                    if &sibling_name != name
+                        && sibling_name.parent().as_ref() == Some(&parent)
+                        && !siblings.iter().any(|d| d.name == sibling_name)
+                    {
+                        siblings.push(doc::Doc::new(sibling_name, sibling_ty));
+                    }
+                }
+                Ok((None, _)) => {}
+                Err(err) => log::info!("Ignoring sidebar link '{}': {}", href, err),
+            }
+        }
+        siblings.sort_by(|d1, d2| (d1.ty, &d1.name).cmp(&(d2.ty, &d2.name)));
+        Ok(siblings)
+    }
+
+    /// Returns the version of rustdoc that generated the page, if it is available.  Only newer
+    /// rustdoc versions store their version in the `data-rustdoc-version` attribute.
+    pub fn find_rustdoc_version(&self) -> anyhow::Result<Option<String>> {
+        let vars = select_first(&self.document, "[data-rustdoc-version]")?;
+        Ok(vars
+            .and_then(|e| e.get_attribute("data-rustdoc-version"))
+            .filter(|version| !version.is_empty()))
+    }
+
+    /// Returns the target of the `[src]` link of the member with the given anchor, or of the item
+    /// if `id` is `None` or the member does not have a `[src]` link.
+    pub fn find_source_link(&self, id: Option<&str>) -> anyhow::Result<Option<String>> {
+        // Rust < 1.58 uses a.srclink, newer versions a.src.
+        if let Some(id) = id {
+            let selector = format!("[id=\"{0}\"] a.srclink, [id=\"{0}\"] a.src", id);
+            let link = select_first(&self.document, &selector)?;
+            if let Some(href) = link.and_then(|a| a.get_attribute("href")) {
+                return Ok(Some(href));
+            }
+            log::info!(
+                "Member {} does not have a source link, using the item link",
+                id
+            );
+        }
+        let link = select_first(&self.document, "h1.fqn a.srclink, .main-heading a.src")?;
+        Ok(link.and_then(|a| a.get_attribute("href")))
+    }
+
+    /// Returns the HTML of the member with the given anchor, i. e. its heading and docblock, or of
+    /// the main content of the page if `id` is `None`.
+    pub fn find_raw_html(&self, id: Option<&str>) -> anyhow::Result<Option<String>> {
+        let id = if let Some(id) = id {
+            id
+        } else {
+            let main = select_first(&self.document, "#main, section#main-content")?;
+            return Ok(main.map(|main| main.as_node().to_string()));
+        };
+        let heading =
+            if let Some(heading) = select_first(&self.document, &format!("[id=\"{}\"]", id))? {
+                heading.as_node().clone()
+            } else {
+                return Ok(None);
+            };
+        // Since Rust 1.54.0, the heading and the docblock are wrapped in details and summary
+        // elements.
+        let details = heading
+            .parent()
+            .filter(|n| n.is_element(&local_name!("summary")))
+            .and_then(|n| n.parent())
+            .filter(|n| n.is_element(&local_name!("details")));
+        if let Some(details) = details {
+            return Ok(Some(details.to_string()));
+        }
+        let mut html = heading.to_string();
+        let mut next = heading.next_sibling_element();
+        while let Some(sibling) = next.take() {
+            if sibling.has_class("docblock")
+                || sibling.has_class("stability")
+                || ItemInfo::is_item_info(&sibling)
+            {
+                html.push_str(&sibling.to_string());
+                next = sibling.next_sibling_element();
+            }
+        }
+        Ok(Some(html))
+    }
+
+    /// Returns the HTML of the section with the given title, i. e. the `h2` heading with this
+    /// title and the following elements up to the next `h2` heading.  The title is compared
+    /// case-insensitively.
+    pub fn find_raw_section(&self, title: &str) -> anyhow::Result<Option<String>> {
+        let title = title.to_lowercase();
+        let heading = select(&self.document, "h2")?.find(|h2| {
+            let text = h2.as_node().text_contents();
+            text.trim().trim_end_matches('§').trim().to_lowercase() == title
+        });
+        let heading = if let Some(heading) = heading {
+            heading.as_node().clone()
+        } else {
+            return Ok(None);
+        };
+        let mut html = heading.to_string();
+        let mut next = heading.next_sibling_element();
+        while let Some(sibling) = next.take() {
+            if !sibling.is_element(&local_name!("h2")) {
+                html.push_str(&sibling.to_string());
+                next = sibling.next_sibling_element();
+            }
+        }
+        Ok(Some(html))
+    }
+
+    /// Returns the crate version that is displayed in the sidebar of the crate root page.
+    pub fn find_crate_version(&self) -> anyhow::Result<Option<String>> {
+        // Older rustdoc versions use <div class="block version"><p>Version x.y.z</p></div>, newer
+        // versions <span class="version">x.y.z</span>.
+        let version = select_first(&self.document, ".sidebar .version")?;
+        Ok(version.and_then(|e| {
+            let text = e.text_contents();
+            let text = text.trim();
+            let version = text.strip_prefix("Version ").unwrap_or(text).trim();
+            if version.is_empty() {
+                None
+            } else {
+                Some(version.to_owned())
+            }
+        }))
+    }
+
+    /// Returns the fully qualified name of the item documented on this page as displayed in the
+    /// heading, e. g. `log::Level` for the heading “Enum log::Level”.
+    pub fn find_item_name(&self) -> anyhow::Result<Option<doc::Fqn>> {
+        // Older rustdoc versions put the name in h1.fqn > .in-band, next to the [src] link in
+        // .out-of-band.  Newer versions use .main-heading > h1.
+        let selectors = ["h1.fqn .in-band", "h1.fqn", ".main-heading h1"];
+        for selector in &selectors {
+            if let Some(heading) = select_first(&self.document, selector)? {
+                let text = node_to_text(heading.as_node());
+                // The heading starts with the item type that can consist of multiple words, e. g.
+                // “Derive Macro”.  The name of a crate is the only name without a path separator.
+                let words: Vec<_> = text.split_whitespace().collect();
+                let name = words
+                    .iter()
+                    .find(|word| word.contains("::"))
+                    .or_else(|| words.last());
+                return Ok(name.map(|name| name.to_string().into()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the relative path of the search index if it is set in the rustdoc variables.
+    pub fn find_search_index(&self) -> anyhow::Result<Option<String>> {
+        let vars = select_first(&self.document, "meta[name=\"rustdoc-vars\"]")?;
+        Ok(vars.and_then(|e| e.get_attribute("data-search-index-js")))
+    }
+
+    /// Returns the targets of all links in the document.
+    pub fn find_links(&self) -> anyhow::Result<Vec<String>> {
+        let links = select(&self.document, "a")?;
+        Ok(links.filter_map(|a| a.get_attribute("href")).collect())
+    }
+
     pub fn find_examples(&self) -> anyhow::Result<Vec<doc::Example>> {
         let examples = select(&self.document, ".rust-example-rendered")?;
         Ok(examples.map(|n| get_example(n.as_node())).collect())
     }
+
+    /// Returns the first sentence of the first paragraph, i. e. the text up to the first period
+    /// that is followed by whitespace and that is not part of a code span.
+    pub fn find_first_sentence(&self) -> anyhow::Result<Option<String>> {
+        let paragraph = if let Some(paragraph) = select_first(&self.document, "p")? {
+            paragraph.as_node().clone()
+        } else {
+            return Ok(None);
+        };
+
+        let mut chars = Vec::new();
+        for node in paragraph.inclusive_descendants() {
+            if let Some(text) = node.as_text() {
+                let is_code = node.ancestors().any(|n| n.is_element(&local_name!("code")));
+                chars.extend(text.borrow().chars().map(|c| (c, is_code)));
+            }
+        }
+        let end = chars
+            .windows(2)
+            .position(|w| w[0] == ('.', false) && w[1].0.is_whitespace())
+            .map(|i| i + 1)
+            .unwrap_or_else(|| chars.len());
+        let sentence: String = chars[..end].iter().map(|(c, _)| c).collect();
+        let sentence = sentence.split_whitespace().collect::<Vec<_>>().join(" ");
+        Ok(Some(sentence).filter(|s| !s.is_empty()))
+    }
 }
 
 impl From<kuchiki::NodeRef> for doc::Text {
@@ -195,8 +551,13 @@ impl From<kuchiki::NodeRef> for doc::Text {
 
 impl From<&kuchiki::NodeRef> for doc::Text {
     fn from(node: &kuchiki::NodeRef) -> doc::Text {
+        // The node is part of the document that is also used for other items, so we modify a
+        // copy.
+        let node = deep_clone(node);
+        remove_noise(&node);
+        replace_task_list_checkboxes(&node);
         doc::Text {
-            plain: node_to_text(node),
+            plain: node_to_text(&node),
             html: node.to_string(),
         }
     }
@@ -216,7 +577,7 @@ impl From<kuchiki::NodeRef> for doc::Code {
 
 impl From<&kuchiki::NodeRef> for doc::Code {
     fn from(node: &kuchiki::NodeRef) -> doc::Code {
-        doc::Code::new(node_to_text(node))
+        doc::Code::dedented(node_to_text(node))
     }
 }
 
@@ -233,17 +594,22 @@ fn node_to_text(node: &kuchiki::NodeRef) -> String {
 }
 
 fn push_node_to_text(s: &mut String, node: &kuchiki::NodeRef) {
+    if is_noise(node) {
+        return;
+    }
     if node.has_class("notable-traits") {
         // The notable-traits element lists informations about types in a code block.  But we only
         // want to extract the code, so we skip this element.
         return;
     }
 
-    let is_docblock = node.has_class("docblock");
+    // Since Rust 1.54.0, the attributes of a declaration are div.code-attribute elements instead
+    // of span.docblock.attributes elements, so we have to add the line break ourselves.
+    let is_docblock = node.has_class("docblock") || node.has_class("code-attribute");
 
     let add_newline = if node.is_element(&local_name!("br")) {
         true
-    } else if node.has_class("fmt-newline") || is_docblock {
+    } else if node.has_class("fmt-newline") || is_docblock || is_where_block(node) {
         !s.is_empty() && !s.ends_with('\n')
     } else {
         false
@@ -265,6 +631,52 @@ fn push_node_to_text(s: &mut String, node: &kuchiki::NodeRef) {
     }
 }
 
+/// Whether the given node is a `where` clause that newer rustdoc versions render as a block
+/// element, e. g. `<div class="where">where T: Clone</div>`.
+fn is_where_block(node: &kuchiki::NodeRef) -> bool {
+    node.is_element(&local_name!("div")) && node.has_class("where")
+}
+
+/// Reads the items from a `sidebar-items.js` file and returns their paths relative to the module
+/// directory.
+///
+/// Older rustdoc versions store the items as `initSidebarItems({"struct":[["Name","Description"]]})`,
+/// newer versions as `window.SIDEBAR_ITEMS = {"struct":["Name"]};`.
+fn read_sidebar_items(path: &path::Path) -> anyhow::Result<Vec<String>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    log::info!("Reading sidebar items from file '{}'", path.display());
+    let s = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read sidebar items file {}", path.display()))?;
+    let json = match (s.find('{'), s.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &s[start..=end],
+        _ => return Ok(Vec::new()),
+    };
+    let items: std::collections::BTreeMap<String, Vec<serde_json::Value>> =
+        serde_json::from_str(json)
+            .with_context(|| format!("Could not parse sidebar items file {}", path.display()))?;
+
+    let mut paths = Vec::new();
+    for (ty, names) in items {
+        for name in names {
+            let name = match &name {
+                serde_json::Value::String(name) => Some(name.as_str()),
+                serde_json::Value::Array(values) => values.first().and_then(|v| v.as_str()),
+                _ => None,
+            };
+            if let Some(name) = name {
+                if ty == "mod" {
+                    paths.push(format!("{}/index.html", name));
+                } else {
+                    paths.push(format!("{}.{}.html", ty, name));
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
+
 fn select(
     element: &kuchiki::NodeRef,
     selector: &str,
@@ -291,6 +703,68 @@ fn select_first(
     select(element, selector).map(|mut i| i.next())
 }
 
+/// Returns the first element matching the given selector that is not part of a docblock.
+///
+/// Crates can use the ids of the sections generated by rustdoc, e. g. `implementations`, for the
+/// headings in their documentation, so we skip the docblocks when looking up the sections.
+fn select_section(
+    element: &kuchiki::NodeRef,
+    selector: &str,
+) -> anyhow::Result<Option<kuchiki::NodeDataRef<kuchiki::ElementData>>> {
+    Ok(select(element, selector)?.find(|e| {
+        !e.as_node()
+            .ancestors()
+            .any(|node| node.has_class("docblock"))
+    }))
+}
+
+/// Checks whether the given node is an element that rustdoc adds for its scripts and themes, for
+/// example the warning that is shown if JavaScript is disabled or the button that copies the item
+/// path to the clipboard.  These elements are not part of the documentation.
+fn is_noise(node: &kuchiki::NodeRef) -> bool {
+    let names = [
+        local_name!("script"),
+        local_name!("noscript"),
+        local_name!("style"),
+        local_name!("button"),
+    ];
+    names.iter().any(|name| node.is_element(name)) || node.has_class("copy-button")
+}
+
+/// Removes the descendants of the given node that are not part of the documentation, see
+/// `is_noise`.
+fn remove_noise(node: &kuchiki::NodeRef) {
+    let noise: Vec<_> = node.descendants().filter(is_noise).collect();
+    for node in noise {
+        node.detach();
+    }
+}
+
+/// Returns a copy of the given node and its descendants that is not attached to a document.
+fn deep_clone(node: &kuchiki::NodeRef) -> kuchiki::NodeRef {
+    let clone = kuchiki::NodeRef::new(node.data().clone());
+    for child in node.children() {
+        clone.append(deep_clone(&child));
+    }
+    clone
+}
+
+/// Replaces the checkboxes that rustdoc generates for task list items with `[x]` and `[ ]` markers
+/// as they would otherwise be dropped by the HTML renderers.
+fn replace_task_list_checkboxes(node: &kuchiki::NodeRef) {
+    if let Ok(checkboxes) = node.select("li > input[type=checkbox]") {
+        let checkboxes: Vec<_> = checkboxes.collect();
+        for checkbox in checkboxes {
+            let checked = checkbox.attributes.borrow().contains("checked");
+            let marker = if checked { "[x] " } else { "[ ] " };
+            checkbox
+                .as_node()
+                .insert_before(kuchiki::NodeRef::new_text(marker));
+            checkbox.as_node().detach();
+        }
+    }
+}
+
 fn it_select_first<I: kuchiki::iter::NodeIterator>(
     iter: I,
     selector: &str,
@@ -298,6 +772,20 @@ fn it_select_first<I: kuchiki::iter::NodeIterator>(
     it_select(iter, selector).map(|mut i| i.next())
 }
 
+/// Returns the description of a member from the given docblock without the “Read more” links that
+/// rustdoc appends to truncated descriptions.
+fn get_member_description(node: &kuchiki::NodeRef) -> doc::Text {
+    if let Ok(links) = node.select("a") {
+        let links: Vec<_> = links
+            .filter(|a| a.text_contents().trim() == "Read more")
+            .collect();
+        for link in links {
+            link.as_node().detach();
+        }
+    }
+    node.into()
+}
+
 fn get_example(node: &kuchiki::NodeRef) -> doc::Example {
     let description_element = node
         .parent()
@@ -312,7 +800,149 @@ fn get_example(node: &kuchiki::NodeRef) -> doc::Example {
             }
         })
         .map(From::from);
-    doc::Example::new(description, node.into())
+    let mut example = doc::Example::new(description, node.into());
+    example.full_code = get_playground_code(node).map(doc::Code::dedented);
+    example.tags = get_example_tags(node);
+    example
+}
+
+/// The doctest attributes that rustdoc adds as classes to the `pre` element of an example and
+/// marks with an icon.
+const EXAMPLE_TAGS: &[&str] = &["compile_fail", "ignore", "should_panic"];
+
+/// Returns the doctest attributes of the given example, see `EXAMPLE_TAGS`.
+fn get_example_tags(node: &kuchiki::NodeRef) -> Vec<String> {
+    EXAMPLE_TAGS
+        .iter()
+        .filter(|tag| node.has_class(tag))
+        .map(|tag| (*tag).to_owned())
+        .collect()
+}
+
+/// Returns the code of the playground link for the given example, if it has one.
+///
+/// rustdoc removes the hidden lines (starting with `#`) from the rendered example, but the link to
+/// the playground (“Run” button) contains the full code that is passed to the compiler.  The link
+/// is placed directly after the `pre` element, or inside it in some rustdoc versions.  Links of
+/// other examples are never used.
+fn get_playground_code(node: &kuchiki::NodeRef) -> Option<String> {
+    let link = select_first(node, "a.test-arrow")
+        .ok()
+        .flatten()
+        .map(|a| a.as_node().clone())
+        .or_else(|| {
+            node.next_sibling_element()
+                .filter(|n| n.has_class("test-arrow"))
+        })?;
+    let href = link.get_attribute("href")?;
+    let url = url::Url::parse(&href).ok()?;
+    url.query_pairs()
+        .find(|(key, _)| key == "code")
+        .map(|(_, value)| value.into_owned())
+}
+
+/// The selectors for the parts of an item page that are used by a group of rustdoc versions.
+struct SelectorSet {
+    /// The rustdoc versions that use these selectors, used for logging.
+    name: &'static str,
+    /// Returns the selector for the definition of an item with the given type.
+    definition: fn(doc::ItemType) -> &'static str,
+    /// The selectors for the main description, in the order they are tried.
+    descriptions: &'static [&'static str],
+    /// The ids of the sections with the inherent impl blocks and the element of the impl headings
+    /// in these sections.
+    impl_sections: &'static [(&'static str, &'static str)],
+    /// The element of the member headings in the sections for the trait methods and the
+    /// associated types.
+    member_heading: &'static str,
+}
+
+/// The selector sets for item pages.  `parse_item_doc` tries the sets in this order until one of
+/// them matches, so support for a new rustdoc version can be added by adding a set here.
+const ITEM_SELECTOR_SETS: &[SelectorSet] = &[
+    // Since Rust 1.54.0, the main description is wrapped in a details element.
+    SelectorSet {
+        name: "rustdoc 1.54.0 and later",
+        definition: get_type_decl_selector,
+        descriptions: &["#main > details.top-doc > .docblock:not(.type-decl)"],
+        impl_sections: &[("implementations", "h2")],
+        member_heading: "h4",
+    },
+    // Before Rust 1.45.0, the section with the inherent impl blocks is called methods.
+    SelectorSet {
+        name: "rustdoc before 1.54.0",
+        definition: get_type_decl_selector,
+        descriptions: &["#main > .docblock:not(.type-decl)"],
+        impl_sections: &[("methods", "h4"), ("implementations", "h4")],
+        member_heading: "h3",
+    },
+    // In newer Rust versions, the main content is wrapped in a section#main-content element and
+    // all definitions are pre.item-decl elements.
+    SelectorSet {
+        name: "rustdoc with section#main-content",
+        definition: |_| "pre.item-decl",
+        descriptions: &[
+            "section#main-content > details.top-doc > .docblock:not(.item-decl)",
+            "section#main-content > .docblock:not(.item-decl)",
+            "section.docblock",
+        ],
+        impl_sections: &[("implementations", "h2")],
+        member_heading: "h4",
+    },
+];
+
+fn get_type_decl_selector(ty: doc::ItemType) -> &'static str {
+    match ty {
+        doc::ItemType::Constant => "pre.const",
+        doc::ItemType::Function => "pre.fn",
+        doc::ItemType::Typedef => "pre.typedef",
+        doc::ItemType::ProcAttribute => "pre.attr",
+        doc::ItemType::ProcDerive => "pre.derive",
+        _ => ".docblock.type-decl",
+    }
+}
+
+/// Returns the first element that matches one of the selectors returned by `f` for the selector
+/// sets in `ITEM_SELECTOR_SETS`.
+fn find_with_selector_sets<F>(
+    document: &kuchiki::NodeRef,
+    part: &str,
+    f: F,
+) -> anyhow::Result<Option<kuchiki::NodeDataRef<kuchiki::ElementData>>>
+where
+    F: Fn(&SelectorSet) -> Vec<&'static str>,
+{
+    for set in ITEM_SELECTOR_SETS {
+        for selector in f(set) {
+            if let Some(element) = select_first(document, selector)? {
+                log::debug!(
+                    "Found {} with the selectors for {}: {}",
+                    part,
+                    set.name,
+                    selector
+                );
+                return Ok(Some(element));
+            }
+        }
+    }
+    log::debug!("Did not find {} with any selector set", part);
+    Ok(None)
+}
+
+/// Returns the first result of `f` for the selector sets in `ITEM_SELECTOR_SETS` that is not
+/// `None`.
+fn find_members_with_selector_sets<T, F>(part: &str, f: F) -> anyhow::Result<Option<T>>
+where
+    F: Fn(&SelectorSet) -> anyhow::Result<Option<T>>,
+{
+    for set in ITEM_SELECTOR_SETS {
+        if let Some(members) = f(set)? {
+            log::debug!("Found {} with the selectors for {}", part, set.name);
+            return Ok(Some(members));
+        }
+    }
+    log::debug!("Did not find {} with any selector set", part);
+    Ok(None)
 }
 
 const MODULE_MEMBER_TYPES: &[doc::ItemType] = &[
@@ -329,6 +959,8 @@ const MODULE_MEMBER_TYPES: &[doc::ItemType] = &[
     doc::ItemType::Function,
     doc::ItemType::Typedef,
     doc::ItemType::Union,
+    doc::ItemType::ProcAttribute,
+    doc::ItemType::ProcDerive,
 ];
 
 fn get_id_part(node: &kuchiki::NodeRef, i: usize) -> Option<String> {
@@ -336,20 +968,70 @@ fn get_id_part(node: &kuchiki::NodeRef, i: usize) -> Option<String> {
     if let Some(id) = node.get_attribute("id") {
         id.splitn(2, '.')
             .nth(i)
-            .and_then(|s| s.splitn(2, '-').next())
+            .and_then(|s| s.split('-').next())
             .map(ToOwned::to_owned)
     } else {
         None
     }
 }
 
+/// Strips the numeric suffix that rustdoc adds to colliding ids, e. g. `foo-1`.
+fn strip_id_suffix(s: &str) -> &str {
+    match s.rsplit_once('-') {
+        Some((base, suffix))
+            if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            base
+        }
+        _ => s,
+    }
+}
+
+/// Whether the given member is part of an inherent impl block, including the inherent impl blocks
+/// of the deref target.  Only these members have a visibility qualifier in their definitions.
+fn is_inherent_impl_member(member: &kuchiki::NodeRef) -> bool {
+    match get_impl_title(member) {
+        Some(title) => {
+            title.starts_with("Methods from ")
+                || (title.starts_with("impl") && !title.contains(" for "))
+        }
+        None => false,
+    }
+}
+
+/// Returns the title of the impl block that contains the given member, e. g. `impl Foo` or
+/// `Methods from Deref<Target = Bar>`.
+fn get_impl_title(member: &kuchiki::NodeRef) -> Option<String> {
+    let impl_items = member
+        .ancestors()
+        .find(|n| n.is_element(&local_name!("div")) && n.has_class("impl-items"))?;
+    let previous = impl_items.previous_sibling_element()?;
+    // Since Rust 1.54.0, the impl blocks are wrapped in details and summary elements.
+    let heading = if previous.is_element(&local_name!("summary")) {
+        select_first(&previous, "h3").ok()??.as_node().to_owned()
+    } else {
+        previous
+    };
+    let title = heading
+        .first_child()
+        .filter(|n| n.is_element(&local_name!("code")))
+        .unwrap_or(heading);
+    let title = title.text_contents();
+    let title = title.trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_owned())
+    }
+}
+
 fn get_fields(
     document: &kuchiki::NodeRef,
     parent: &doc::Fqn,
 ) -> anyhow::Result<(doc::ItemType, Vec<doc::MemberGroup>)> {
     let ty = doc::ItemType::StructField;
     let mut fields = MemberDocs::new(parent, ty);
-    let heading = select_first(document, &format!("#{}", get_item_group_id(ty)))?;
+    let heading = select_section(document, &format!("#{}", get_item_group_id(ty)))?;
 
     let mut next = heading.as_ref().and_then(NodeRefExt::next_sibling_element);
     let mut name: Option<String> = None;
@@ -362,7 +1044,13 @@ fn get_fields(
             definition = Some(element.into());
         } else if element.is_element(&local_name!("div")) {
             if element.has_class("docblock") {
-                fields.push(&mut name, &mut definition, Some(element.into()))?;
+                fields.push(
+                    &mut name,
+                    &mut definition,
+                    Some(get_member_description(element)),
+                )?;
+            } else if ItemInfo::is_item_info(element) {
+                fields.item_info = ItemInfo::parse(element)?;
             }
         } else {
             fields.push(&mut name, &mut definition, None)?;
@@ -379,34 +1067,22 @@ fn get_methods(
     parent: &doc::Fqn,
 ) -> anyhow::Result<(doc::ItemType, Vec<doc::MemberGroup>)> {
     let ty = doc::ItemType::Method;
-    let mut groups: Vec<doc::MemberGroup> = Vec::new();
-
-    // Rust < 1.45
-    groups.append(&mut get_method_groups(
-        document,
-        parent,
-        "methods".to_owned(),
-        ty,
-        &local_name!("h4"),
-    )?);
-    // Rust >= 1.45, < 1.54.0
-    groups.append(&mut get_method_groups(
-        document,
-        parent,
-        "implementations".to_owned(),
-        ty,
-        &local_name!("h4"),
-    )?);
-    // Rust >= 1.54.0
-    groups.append(&mut get_method_groups(
-        document,
-        parent,
-        "implementations".to_owned(),
-        ty,
-        &local_name!("h2"),
-    )?);
+    let mut groups = find_members_with_selector_sets("methods", |set| {
+        let mut groups: Vec<doc::MemberGroup> = Vec::new();
+        for (id, subheading_type) in set.impl_sections {
+            groups.append(&mut get_method_groups(
+                document,
+                parent,
+                (*id).to_owned(),
+                ty,
+                &(*subheading_type).into(),
+            )?);
+        }
+        Ok(Some(groups).filter(|groups| !groups.is_empty()))
+    })?
+    .unwrap_or_default();
 
-    let heading = select_first(document, "#deref-methods")?;
+    let heading = select_section(document, "#deref-methods")?;
     if let Some(heading) = heading {
         let title = heading.as_node().text_contents();
         if let Some(impl_items) = heading.as_node().next_sibling() {
@@ -423,58 +1099,43 @@ fn get_methods(
         }
     }
 
-    let heading = select_first(document, "#required-methods")?;
+    // So far, we only have methods from inherent impl blocks that have a visibility qualifier.
+    for member in groups.iter_mut().flat_map(|group| group.members.iter_mut()) {
+        member.visibility = member.definition.as_ref().map(doc::Code::visibility);
+    }
+
+    let heading = select_section(document, "#required-methods")?;
     if let Some(heading) = heading {
         if let Some(methods) = heading.as_node().next_sibling() {
             let title = "Required Methods";
-            // Rust < 1.54.0
-            let group = if let Some(group) = get_method_group(
-                parent,
-                Some(title.to_owned()),
-                &methods,
-                doc::ItemType::TyMethod,
-                &local_name!("h3"),
-            )? {
-                Some(group)
-            } else {
-                // Rust >= 1.54.0
+            let group = find_members_with_selector_sets("required methods", |set| {
                 get_method_group(
                     parent,
                     Some(title.to_owned()),
                     &methods,
                     doc::ItemType::TyMethod,
-                    &local_name!("h4"),
-                )?
-            };
+                    &set.member_heading.into(),
+                )
+            })?;
             if let Some(group) = group {
                 groups.push(group);
             }
         }
     }
 
-    let heading = select_first(document, "#provided-methods")?;
+    let heading = select_section(document, "#provided-methods")?;
     if let Some(heading) = heading {
         if let Some(methods) = heading.as_node().next_sibling() {
             let title = "Provided Methods";
-            // Rust < 1.54.0
-            let group = if let Some(group) = get_method_group(
-                parent,
-                Some(title.to_owned()),
-                &methods,
-                doc::ItemType::TyMethod,
-                &local_name!("h3"),
-            )? {
-                Some(group)
-            } else {
-                // Rust >= 1.54.0
+            let group = find_members_with_selector_sets("provided methods", |set| {
                 get_method_group(
                     parent,
                     Some(title.to_owned()),
                     &methods,
                     doc::ItemType::TyMethod,
-                    &local_name!("h4"),
-                )?
-            };
+                    &set.member_heading.into(),
+                )
+            })?;
             if let Some(group) = group {
                 groups.push(group);
             }
@@ -491,28 +1152,18 @@ fn get_assoc_types(
     let ty = doc::ItemType::AssocType;
     let mut groups: Vec<doc::MemberGroup> = Vec::new();
 
-    let heading = select_first(document, "#associated-types")?;
+    let heading = select_section(document, "#associated-types")?;
     if let Some(heading) = heading {
         if let Some(methods) = heading.as_node().next_sibling() {
-            // Rust < 1.54.0
-            let group = if let Some(group) = get_method_group(
-                parent,
-                None,
-                &methods,
-                doc::ItemType::AssocType,
-                &local_name!("h3"),
-            )? {
-                Some(group)
-            } else {
-                // Rust >= 1.54.0
+            let group = find_members_with_selector_sets("associated types", |set| {
                 get_method_group(
                     parent,
                     None,
                     &methods,
                     doc::ItemType::AssocType,
-                    &local_name!("h4"),
-                )?
-            };
+                    &set.member_heading.into(),
+                )
+            })?;
             if let Some(group) = group {
                 groups.push(group);
             }
@@ -530,16 +1181,18 @@ fn get_method_groups(
     subheading_type: &markup5ever::LocalName,
 ) -> anyhow::Result<Vec<doc::MemberGroup>> {
     let mut groups: Vec<doc::MemberGroup> = Vec::new();
-    let heading = select_first(document, &format!("#{}", heading_id))?;
+    let heading = select_section(document, &format!("#{}", heading_id))?;
     let mut next = heading.as_ref().and_then(NodeRefExt::next_sibling_element);
 
     while let Some(subheading) = next.take() {
         if subheading.is_element(&local_name!("h3")) && subheading.has_class("impl") {
             if let Some(title) = subheading.first_child() {
-                if let Some(impl_items) = subheading.next_sibling() {
-                    if let Some(group) =
+                let (description, impl_items) = get_impl_description(subheading.next_sibling());
+                if let Some(impl_items) = impl_items {
+                    if let Some(mut group) =
                         get_impl_items(parent, &title, &impl_items, ty, subheading_type)?
                     {
+                        group.description = description;
                         groups.push(group);
                     }
                     next = impl_items.next_sibling();
@@ -554,10 +1207,12 @@ fn get_method_groups(
                         .filter(|n| n.is_element(&local_name!("code")))
                         .unwrap_or_else(|| n.as_node().to_owned())
                 }) {
-                    if let Some(impl_items) = summary.next_sibling() {
-                        if let Some(group) =
+                    let (description, impl_items) = get_impl_description(summary.next_sibling());
+                    if let Some(impl_items) = impl_items {
+                        if let Some(mut group) =
                             get_impl_items(parent, &title, &impl_items, ty, subheading_type)?
                         {
+                            group.description = description;
                             groups.push(group);
                         }
                         next = subheading.next_sibling();
@@ -570,6 +1225,21 @@ fn get_method_groups(
     Ok(groups)
 }
 
+/// Returns the doc comment of an impl block and the element with its items, given the element
+/// after the impl header.  If the impl block is documented, rustdoc inserts a docblock between
+/// the header and the items.
+fn get_impl_description(
+    node: Option<kuchiki::NodeRef>,
+) -> (Option<doc::Text>, Option<kuchiki::NodeRef>) {
+    match node {
+        Some(node) if node.is_element(&local_name!("div")) && node.has_class("docblock") => {
+            let impl_items = node.next_sibling();
+            (Some(node.into()), impl_items)
+        }
+        node => (None, node),
+    }
+}
+
 fn get_impl_items(
     parent: &doc::Fqn,
     title: &kuchiki::NodeRef,
@@ -601,8 +1271,15 @@ fn get_method_group(
             methods.push(&mut name, &mut definition, None)?;
             name = get_id_part(&element, 1);
             definition = it_select_first(element.children(), "code")?.map(From::from);
+            methods.notable_traits = get_notable_traits(&element)?;
         } else if element.is_element(&local_name!("div")) && element.has_class("docblock") {
-            methods.push(&mut name, &mut definition, Some(element.into()))?;
+            methods.push(
+                &mut name,
+                &mut definition,
+                Some(get_member_description(&element)),
+            )?;
+        } else if ItemInfo::is_item_info(&element) {
+            methods.item_info = ItemInfo::parse(&element)?;
         } else if element.is_element(&local_name!("details")) {
             // Since Rust 1.54.0, the heading and the docblock are wrapped in details and summary
             // elements.
@@ -612,10 +1289,17 @@ fn get_method_group(
                     name = get_id_part(div.as_node(), 1);
                     definition =
                         it_select_first(div.as_node().children(), ".code-header")?.map(From::from);
+                    methods.notable_traits = get_notable_traits(div.as_node())?;
                 }
             }
+            // The item info is a sibling of the summary element.
+            let item_info = element.children().find(ItemInfo::is_item_info);
+            if let Some(item_info) = item_info {
+                methods.item_info = ItemInfo::parse(&item_info)?;
+            }
             if let Some(docblock) = select_first(&element, "div.docblock")? {
-                methods.push(&mut name, &mut definition, Some(docblock.into()))?;
+                let description = get_member_description(docblock.as_node());
+                methods.push(&mut name, &mut definition, Some(description))?;
             }
         }
     }
@@ -623,13 +1307,188 @@ fn get_method_group(
     Ok(methods.into_member_group(title))
 }
 
+/// Returns the platforms and the required crate features from the portability notice of the
+/// item, e. g. `Unix` for “This is supported on Unix only.” and `std` for “Available on crate
+/// feature `std` only.”
+///
+/// If the notice does not have the expected format, the full text is used as the platform.  If it
+/// only lists crate features, the platform is `None`.
+fn get_portability(document: &kuchiki::NodeRef) -> anyhow::Result<(Option<String>, Vec<String>)> {
+    // Older rustdoc versions wrap the notice in a .stability element, newer ones in .item-info.
+    let selectors = [
+        "#main > .stability > .stab.portability",
+        "#main > .item-info > .stab.portability",
+        "section#main-content > .item-info > .stab.portability",
+    ];
+    for selector in &selectors {
+        if let Some(notice) = select_first(document, selector)? {
+            return parse_portability(notice.as_node());
+        }
+    }
+    Ok((None, Vec::new()))
+}
+
+/// Returns the deprecation notice of the item of the given document, if any.
+fn get_deprecation(document: &kuchiki::NodeRef) -> anyhow::Result<Option<doc::DeprecatedInfo>> {
+    let selectors = [
+        "#main > .stability > .stab.deprecated",
+        "#main > .item-info > .stab.deprecated",
+        "section#main-content > .item-info > .stab.deprecated",
+    ];
+    for selector in &selectors {
+        if let Some(notice) = select_first(document, selector)? {
+            return Ok(Some(parse_deprecation(notice.as_node())));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether the item of the given document is marked with `#[doc(hidden)]`.  rustdoc only
+/// documents these items with `--document-hidden-items` and marks them with a `.stab.hidden`
+/// badge, either in the item info or in the heading.
+fn is_hidden(document: &kuchiki::NodeRef) -> anyhow::Result<bool> {
+    let selectors = [
+        "#main > .stability > .stab.hidden",
+        "#main > .item-info > .stab.hidden",
+        "section#main-content > .item-info > .stab.hidden",
+        "h1.fqn .stab.hidden",
+        ".main-heading .stab.hidden",
+    ];
+    for selector in &selectors {
+        if select_first(document, selector)?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn parse_portability(notice: &kuchiki::NodeRef) -> anyhow::Result<(Option<String>, Vec<String>)> {
+    // rustdoc renders feature names as code elements
+    let features: Vec<_> = select(notice, "code")?
+        .map(|n| n.text_contents().trim().to_owned())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let text = notice.text_contents();
+    let text = text.trim();
+    let platform = ["This is supported on ", "Available on ", "Available with "]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+        .map(|s| {
+            s.strip_suffix(" only.")
+                .or_else(|| s.strip_suffix(" only"))
+                .unwrap_or(s)
+        })
+        .unwrap_or(text)
+        .trim();
+
+    let mut rest = platform.to_owned();
+    for feature in &features {
+        rest = rest.replace(feature.as_str(), "");
+    }
+    let only_features = !features.is_empty()
+        && rest
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+            .all(|w| ["", "crate", "feature", "features", "and", "or"].contains(&w));
+    let platform = if only_features {
+        None
+    } else {
+        Some(platform.to_owned())
+    };
+    Ok((platform, features))
+}
+
+/// The notices from the `.item-info` (or `.stability` in older rustdoc versions) element of a
+/// member.
+#[derive(Debug, Default)]
+struct ItemInfo {
+    deprecated: Option<doc::DeprecatedInfo>,
+    platform: Option<String>,
+    required_features: Vec<String>,
+    hidden: bool,
+}
+
+impl ItemInfo {
+    fn is_item_info(node: &kuchiki::NodeRef) -> bool {
+        node.is_element(&local_name!("div"))
+            && (node.has_class("item-info") || node.has_class("stability"))
+    }
+
+    fn parse(node: &kuchiki::NodeRef) -> anyhow::Result<ItemInfo> {
+        let mut info = ItemInfo::default();
+        if let Some(notice) = select_first(node, ".stab.deprecated")? {
+            // Newer rustdoc versions prepend an emoji to the notice.
+            if let Some(emoji) = select_first(notice.as_node(), ".emoji")? {
+                emoji.as_node().detach();
+            }
+            info.deprecated = Some(parse_deprecation(notice.as_node()));
+        }
+        if let Some(notice) = select_first(node, ".stab.portability")? {
+            let (platform, required_features) = parse_portability(notice.as_node())?;
+            info.platform = platform;
+            info.required_features = required_features;
+        }
+        info.hidden = select_first(node, ".stab.hidden")?.is_some();
+        Ok(info)
+    }
+}
+
+/// Parses a deprecation notice like `Deprecated since 1.42.0: <p>use <code>X</code></p>`.
+fn parse_deprecation(node: &kuchiki::NodeRef) -> doc::DeprecatedInfo {
+    let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = normalize(&node.text_contents());
+    let (header, note) = match text.split_once(':') {
+        Some((header, note)) => (header, Some(note.trim())),
+        None => (text.as_str(), None),
+    };
+    // Newer rustdoc versions prepend an emoji to the notice.
+    let since = header
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .trim()
+        .strip_prefix("Deprecated since ")
+        .map(ToOwned::to_owned);
+    // The replacement is usually formatted as inline code, so we keep the code markers.
+    let mut code_text = String::new();
+    push_text_with_code(&mut code_text, node);
+    let code_text = normalize(&code_text);
+    let replacement = code_text
+        .split_once(':')
+        .and_then(|(_, note)| doc::DeprecatedInfo::find_replacement(note));
+    doc::DeprecatedInfo {
+        since,
+        note: note.filter(|s| !s.is_empty()).map(ToOwned::to_owned),
+        replacement,
+    }
+}
+
+/// Appends the text of the given node to the string and encloses inline code in backticks.
+fn push_text_with_code(s: &mut String, node: &kuchiki::NodeRef) {
+    if node.is_element(&local_name!("code")) {
+        s.push('`');
+        s.push_str(&node.text_contents());
+        s.push('`');
+    } else if let Some(text) = node.as_text() {
+        s.push_str(&text.borrow());
+    } else {
+        for child in node.children() {
+            push_text_with_code(s, &child);
+        }
+    }
+}
+
+/// Extracts the notable traits for the return type of a method definition, see the
+/// `notable_traits_decl` function in `librustdoc`.
+fn get_notable_traits(node: &kuchiki::NodeRef) -> anyhow::Result<Option<doc::Code>> {
+    select_first(node, ".notable-traits-tooltiptext .docblock").map(|n| n.map(From::from))
+}
+
 fn get_variants(
     document: &kuchiki::NodeRef,
     parent: &doc::Fqn,
 ) -> anyhow::Result<(doc::ItemType, Vec<doc::MemberGroup>)> {
     let ty = doc::ItemType::Variant;
     let mut variants = MemberDocs::new(parent, ty);
-    let heading = select_first(document, &format!("#{}", get_item_group_id(ty)))?;
+    let heading = select_section(document, &format!("#{}", get_item_group_id(ty)))?;
 
     let mut next = heading.as_ref().and_then(NodeRefExt::next_sibling_element);
     let mut name: Option<String> = None;
@@ -641,7 +1500,13 @@ fn get_variants(
                 name = get_id_part(element, 1);
                 definition = Some(element.into());
             } else if element.has_class("docblock") {
-                variants.push(&mut name, &mut definition, Some(element.into()))?;
+                variants.push(
+                    &mut name,
+                    &mut definition,
+                    Some(get_member_description(element)),
+                )?;
+            } else if ItemInfo::is_item_info(element) {
+                variants.item_info = ItemInfo::parse(element)?;
             }
 
             next = element.next_sibling();
@@ -689,62 +1554,110 @@ fn get_implementation_group(
 ) -> anyhow::Result<Option<doc::MemberGroup>> {
     let ty = doc::ItemType::Impl;
     let mut impls = MemberDocs::new(parent, ty);
-    let list_div = select_first(document, &format!("#{}", list_id))?;
+    let list_div = select_section(document, &format!("#{}", list_id))?;
 
     if let Some(list_div) = list_div {
         for item in list_div.as_node().children() {
-            let h3 = if item.is_element(&local_name!("details")) {
+            // The header and the element after the header, i. e. the docblock or the items
+            let (h3, next) = if item.is_element(&local_name!("details")) {
                 if let Some(summary) = item.first_child() {
-                    select_first(&summary, "h3.impl, h3.code-header")?
-                        .map(|n| n.as_node().to_owned())
+                    let h3 = select_first(&summary, "h3.impl, h3.code-header")?
+                        .map(|n| n.as_node().to_owned());
+                    (h3, summary.next_sibling())
                 } else {
-                    None
+                    (None, None)
                 }
             } else if item.is_element(&local_name!("h3")) && item.has_class("impl") {
-                Some(item)
+                (Some(item.clone()), item.next_sibling())
             } else if item.is_element(&local_name!("div")) && item.has_class("impl") {
-                select_first(&item, "h3")?.map(|n| n.as_node().to_owned())
+                let h3 = select_first(&item, "h3")?.map(|n| n.as_node().to_owned());
+                (h3, item.next_sibling())
             } else {
-                None
+                (None, None)
             };
 
             if let Some(h3) = h3 {
-                let a = select_first(&h3, "a")?;
-                let mut name = a.map(|n| n.as_node().text_contents());
+                let mut name = get_impl_trait_link(&h3).map(|n| n.text_contents());
                 let mut definition = Some(
                     h3.first_child()
                         .filter(|n| n.is_element(&local_name!("code")))
                         .map(doc::Code::from)
                         .unwrap_or_else(|| h3.into()),
                 );
-                impls.push(&mut name, &mut definition, None)?;
+                let (description, impl_items) = get_impl_description(next);
+                let items = match (&name, impl_items) {
+                    (Some(name), Some(impl_items)) if impl_items.has_class("impl-items") => {
+                        get_method_group(
+                            &parent.child(name),
+                            None,
+                            &impl_items,
+                            doc::ItemType::Method,
+                            &local_name!("h4"),
+                        )?
+                    }
+                    _ => None,
+                };
+                impls.push(&mut name, &mut definition, description)?;
+                if let (Some(items), Some(doc)) = (items, impls.docs.last_mut()) {
+                    doc.groups.insert(doc::ItemType::Method, vec![items]);
+                }
             }
         }
     }
 
-    impls.sort();
-
+    // We keep the order of the rustdoc output.  The viewers group the implementations by trait,
+    // see viewer::utils::group_impls.
     Ok(impls.into_member_group(Some(title.to_owned())))
 }
 
+/// Returns the link to the implemented trait in the given impl header.  This is the first link
+/// that is not part of the generic parameters, so that we skip the trait bounds in headers like
+/// `impl<T: Display + ?Sized> ToString for T`.
+fn get_impl_trait_link(header: &kuchiki::NodeRef) -> Option<kuchiki::NodeRef> {
+    let mut depth = 0;
+    let mut previous = ' ';
+    for node in header.inclusive_descendants() {
+        if let Some(text) = node.as_text() {
+            for c in text.borrow().chars() {
+                match c {
+                    '<' => depth += 1,
+                    // ignore the arrow in bounds like Fn() -> T
+                    '>' if previous != '-' => depth -= 1,
+                    _ => {}
+                }
+                previous = c;
+            }
+        } else if depth == 0 && node.is_element(&local_name!("a")) {
+            return Some(node);
+        }
+    }
+    None
+}
+
 fn get_members(
     document: &kuchiki::NodeRef,
     parent: &doc::Fqn,
     ty: doc::ItemType,
 ) -> anyhow::Result<Vec<doc::Doc>> {
     let mut members: Vec<doc::Doc> = Vec::new();
-    if let Some(table) = select_first(document, &format!("#{} + table", get_item_group_id(ty)))? {
+    if let Some(table) = select_section(document, &format!("#{} + table", get_item_group_id(ty)))? {
         let items = select(table.as_node(), "td:first-child > :first-child")?;
         for item in items {
-            let item_name = item.as_node().text_contents();
-            let docblock = item.as_node().parent().and_then(|n| n.next_sibling());
+            let cell = item.as_node().parent();
+            let (item_name, _) = strip_private_marker(&item.as_node().text_contents());
+            let (_, is_private) =
+                strip_private_marker(&cell.as_ref().map(|n| n.text_contents()).unwrap_or_default());
+            let docblock = cell.and_then(|n| n.next_sibling());
 
             let mut doc = doc::Doc::new(parent.child(&item_name), ty);
-            doc.description = docblock.map(From::from);
+            doc.description = docblock.as_ref().map(get_member_description);
+            if is_private {
+                doc.visibility = Some(doc::Visibility::Private);
+            }
             members.push(doc);
         }
     }
-    if let Some(div) = select_first(
+    if let Some(div) = select_section(
         document,
         &format!("#{} + div.item-table", get_item_group_id(ty)),
     )? {
@@ -753,15 +1666,51 @@ fn get_members(
             if !item.has_class("module-item") || !docblock.has_class("docblock-short") {
                 continue;
             }
-            let item_name = item.text_contents();
+            let (item_name, is_private) = strip_private_marker(&item.text_contents());
             let mut doc = doc::Doc::new(parent.child(&item_name), ty);
-            doc.description = Some(docblock.into());
+            doc.description = Some(get_member_description(&docblock));
+            if is_private {
+                doc.visibility = Some(doc::Visibility::Private);
+            }
             members.push(doc);
         }
     }
     Ok(members)
 }
 
+/// The marker that rustdoc appends to the names of items that are not public in the module item
+/// listings if the documentation is generated with `--document-private-items`.
+const PRIVATE_ITEM_MARKER: char = '🔒';
+
+/// Strips the marker for private items from the given name and returns whether it was found.
+///
+/// rustdoc uses the same marker for private and crate-visible items, so all marked items are
+/// treated as private.
+fn strip_private_marker(s: &str) -> (String, bool) {
+    let name = s.replace(PRIVATE_ITEM_MARKER, "");
+    (name.trim().to_owned(), s.contains(PRIVATE_ITEM_MARKER))
+}
+
+/// The item types whose definitions start with a visibility qualifier.
+const VISIBILITY_TYPES: &[doc::ItemType] = &[
+    doc::ItemType::Struct,
+    doc::ItemType::Enum,
+    doc::ItemType::Union,
+    doc::ItemType::Function,
+    doc::ItemType::Constant,
+    doc::ItemType::Static,
+    doc::ItemType::Trait,
+    doc::ItemType::Typedef,
+];
+
+/// The item types whose declaration attributes, e. g. `#[repr(C)]`, are stored in
+/// `doc::Doc::attributes`.
+const ATTRIBUTE_TYPES: &[doc::ItemType] = &[
+    doc::ItemType::Struct,
+    doc::ItemType::Enum,
+    doc::ItemType::Union,
+];
+
 const MEMBER_TYPES: &[doc::ItemType] = &[
     doc::ItemType::StructField,
     doc::ItemType::Variant,
@@ -774,13 +1723,25 @@ fn get_member(
     document: &kuchiki::NodeRef,
     name: &str,
 ) -> anyhow::Result<Option<kuchiki::NodeDataRef<kuchiki::ElementData>>> {
+    // Required trait methods are listed as methods on the trait page, but have their own id.
     let selectors: Vec<_> = MEMBER_TYPES
         .iter()
+        .chain(&[doc::ItemType::TyMethod])
         .map(|ty| get_member_selector(*ty, name))
         .collect();
     select_first(document, &selectors.join(", "))
 }
 
+/// Parses the id of a member element, e. g. `method.new`, and returns the type and the name of
+/// the member.  Older rustdoc versions use the `tymethod` prefix for required trait methods.
+fn parse_member_id(id: &str) -> Option<(doc::ItemType, &str)> {
+    let (ty, name) = id.split_once('.')?;
+    match ty.parse() {
+        Ok(ty) if MEMBER_TYPES.contains(&ty) || ty == doc::ItemType::TyMethod => Some((ty, name)),
+        _ => None,
+    }
+}
+
 fn get_member_selector(ty: doc::ItemType, name: &str) -> String {
     format!("#{}\\.{}", get_item_id(ty), name)
 }
@@ -789,6 +1750,8 @@ struct MemberDocs<'a> {
     docs: Vec<doc::Doc>,
     parent: &'a doc::Fqn,
     ty: doc::ItemType,
+    notable_traits: Option<doc::Code>,
+    item_info: ItemInfo,
 }
 
 impl<'a> MemberDocs<'a> {
@@ -797,17 +1760,11 @@ impl<'a> MemberDocs<'a> {
             docs: Vec::new(),
             parent,
             ty,
+            notable_traits: None,
+            item_info: Default::default(),
         }
     }
 
-    pub fn sort(&mut self) {
-        self.docs.sort_by(|d1, d2| {
-            d1.name
-                .cmp(&d2.name)
-                .then_with(|| d1.definition.cmp(&d2.definition))
-        })
-    }
-
     pub fn push(
         &mut self,
         name: &mut Option<String>,
@@ -816,11 +1773,18 @@ impl<'a> MemberDocs<'a> {
     ) -> anyhow::Result<()> {
         let name = name.take();
         let definition = definition.take();
+        let notable_traits = self.notable_traits.take();
+        let item_info = std::mem::take(&mut self.item_info);
 
         if let Some(name) = name {
             let mut doc = doc::Doc::new(self.parent.child(&name), self.ty);
             doc.definition = definition;
+            doc.notable_traits = notable_traits;
             doc.description = description;
+            doc.deprecated = item_info.deprecated;
+            doc.platform = item_info.platform;
+            doc.required_features = item_info.required_features;
+            doc.hidden = item_info.hidden;
             self.docs.push(doc);
         }
         Ok(())
@@ -907,8 +1871,8 @@ fn get_item_group_id(ty: doc::ItemType) -> &'static str {
         ItemType::ForeignType => "foreign-types",
         ItemType::Keyword => "keywords",
         ItemType::OpaqueTy => "opaque-types",
-        ItemType::ProcAttribute => "proc-attributes",
-        ItemType::ProcDerive => "proc-derives",
+        ItemType::ProcAttribute => "attributes",
+        ItemType::ProcDerive => "derives",
         ItemType::TraitAlias => "trait-aliases",
     }
 }
@@ -918,6 +1882,23 @@ mod tests {
     use crate::doc;
     use crate::test_utils::{with_rustdoc, Format};
 
+    #[test]
+    fn test_check_file_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("struct.Foo.html");
+        std::fs::write(&path, vec![b' '; 3 << 20]).unwrap();
+        assert!(super::check_file_size(&path, 4 << 20).is_ok());
+        let err = super::check_file_size(&path, 2 << 20).unwrap_err();
+        assert_eq!(
+            format!(
+                "The HTML file {} is too large (3 MB, the limit is 2 MB, see --max-file-size)",
+                path.display()
+            ),
+            err.to_string()
+        );
+        assert!(super::check_file_size(&dir.path().join("missing.html"), 4 << 20).is_err());
+    }
+
     #[test]
     fn test_find_item() {
         with_rustdoc("*", Format::all(), |_, _, path| {
@@ -930,6 +1911,15 @@ mod tests {
                 parser.find_item("NodeRef").unwrap()
             );
         });
+
+        // Older rustdoc versions use ul.docblock instead of ul.all-items.
+        let html = r#"<html><body><h3 id="structs">Structs</h3><ul class="docblock">
+            <li><a href="struct.Foo.html">Foo</a></li></ul></body></html>"#;
+        let parser = super::Parser::from_string(html).unwrap();
+        assert_eq!(
+            Some("struct.Foo.html".to_owned()),
+            parser.find_item("Foo").unwrap()
+        );
     }
 
     #[test]
@@ -946,6 +1936,629 @@ mod tests {
             assert_eq!(doc::ItemType::Struct, doc.ty);
             assert!(doc.definition.is_some());
             assert!(doc.description.is_some());
+            assert_eq!(Some(doc::Visibility::Public), doc.visibility);
+        });
+    }
+
+    #[test]
+    fn test_parse_proc_macro_doc() {
+        let html = r#"<html><body><section id="main">
+            <h1 class="fqn">Derive Macro <a class="derive">Serialize</a></h1>
+            <pre class="rust derive">#[derive(Serialize)]
+{
+    // Attributes available to this derive:
+    #[serde]
+}
+</pre>
+            <div class="docblock"><p>Derives the Serialize trait.</p></div>
+            </section></body></html>"#;
+        let name: doc::Fqn = "serde_derive::Serialize".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::ProcDerive)
+            .unwrap();
+
+        assert_eq!(doc::ItemType::ProcDerive, doc.ty);
+        let definition = doc.definition.unwrap();
+        assert!(definition.starts_with("#[derive(Serialize)]"));
+        assert_eq!(
+            "Derives the Serialize trait.",
+            doc.description.unwrap().plain
+        );
+    }
+
+    /// Parses an item page as generated by rustdoc 1.54.0 with the given content after the
+    /// heading.
+    fn parse_item_page(name: &str, ty: doc::ItemType, content: &str) -> doc::Doc {
+        let html = format!(
+            r#"<html><body><section id="main" class="content">
+            <h1 class="fqn"><span class="in-band">{} <a>{}</a></span></h1>
+            {}
+            </section></body></html>"#,
+            ty.name(),
+            name,
+            content
+        );
+        let name: doc::Fqn = name.to_owned().into();
+        super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, ty)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_item_doc_noise() {
+        // A module page with the noscript warning and the copy buttons of newer rustdoc versions
+        // inside of the description.
+        let content = r##"<details class="rustdoc-toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary><div class="docblock"><noscript><div class="warning">JavaScript is disabled in your browser.</div></noscript><script>window.rootPath = "../";</script><style>.foo { color: red; }</style><p>A foo crate.</p>
+            <div class="example-wrap"><pre class="rust rust-example-rendered"><code>foo::bar();</code></pre><button class="copy-button" title="Copy to clipboard">Copy</button></div></div></details>"##;
+        let doc = parse_item_page("foo", doc::ItemType::Module, content);
+
+        let description = doc.description.unwrap();
+        assert!(description.plain.starts_with("A foo crate."));
+        assert!(description.plain.contains("foo::bar();"));
+        for noise in &["JavaScript", "rootPath", "color", "Copy"] {
+            assert!(!description.plain.contains(noise), "{}", noise);
+            assert!(!description.html.contains(noise), "{}", noise);
+        }
+    }
+
+    #[test]
+    fn test_text_keeps_document() {
+        use kuchiki::traits::TendrilSink;
+
+        let html = concat!(
+            r#"<div class="docblock"><p>Foo</p><button class="copy-button">Copy</button>"#,
+            r#"<ul><li><input type="checkbox" checked>Bar</li></ul></div>"#,
+        );
+        let document = kuchiki::parse_html().one(html);
+        let docblock = document.select_first(".docblock").unwrap();
+
+        let text = doc::Text::from(docblock.as_node());
+        assert!(text.plain.starts_with("Foo"));
+        assert!(text.plain.contains("[x] Bar"));
+        assert!(!text.html.contains("button"));
+        assert!(!text.html.contains("input"));
+        assert!(document.select_first(".copy-button").is_ok());
+        assert!(document.select_first("input").is_ok());
+    }
+
+    #[test]
+    fn test_parse_item_doc_repr() {
+        // Before Rust 1.54.0, attributes are span elements, afterwards div elements.
+        let definitions = &[
+            r#"<pre class="rust struct"><span class="docblock attributes top-attr">#[repr(C)]</span>pub struct Foo {
+    pub a: u8,
+}</pre>"#,
+            r#"<pre class="rust struct"><code><div class="code-attribute">#[repr(C)]</div>pub struct Foo {
+    pub a: u8,
+}</code></pre>"#,
+        ];
+        for definition in definitions {
+            let content = format!(r#"<div class="docblock type-decl">{}</div>"#, definition);
+            let doc = parse_item_page("foo::Foo", doc::ItemType::Struct, &content);
+
+            assert_eq!(vec!["#[repr(C)]"], doc.attributes);
+            assert_eq!(Some(doc::Visibility::Public), doc.visibility);
+            assert!(doc
+                .definition
+                .unwrap()
+                .starts_with("#[repr(C)]\npub struct Foo {"));
+        }
+    }
+
+    #[test]
+    fn test_impl_description() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("log").join("struct.MetadataBuilder.html");
+            let mut html = std::fs::read_to_string(path).unwrap();
+            // Add a doc comment to the first impl block.  rustdoc inserts it between the impl
+            // header and the impl items.
+            let docblock = r#"<div class="docblock"><p>Builds metadata.</p></div>"#;
+            let i = html
+                .find(r#"<div class="impl-items">"#)
+                .or_else(|| html.find("<div class='impl-items'>"))
+                .unwrap();
+            html.insert_str(i, docblock);
+
+            let name: doc::Fqn = "log::MetadataBuilder".to_owned().into();
+            let doc = super::Parser::from_string(html)
+                .unwrap()
+                .parse_item_doc(&name, doc::ItemType::Struct)
+                .unwrap();
+
+            let groups = &doc.groups[&doc::ItemType::Method];
+            assert_eq!(1, groups.len());
+            let group = &groups[0];
+            assert_eq!(Some("impl<'a> MetadataBuilder<'a>"), group.title.as_deref());
+            assert_eq!(
+                "Builds metadata.",
+                group.description.as_ref().unwrap().plain.trim()
+            );
+            assert!(group
+                .members
+                .iter()
+                .any(|member| member.name.last() == "level"));
+        });
+    }
+
+    #[test]
+    fn test_parse_item_doc_docblock_heading_ids() {
+        // The description uses the same ids as the member group headings generated by rustdoc.
+        let content = r##"<div class="docblock type-decl"><pre class="rust struct">pub struct Foo;</pre></div>
+            <details class="rustdoc-toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+            <div class="docblock"><p>A foo.</p>
+            <h2 id="implementations">Implementations</h2><p>See below.</p>
+            <h2 id="variants">Variants</h2><div><h3>Garbage</h3></div>
+            </div></details>
+            <h2 id="implementations" class="small-section-header">Implementations</h2>
+            <details class="rustdoc-toggle implementors-toggle" open><summary><div id="impl" class="impl">
+            <h3 class="code-header in-band">impl Foo</h3></div></summary><div class="impl-items">
+            <details class="rustdoc-toggle method-toggle" open><summary><div id="method.new" class="method">
+            <h4 class="code-header">pub fn <a href="#method.new" class="fnname">new</a>() -&gt; Foo</h4></div></summary>
+            <div class="docblock"><p>Creates a foo.</p></div></details></div></details>"##;
+        let doc = parse_item_page("foo::Foo", doc::ItemType::Struct, content);
+
+        assert_eq!(
+            vec![doc::ItemType::Method],
+            doc.groups.keys().copied().collect::<Vec<_>>()
+        );
+        let methods = &doc.groups[&doc::ItemType::Method];
+        assert_eq!(1, methods.len());
+        assert_eq!(Some("impl Foo"), methods[0].title.as_deref());
+        assert_eq!(1, methods[0].members.len());
+        assert_eq!("foo::Foo::new", methods[0].members[0].name.as_ref());
+    }
+
+    #[test]
+    fn test_parse_item_doc_main_content() {
+        // excerpt from the documentation generated by rustdoc 1.95.0
+        let html = r##"<html><body><main><div class="width-limiter">
+            <section id="main-content" class="content" tabindex="-1">
+            <div class="main-heading"><h1>Trait <span class="trait">Log</span></h1></div>
+            <pre class="rust item-decl"><code>pub trait Log: Sync + Send {
+    // Required methods
+    fn <a href="#tymethod.log" class="fn">log</a>(&amp;self, record: &amp;Record&lt;'_&gt;);
+}</code></pre>
+            <details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+            <div class="docblock"><p>A trait encapsulating the operations required of a logger.</p></div>
+            </details>
+            <h2 id="required-methods" class="section-header">Required Methods</h2>
+            <div class="methods"><details class="toggle method-toggle" open><summary>
+            <section id="tymethod.log" class="method"><h4 class="code-header">fn log(&amp;self, record: &amp;Record&lt;'_&gt;)</h4></section>
+            </summary><div class="docblock"><p>Logs the <code>Record</code>.</p></div></details></div>
+            </section></div></main></body></html>"##;
+        let name: doc::Fqn = "log::Log".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Trait)
+            .unwrap();
+
+        let definition = doc.definition.unwrap();
+        assert!(definition.starts_with("pub trait Log: Sync + Send {"));
+        assert_eq!(
+            "A trait encapsulating the operations required of a logger.",
+            doc.description.unwrap().plain
+        );
+    }
+
+    #[test]
+    fn test_find_siblings() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("struct.NodeRef.html");
+            let name: doc::Fqn = "kuchiki::NodeRef".to_owned().into();
+            let siblings = super::Parser::from_file(path)
+                .unwrap()
+                .find_siblings(&name, doc::ItemType::Struct)
+                .unwrap();
+            let siblings: Vec<_> = siblings.iter().map(|d| (d.ty, d.name.full())).collect();
+
+            assert!(siblings.contains(&(doc::ItemType::Struct, "kuchiki::NodeDataRef")));
+            assert!(siblings.contains(&(doc::ItemType::Function, "kuchiki::parse_html")));
+            assert!(siblings.contains(&(doc::ItemType::Module, "kuchiki::traits")));
+            assert!(!siblings.iter().any(|(_, name)| *name == "kuchiki::NodeRef"));
+        });
+    }
+
+    #[test]
+    fn test_find_siblings_sidebar_links() {
+        let html = r##"<html><body><nav class="sidebar">
+            <a href="../log/index.html">log</a>
+            <h2 class="location"><a href="#">Trait Log</a></h2>
+            <div class="sidebar-links"><a href="#tymethod.log">log</a></div>
+            <h2><a href="index.html">In crate log</a></h2>
+            <ul class="block">
+                <li><a href="trait.Log.html">Log</a></li>
+                <li><a href="struct.Metadata.html">Metadata</a></li>
+                <li><a href="fn.logger.html">logger</a></li>
+                <li><a href="macros/index.html">macros</a></li>
+                <li><a href="https://docs.rs/log">docs.rs</a></li>
+            </ul>
+            </nav><section id="main-content"></section></body></html>"##;
+        let name: doc::Fqn = "log::Log".to_owned().into();
+        let siblings = super::Parser::from_string(html)
+            .unwrap()
+            .find_siblings(&name, doc::ItemType::Trait)
+            .unwrap();
+        let siblings: Vec<_> = siblings.iter().map(|d| (d.ty, d.name.full())).collect();
+
+        assert_eq!(
+            vec![
+                (doc::ItemType::Module, "log::macros"),
+                (doc::ItemType::Struct, "log::Metadata"),
+                (doc::ItemType::Function, "log::logger"),
+            ],
+            siblings
+        );
+    }
+
+    #[test]
+    fn test_find_crate_version() {
+        with_rustdoc(">=1.49.0", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("index.html");
+            let version = super::Parser::from_file(path)
+                .unwrap()
+                .find_crate_version()
+                .unwrap();
+            assert!(version.unwrap().starts_with("0.8."));
+        });
+
+        let html = r#"<html><body><nav class="sidebar"><div class="sidebar-crate"><h2>
+            <a href="../anyhow/index.html">anyhow</a><span class="version">1.0.41</span>
+            </h2></div></nav></body></html>"#;
+        let version = super::Parser::from_string(html)
+            .unwrap()
+            .find_crate_version()
+            .unwrap();
+        assert_eq!(Some("1.0.41".to_owned()), version);
+    }
+
+    #[test]
+    fn test_item_selector_sets() {
+        let name: doc::Fqn = "krate::Foo".to_owned().into();
+
+        let html = r#"<html><body><section id="main">
+            <pre class="rust struct">pub struct Foo;</pre>
+            <div class="docblock"><p>Old description.</p></div>
+            </section></body></html>"#;
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Function)
+            .unwrap();
+        assert_eq!("Old description.", doc.description.unwrap().plain.trim());
+        assert!(doc.definition.is_none());
+
+        let html = r#"<html><body><section id="main-content">
+            <pre class="rust item-decl"><code>pub struct Foo;</code></pre>
+            <details class="top-doc"><summary></summary>
+            <div class="docblock"><p>New description.</p></div></details>
+            </section></body></html>"#;
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Struct)
+            .unwrap();
+        assert_eq!("New description.", doc.description.unwrap().plain.trim());
+        assert_eq!("pub struct Foo;", doc.definition.unwrap().trim());
+    }
+
+    #[test]
+    fn test_find_rustdoc_version() {
+        with_rustdoc("*", Format::all(), |version, _, path| {
+            let path = path.join("kuchiki").join("index.html");
+            let rustdoc_version = super::Parser::from_file(path)
+                .unwrap()
+                .find_rustdoc_version()
+                .unwrap();
+            // Only newer rustdoc versions store their version in the page.
+            if let Some(rustdoc_version) = rustdoc_version {
+                assert!(rustdoc_version.starts_with(&version.to_string()));
+            }
+        });
+
+        let html = r#"<html><body><div id="rustdoc-vars" data-root-path="../"
+            data-rustdoc-version="1.62.0 (a8314ef7d 2022-06-27)"></div></body></html>"#;
+        let version = super::Parser::from_string(html)
+            .unwrap()
+            .find_rustdoc_version()
+            .unwrap();
+        assert_eq!(Some("1.62.0 (a8314ef7d 2022-06-27)".to_owned()), version);
+    }
+
+    #[test]
+    fn test_find_source_link() {
+        with_rustdoc("*", Format::all(), |version, _, path| {
+            let path = path.join("log").join("trait.Log.html");
+            let parser = super::Parser::from_file(path).unwrap();
+            let item_link = parser.find_source_link(None).unwrap().unwrap();
+            assert!(item_link.starts_with("../src/log/lib.rs.html#"));
+            let member_link = parser
+                .find_source_link(Some("tymethod.enabled"))
+                .unwrap()
+                .unwrap();
+            assert!(member_link.starts_with("../src/log/lib.rs.html#"));
+            // Older rustdoc versions do not add source links to required methods.
+            if version >= &semver::Version::new(1, 50, 0) {
+                assert_ne!(item_link, member_link);
+            }
+        });
+    }
+
+    #[test]
+    fn test_find_raw_html() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("struct.NodeDataRef.html");
+            let parser = super::Parser::from_file(path).unwrap();
+
+            let main = parser.find_raw_html(None).unwrap().unwrap();
+            assert!(main.contains(r#"id="main""#));
+            assert!(main.contains("Trait Implementations"));
+
+            let member = parser
+                .find_raw_html(Some("method.as_node"))
+                .unwrap()
+                .unwrap();
+            assert!(member.contains(r#"id="method.as_node""#));
+            assert!(member.contains(r#"class="docblock""#));
+            assert!(!member.contains("Trait Implementations"));
+            assert_eq!(None, parser.find_raw_html(Some("method.foo")).unwrap());
+
+            let section = parser
+                .find_raw_section("trait implementations")
+                .unwrap()
+                .unwrap();
+            assert!(section.starts_with("<h2"));
+            assert!(section.contains("Deref"));
+            assert!(!section.contains("as_node"));
+            assert_eq!(None, parser.find_raw_section("Foo").unwrap());
+        });
+    }
+
+    #[test]
+    fn test_find_examples_full_code() {
+        let html = r#"<html><body><div class="docblock">
+            <p>Example:</p>
+            <div class="example-wrap"><pre class="rust rust-example-rendered"><code>let x = foo();
+</code></pre><a class="test-arrow" target="_blank" href="https://play.rust-lang.org/?code=%23!%5Ballow(unused)%5D%0Afn%20main()%20%7B%0Ause%20foo%3A%3Afoo%3B%0Alet%20x%20%3D%20foo()%3B%0A%7D&amp;edition=2018">Run</a></div>
+            <div class="example-wrap"><pre class="rust rust-example-rendered"><code>let y = bar();
+</code></pre></div>
+            </div></body></html>"#;
+        let examples = super::Parser::from_string(html)
+            .unwrap()
+            .find_examples()
+            .unwrap();
+
+        assert_eq!(2, examples.len());
+        assert_eq!("let x = foo();", examples[0].code.as_str());
+        assert_eq!(
+            Some("#![allow(unused)]\nfn main() {\nuse foo::foo;\nlet x = foo();\n}"),
+            examples[0].full_code.as_ref().map(|c| c.as_str())
+        );
+        assert_eq!(None, examples[1].full_code);
+
+        // The link of the next example is not used if the examples are not wrapped.
+        let html = r#"<html><body><div class="docblock">
+            <pre class="rust rust-example-rendered"><code>let x = foo();
+</code></pre>
+            <pre class="rust rust-example-rendered"><code>let y = bar();
+</code></pre><a class="test-arrow" target="_blank" href="https://play.rust-lang.org/?code=let%20y%20%3D%20bar()%3B&amp;edition=2018">Run</a>
+            </div></body></html>"#;
+        let examples = super::Parser::from_string(html)
+            .unwrap()
+            .find_examples()
+            .unwrap();
+
+        assert_eq!(2, examples.len());
+        assert_eq!(None, examples[0].full_code);
+        assert_eq!(
+            Some("let y = bar();"),
+            examples[1].full_code.as_ref().map(|c| c.as_str())
+        );
+    }
+
+    #[test]
+    fn test_impl_items() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("anyhow").join("struct.Error.html");
+            let name: doc::Fqn = "anyhow::Error".to_owned().into();
+            let doc = super::Parser::from_file(path)
+                .unwrap()
+                .parse_item_doc(&name, doc::ItemType::Struct)
+                .unwrap();
+            let groups = &doc.groups[&doc::ItemType::Impl];
+            let get_group = |title| {
+                groups
+                    .iter()
+                    .find(|g| g.title.as_deref() == Some(title))
+                    .unwrap()
+            };
+
+            let auto = get_group("Auto Trait Implementations");
+            assert!(auto.members.iter().all(|m| m.groups.is_empty()));
+            assert!(!auto.has_documented_members());
+
+            let traits = get_group("Trait Implementations");
+            let debug = traits
+                .members
+                .iter()
+                .find(|m| m.name.last() == "Debug")
+                .unwrap();
+            let methods = &debug.groups[&doc::ItemType::Method];
+            assert_eq!(
+                vec!["anyhow::Error::Debug::fmt"],
+                methods[0]
+                    .members
+                    .iter()
+                    .map(|m| m.name.as_ref())
+                    .collect::<Vec<_>>()
+            );
+            assert!(traits.has_documented_members());
+        });
+    }
+
+    #[test]
+    fn test_blanket_impl_bounds() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("struct.NodeRef.html");
+            let name: doc::Fqn = "kuchiki::NodeRef".to_owned().into();
+            let doc = super::Parser::from_file(path)
+                .unwrap()
+                .parse_item_doc(&name, doc::ItemType::Struct)
+                .unwrap();
+            let groups = &doc.groups[&doc::ItemType::Impl];
+            let blanket = groups
+                .iter()
+                .find(|g| g.title.as_deref() == Some("Blanket Implementations"))
+                .unwrap();
+            let into = blanket
+                .members
+                .iter()
+                .find(|m| m.name.last() == "Into")
+                .unwrap();
+            let definition = into.definition.as_ref().unwrap().to_string();
+            let lines: Vec<_> = definition.lines().map(str::trim).collect();
+            assert_eq!(
+                vec!["impl<T, U> Into<U> for T", "where", "U: From<T>,"],
+                lines
+            );
+        });
+    }
+
+    #[test]
+    fn test_blanket_impl_where_block() {
+        let html = r#"<html><body><div id="blanket-implementations-list">
+            <details><summary><section id="impl-ToString" class="impl">
+            <h3 class="code-header">impl&lt;T: <a>Display</a> + ?Sized&gt; <a>ToString</a> for T<div class="where">where
+                T: <a>Clone</a>,</div></h3>
+            </section></summary></details>
+            </div></body></html>"#;
+        let parser = super::Parser::from_string(html).unwrap();
+        let name: doc::Fqn = "foo::Foo".to_owned().into();
+        let (_, groups) = super::get_implementations(&parser.document, &name).unwrap();
+        let member = &groups[0].members[0];
+        assert_eq!("ToString", member.name.last());
+        let definition = member.definition.as_ref().unwrap().to_string();
+        let lines: Vec<_> = definition.lines().map(str::trim).collect();
+        assert_eq!(
+            vec![
+                "impl<T: Display + ?Sized> ToString for T",
+                "where",
+                "T: Clone,"
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_find_first_sentence() {
+        let find = |html: &str| {
+            super::Parser::from_string(html)
+                .unwrap()
+                .find_first_sentence()
+                .unwrap()
+        };
+        assert_eq!(
+            Some("A mutual exclusion primitive.".to_owned()),
+            find("<p>A mutual\n exclusion primitive. It is useful.</p><p>Second.</p>")
+        );
+        assert_eq!(
+            Some("Calls a. b and Foo::bar.".to_owned()),
+            find("<p>Calls <code>a. b</code> and <code>Foo::bar</code>. More.</p>")
+        );
+        assert_eq!(
+            Some("Version 1.2 is supported".to_owned()),
+            find("<p>Version 1.2 is supported</p>")
+        );
+        assert_eq!(None, find("<pre>let x = 1. y;</pre>"));
+    }
+
+    #[test]
+    fn test_find_examples_tags() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path
+                .join("rand_core")
+                .join("impls")
+                .join("fn.fill_via_u32_chunks.html");
+            let examples = super::Parser::from_file(path)
+                .unwrap()
+                .find_examples()
+                .unwrap();
+            assert_eq!(1, examples.len());
+            assert_eq!(vec!["ignore".to_owned()], examples[0].tags);
+        });
+
+        let html = r#"<html><body><div class="docblock">
+            <div class="example-wrap"><pre class="rust rust-example-rendered should_panic"><code>panic!();
+</code></pre></div>
+            <div class="example-wrap"><pre class="rust rust-example-rendered edition"><code>let x = 1;
+</code></pre></div>
+            </div></body></html>"#;
+        let examples = super::Parser::from_string(html)
+            .unwrap()
+            .find_examples()
+            .unwrap();
+        assert_eq!(2, examples.len());
+        assert_eq!(vec!["should_panic".to_owned()], examples[0].tags);
+        assert!(examples[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_portability() {
+        let html = r#"<html><body><section id="main">
+            <h1 class="fqn">Trait <a class="trait">FileExt</a></h1>
+            <div class="item-info"><div class="stab portability">This is supported on <strong>Unix</strong> only.</div></div>
+            <pre class="rust trait">pub trait FileExt {}</pre>
+            </section></body></html>"#;
+        let name: doc::Fqn = "std::os::unix::fs::FileExt".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Trait)
+            .unwrap();
+        assert_eq!(Some("Unix".to_owned()), doc.platform);
+
+        let html = r#"<html><body><section id="main-content">
+            <span class="item-info"><div class="stab portability">Available on <strong>crate feature <code>std</code></strong> only.</div></span>
+            <pre class="rust item-decl"><code>pub struct Error {}</code></pre>
+            </section></body></html>"#;
+        let name: doc::Fqn = "anyhow::Error".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Struct)
+            .unwrap();
+        assert_eq!(None, doc.platform);
+        assert_eq!(vec!["std".to_owned()], doc.required_features);
+
+        let html = r#"<html><body><section id="main">
+            <div class="item-info"><div class="stab portability">This is supported on <strong>crate feature <code>rt</code> and Unix</strong> only.</div></div>
+            <pre class="rust fn">pub fn spawn()</pre>
+            </section></body></html>"#;
+        let name: doc::Fqn = "tokio::spawn".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Function)
+            .unwrap();
+        assert_eq!(Some("crate feature rt and Unix".to_owned()), doc.platform);
+        assert_eq!(vec!["rt".to_owned()], doc.required_features);
+
+        let html = r#"<html><body><section id="main">
+            <div class="item-info"><div class="stab portability">Available with feature <code>serde</code></div></div>
+            <pre class="rust struct">pub struct Value</pre>
+            </section></body></html>"#;
+        let name: doc::Fqn = "foo::Value".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_item_doc(&name, doc::ItemType::Struct)
+            .unwrap();
+        assert_eq!(None, doc.platform);
+        assert_eq!(vec!["serde".to_owned()], doc.required_features);
+
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("struct.NodeRef.html");
+            let name: doc::Fqn = "kuchiki::NodeRef".to_owned().into();
+            let doc = super::Parser::from_file(path)
+                .unwrap()
+                .parse_item_doc(&name, doc::ItemType::Struct)
+                .unwrap();
+            assert_eq!(None, doc.platform);
         });
     }
 
@@ -962,6 +2575,217 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_member_description_read_more() {
+        let html = r#"<html><body><section id="main">
+            <h1 class="fqn">Crate <a class="mod">test</a></h1>
+            <h2 id="structs" class="section-header">Structs</h2>
+            <div class="item-table"><div class="item-left module-item"><a class="struct" href="struct.Foo.html">Foo</a></div><div class="item-right docblock-short">A foo. <a href="struct.Foo.html">Read more</a></div></div>
+            </section></body></html>"#;
+        let name: doc::Fqn = "test".to_owned().into();
+        let doc = super::Parser::from_string(html)
+            .unwrap()
+            .parse_module_doc(&name)
+            .unwrap();
+        let members = &doc.groups[&doc::ItemType::Struct][0].members;
+        assert_eq!(1, members.len());
+        let description = members[0].description.as_ref().unwrap();
+        assert_eq!("A foo.", description.plain.trim());
+        assert!(!description.html.contains("Read more"));
+
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("log").join("enum.LevelFilter.html");
+            let name: doc::Fqn = "log::LevelFilter".to_owned().into();
+            let doc = super::Parser::from_file(path)
+                .unwrap()
+                .parse_item_doc(&name, doc::ItemType::Enum)
+                .unwrap();
+            for member in doc.groups.values().flatten().flat_map(|g| &g.members) {
+                if let Some(description) = &member.description {
+                    assert!(!description.plain.contains("Read more"), "{}", member.name);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_member_selector_sets() {
+        let layouts = [
+            // Rust < 1.54.0
+            r#"<div id="main"><h2 id="required-methods">Required methods</h2><div class="methods">
+            <h3 id="tymethod.foo" class="method"><code>fn foo()</code></h3>
+            <div class="docblock"><p>A foo.</p></div></div></div>"#,
+            // Rust >= 1.54.0
+            r#"<div id="main"><h2 id="required-methods">Required methods</h2><div class="methods">
+            <details><summary><div id="tymethod.foo" class="method"><h4 class="code-header">fn foo()</h4>
+            </div></summary><div class="docblock"><p>A foo.</p></div></details></div></div>"#,
+        ];
+        let parent: doc::Fqn = "test::Foo".to_owned().into();
+        for layout in &layouts {
+            let html = format!("<html><body>{}</body></html>", layout);
+            let document = super::Parser::from_string(html).unwrap().document;
+            let (_, groups) = super::get_methods(&document, &parent).unwrap();
+            assert_eq!(1, groups.len(), "{}", layout);
+            assert_eq!(Some("Required Methods"), groups[0].title.as_deref());
+            assert_eq!(1, groups[0].members.len(), "{}", layout);
+            let member = &groups[0].members[0];
+            assert_eq!("test::Foo::foo", member.name.as_ref());
+            assert_eq!(doc::ItemType::TyMethod, member.ty);
+            assert_eq!("A foo.", member.description.as_ref().unwrap().plain.trim());
+        }
+    }
+
+    #[test]
+    fn test_member_item_info() {
+        let item_info = r#"<span class="stab deprecated"><span class="emoji">👎</span> Deprecated since 1.42.0: <p>use X</p></span>
+            <div class="stab portability">This is supported on <strong>Unix</strong> only.</div>"#;
+        let layouts = [
+            format!(
+                r#"<h4 id="method.foo" class="method"><code>fn foo()</code></h4>
+                <div class="stability">{}</div>
+                <div class="docblock"><p>A foo.</p></div>"#,
+                item_info
+            ),
+            format!(
+                r#"<details><summary><div id="method.foo" class="method"><h4 class="code-header">fn foo()</h4></div></summary>
+                <div class="item-info">{}</div>
+                <div class="docblock"><p>A foo.</p></div></details>"#,
+                item_info
+            ),
+        ];
+        let parent: doc::Fqn = "test::Foo".to_owned().into();
+        for layout in &layouts {
+            let html = format!(
+                r#"<html><body><div class="impl-items">{}</div></body></html>"#,
+                layout
+            );
+            let document = super::Parser::from_string(html).unwrap().document;
+            let impl_items = super::select_first(&document, "div.impl-items")
+                .unwrap()
+                .unwrap();
+            let group = super::get_method_group(
+                &parent,
+                None,
+                impl_items.as_node(),
+                doc::ItemType::Method,
+                &markup5ever::local_name!("h4"),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(1, group.members.len());
+            let member = &group.members[0];
+            assert_eq!("test::Foo::foo", member.name.as_ref());
+            let deprecated = member.deprecated.as_ref().unwrap();
+            assert_eq!("Deprecated since 1.42.0: use X", deprecated.to_string());
+            assert_eq!(Some("1.42.0"), deprecated.since.as_deref());
+            assert_eq!(Some("use X"), deprecated.note.as_deref());
+            assert_eq!(None, deprecated.replacement);
+            assert_eq!(Some("Unix"), member.platform.as_deref());
+            assert_eq!("A foo.", member.description.as_ref().unwrap().plain.trim());
+        }
+    }
+
+    #[test]
+    fn test_parse_deprecation() {
+        let parse = |html: &str| {
+            let html = format!(
+                r#"<html><body><div class="item-info">{}</div></body></html>"#,
+                html
+            );
+            let document = super::Parser::from_string(html).unwrap().document;
+            let node = super::select_first(&document, "div.item-info")
+                .unwrap()
+                .unwrap();
+            let deprecated = super::ItemInfo::parse(node.as_node())
+                .unwrap()
+                .deprecated
+                .unwrap();
+            (deprecated.since, deprecated.note, deprecated.replacement)
+        };
+        let s = |s: &str| Some(s.to_owned());
+        assert_eq!(
+            (
+                s("1.33.0"),
+                s("replaced by Error::source, which can support downcasting"),
+                s("Error::source")
+            ),
+            parse(
+                r#"<div class='stab deprecated'>Deprecated since 1.33.0: <p>replaced by
+                Error::source, which can support downcasting</p></div>"#
+            )
+        );
+        assert_eq!(
+            (None, s("use foo::bar instead"), s("foo::bar")),
+            parse(
+                r#"<span class="stab deprecated"><span class="emoji">👎</span> Deprecated:
+                <p>use <code>foo::bar</code> instead</p></span>"#
+            )
+        );
+        assert_eq!(
+            (s("1.42.0"), None, None),
+            parse(r#"<div class="stab deprecated">Deprecated since 1.42.0</div>"#)
+        );
+    }
+
+    #[test]
+    fn test_is_hidden() {
+        let layouts = [
+            r#"<div id="main"><h1 class="fqn">Struct <a>Foo</a></h1>
+            <div class="item-info"><div class="stab hidden">Hidden</div></div></div>"#,
+            r#"<section id="main-content"><div class="main-heading"><h1>Struct <a>Foo</a>
+            <span class="stab hidden">Hidden</span></h1></div></section>"#,
+        ];
+        for layout in &layouts {
+            let html = format!("<html><body>{}</body></html>", layout);
+            let document = super::Parser::from_string(html).unwrap().document;
+            assert!(super::is_hidden(&document).unwrap(), "{}", layout);
+        }
+
+        // Hidden members do not make the item hidden.
+        let html = r#"<html><body><div id="main"><h1 class="fqn">Struct <a>Foo</a></h1>
+            <div class="impl-items"><div class="item-info"><div class="stab hidden">Hidden</div>
+            </div></div></div></body></html>"#;
+        let document = super::Parser::from_string(html).unwrap().document;
+        assert!(!super::is_hidden(&document).unwrap());
+
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("struct.NodeRef.html");
+            let document = super::Parser::from_file(path).unwrap().document;
+            assert!(!super::is_hidden(&document).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_get_deprecation() {
+        let notice = r#"<span class="stab deprecated"><span class="emoji">👎</span> Deprecated since 1.42.0: <p>use <code>Bar</code> instead</p></span>"#;
+        let layouts = [
+            format!(
+                r#"<div id="main"><h1 class="fqn">Struct <a>Foo</a></h1>
+                <div class="stability">{}</div></div>"#,
+                notice
+            ),
+            format!(
+                r#"<section id="main-content"><div class="main-heading"><h1>Struct <a>Foo</a></h1></div>
+                <div class="item-info">{}</div></section>"#,
+                notice
+            ),
+        ];
+        for layout in &layouts {
+            let html = format!("<html><body>{}</body></html>", layout);
+            let document = super::Parser::from_string(html).unwrap().document;
+            let deprecated = super::get_deprecation(&document).unwrap().unwrap();
+            assert_eq!(Some("1.42.0"), deprecated.since.as_deref(), "{}", layout);
+            assert_eq!(Some("Bar"), deprecated.replacement.as_deref(), "{}", layout);
+        }
+
+        // Deprecated members do not make the item deprecated.
+        let html = r#"<html><body><div id="main"><h1 class="fqn">Struct <a>Foo</a></h1>
+            <div class="impl-items"><div class="item-info"><span class="stab deprecated">Deprecated
+            </span></div></div></div></body></html>"#;
+        let document = super::Parser::from_string(html).unwrap().document;
+        assert_eq!(None, super::get_deprecation(&document).unwrap());
+    }
+
     #[test]
     fn test_parse_member_doc() {
         with_rustdoc("*", Format::all(), |_, _, path| {
@@ -980,6 +2804,109 @@ mod tests {
                 definition
             );
             assert!(doc.description.is_some());
+            assert_eq!(Some(doc::Visibility::Public), doc.visibility);
         });
     }
+
+    #[test]
+    fn test_private_item_marker() {
+        let layouts = [
+            r#"<h2 id="structs">Structs</h2><table>
+            <tr><td><a class="struct" href="struct.Foo.html">Foo</a></td><td class="docblock-short">A foo.</td></tr>
+            <tr><td><a class="struct" href="struct.Bar.html">Bar</a>&nbsp;<span title="Restricted Visibility">🔒</span></td><td class="docblock-short">A bar.</td></tr>
+            </table>"#,
+            concat!(
+                r#"<h2 id="structs">Structs</h2><div class="item-table">"#,
+                r#"<div class="item-left module-item"><a class="struct" href="struct.Foo.html">Foo</a></div>"#,
+                r#"<div class="item-right docblock-short">A foo.</div>"#,
+                r#"<div class="item-left module-item"><a class="struct" href="struct.Bar.html">Bar</a>"#,
+                r#"<span title="Restricted Visibility">&nbsp;🔒</span></div>"#,
+                r#"<div class="item-right docblock-short">A bar.</div></div>"#,
+            ),
+        ];
+        let parent: doc::Fqn = "test".to_owned().into();
+        for layout in &layouts {
+            let html = format!("<html><body>{}</body></html>", layout);
+            let document = super::Parser::from_string(html).unwrap().document;
+            let members = super::get_members(&document, &parent, doc::ItemType::Struct).unwrap();
+            let members: Vec<_> = members
+                .iter()
+                .map(|doc| (doc.name.as_ref(), doc.visibility))
+                .collect();
+            assert_eq!(
+                vec![
+                    ("test::Foo", None),
+                    ("test::Bar", Some(doc::Visibility::Private)),
+                ],
+                members
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_members() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let parser = super::Parser::from_file(path.join("log").join("trait.Log.html")).unwrap();
+            let members = parser.list_members().unwrap();
+            let names: Vec<_> = members.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(vec!["enabled", "log", "flush"], names);
+
+            let path = path.join("kuchiki").join("struct.NodeRef.html");
+            let parser = super::Parser::from_file(path).unwrap();
+            let members = parser.list_members().unwrap();
+            let serialize: Vec<_> = members
+                .iter()
+                .filter(|(name, _)| name == "serialize")
+                .collect();
+            assert_eq!(
+                vec![&("serialize".to_owned(), doc::ItemType::Method)],
+                serialize
+            );
+        });
+    }
+
+    #[test]
+    fn test_find_duplicate_members() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("kuchiki").join("struct.NodeRef.html");
+            let parser = super::Parser::from_file(path).unwrap();
+            let name: doc::Fqn = "kuchiki::NodeRef::serialize".to_owned().into();
+            let members = parser.find_duplicate_members(&name).unwrap();
+            let members: Vec<_> = members
+                .iter()
+                .map(|(name, ty, title)| (name.as_ref(), *ty, title.as_deref()))
+                .collect();
+            assert_eq!(
+                vec![
+                    (
+                        "kuchiki::NodeRef::serialize",
+                        doc::ItemType::Method,
+                        Some("impl NodeRef")
+                    ),
+                    (
+                        "kuchiki::NodeRef::serialize-1",
+                        doc::ItemType::Method,
+                        Some("impl Serialize for NodeRef")
+                    ),
+                ],
+                members
+            );
+
+            let name: doc::Fqn = "kuchiki::NodeRef::serialize-1".to_owned().into();
+            let doc = parser
+                .parse_member_doc(&name, doc::ItemType::Method)
+                .unwrap();
+            assert_eq!("kuchiki::NodeRef::serialize", doc.name.as_ref());
+            assert!(doc.url.unwrap().ends_with("#method.serialize-1"));
+        });
+    }
+
+    #[test]
+    fn test_strip_id_suffix() {
+        assert_eq!("foo", super::strip_id_suffix("foo"));
+        assert_eq!("foo", super::strip_id_suffix("foo-1"));
+        assert_eq!("foo", super::strip_id_suffix("foo-12"));
+        assert_eq!("foo-bar", super::strip_id_suffix("foo-bar"));
+        assert_eq!("foo-", super::strip_id_suffix("foo-"));
+    }
 }
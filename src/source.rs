@@ -3,11 +3,19 @@
 
 //! Handles documentation sources, for example local directories.
 
+use std::cell;
+use std::cmp;
+use std::collections;
 use std::fs;
 use std::path;
+use std::process;
+use std::rc;
+use std::time;
 
 use anyhow::anyhow;
+use anyhow::Context as _;
 
+use crate::cache;
 use crate::doc;
 use crate::index;
 use crate::parser::html;
@@ -19,57 +27,421 @@ pub trait Source {
         name: &doc::Fqn,
         ty: Option<doc::ItemType>,
     ) -> anyhow::Result<Option<doc::Doc>>;
-    fn load_index(&self) -> anyhow::Result<Option<index::Index>>;
+    /// Returns the types of the items with the given name.  This only checks the item list and
+    /// the existing files and does not parse the documentation.
+    fn find_items(&self, name: &doc::Fqn) -> anyhow::Result<Vec<doc::ItemType>>;
+    /// Returns the members of the parent item that have the same name as the given member, see
+    /// `html::Parser::find_duplicate_members`.  The description of the returned items is the
+    /// title of the impl block that contains the member.
+    fn find_duplicate_members(&self, name: &doc::Fqn) -> anyhow::Result<Vec<index::IndexItem>>;
+    /// Returns the names of the members of the given item, see `html::Parser::list_members`, or
+    /// `None` if the item could not be found.
+    fn list_members(&self, name: &doc::Fqn) -> anyhow::Result<Option<Vec<doc::Fqn>>>;
+    /// Returns the search index of this source.  Sources may cache the loaded index.
+    fn load_index(&self) -> anyhow::Result<Option<rc::Rc<index::Index>>>;
+    fn has_crate(&self, name: &str) -> bool;
+    /// Returns the names of the crates in this source and the URLs of their main pages.
+    fn crate_roots(&self) -> anyhow::Result<Vec<(String, String)>>;
+    /// Returns the operations that are supported by this source.  `Sources` only calls the
+    /// methods of a source if it has the corresponding capability.
+    fn capabilities(&self) -> SourceCapabilities;
+    /// Returns the name of the item documented in this source if it is a single HTML file, see
+    /// `FileSource`.
+    fn file_item(&self) -> Option<&doc::Fqn>;
 }
 
+bitflags::bitflags! {
+    /// The operations that are supported by a source, see `Source::capabilities`.
+    pub struct SourceCapabilities: u8 {
+        /// Looking up items with `find_doc` and `find_items`.
+        const FIND = 0b0000_0001;
+        /// Searching items in the index returned by `load_index`.
+        const SEARCH = 0b0000_0010;
+        /// Listing the crates with `crate_roots`.
+        const LIST_CRATES = 0b0000_0100;
+        /// Listing the members of an item with `list_members` and `find_duplicate_members`.
+        const LIST_ITEMS = 0b0000_1000;
+        /// Watching the documentation files for changes, see the `--watch` option.
+        const WATCH = 0b0001_0000;
+    }
+}
+
+/// The maximum number of parent items that are loaded to check the visibility of search results,
+/// see `Sources::retain_public`.
+const MAX_VISIBILITY_PARENTS: usize = 20;
+
 /// A collection of sources.
-pub struct Sources(Vec<Box<dyn Source>>);
+pub struct Sources {
+    sources: Vec<Box<dyn Source>>,
+    /// Sources that are searched first for the crates matching the pattern, see `route`.
+    crate_sources: Vec<(String, Box<dyn Source>)>,
+    preferred_crate: Option<String>,
+    /// Whether items marked with `#[doc(hidden)]` are returned by `find`.
+    show_hidden: bool,
+}
 
 /// Local directory containing documentation data.
 ///
 /// The directory must contain documentation for one or more crates in subdirectories.  Suitable
 /// directories are the `doc` directory generated by `cargo doc` or the root directory of the Rust
 /// documentation.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct DirSource {
     path: path::PathBuf,
+    remote: Option<Remote>,
+    /// The search index set in the configuration, see `find_index`.
+    index: Option<path::PathBuf>,
+    /// The cached crate versions, see `get_crate_version`.
+    crate_versions: cell::RefCell<collections::HashMap<String, Option<String>>>,
+    /// The cached search index, see `load_index`.
+    loaded_index: cell::RefCell<Option<Option<rc::Rc<index::Index>>>>,
+    /// The maximum size of the HTML files that are parsed in bytes, see `parse_file`.
+    max_file_size: u64,
+}
+
+/// A single HTML file generated by rustdoc, for example a downloaded documentation page.
+///
+/// The name of the item is read from the heading of the page and its type from the file name.
+/// Only this item and its members can be looked up.  The file is only parsed once.
+pub struct FileSource {
+    path: path::PathBuf,
+    name: doc::Fqn,
+    ty: doc::ItemType,
+    parser: html::Parser,
+    crate_version: Option<String>,
+}
+
+/// A website that files missing in a directory source are downloaded from.
+///
+/// The directory is used as a cache for the downloaded files.  Only the crates listed in `crates`
+/// are looked up on the website.
+#[derive(Clone, Debug, PartialEq)]
+struct Remote {
+    url: String,
+    crates: Vec<String>,
+}
+
+/// The crates documented on doc.rust-lang.org.
+const STD_CRATES: &[&str] = &["std", "alloc", "core", "proc_macro", "test"];
+
+/// Whether the given crate is part of the standard library, i. e. whether its documentation can be
+/// downloaded with the `--online` option.
+pub fn is_std_crate(krate: &str) -> bool {
+    STD_CRATES.contains(&krate)
 }
 
 impl Sources {
     pub fn new(sources: Vec<Box<dyn Source>>) -> Sources {
-        Sources(sources)
+        Sources {
+            sources,
+            crate_sources: Vec::new(),
+            preferred_crate: None,
+            show_hidden: false,
+        }
+    }
+
+    /// Use the given source for the crates matching the given pattern:  It is searched before all
+    /// other sources when looking up and searching items of these crates.
+    ///
+    /// The pattern is either a crate name or a prefix followed by `*`.  If multiple patterns match
+    /// a crate, exact matches are used before prefix matches, and longer prefixes before shorter
+    /// ones.
+    pub fn route(&mut self, pattern: &str, source: Box<dyn Source>) {
+        self.crate_sources.push((pattern.replace('-', "_"), source));
+        // sort_by_key is stable, so the order of equally specific patterns is preserved
+        self.crate_sources
+            .sort_by_key(|(pattern, _)| (pattern.ends_with('*'), cmp::Reverse(pattern.len())));
     }
 
-    /// Find the documentation for an item with the given name (exact matches only).
+    /// Returns the sources with the given capabilities that should be searched for the given
+    /// crate, starting with the sources set with `route`.
+    fn get_sources<'a>(
+        &'a self,
+        krate: &'a str,
+        capabilities: SourceCapabilities,
+    ) -> impl Iterator<Item = &'a dyn Source> {
+        self.crate_sources
+            .iter()
+            .filter(move |(pattern, _)| matches_crate(pattern, krate))
+            .map(|(_, source)| source.as_ref())
+            .chain(self.sources.iter().map(AsRef::as_ref))
+            .filter(move |source| source.capabilities().contains(capabilities))
+    }
+
+    /// Returns all sources with the given capabilities together with the crate pattern if they
+    /// have been set with `route`.
+    fn get_all_sources(
+        &self,
+        capabilities: SourceCapabilities,
+    ) -> impl Iterator<Item = (Option<&String>, &dyn Source)> {
+        self.crate_sources
+            .iter()
+            .map(|(pattern, source)| (Some(pattern), source.as_ref()))
+            .chain(self.sources.iter().map(|source| (None, source.as_ref())))
+            .filter(move |(_, source)| source.capabilities().contains(capabilities))
+    }
+
+    /// Prefer the given crate when looking up items:  Sources that contain the crate are searched
+    /// first, and search results from the crate are listed first.
+    pub fn prefer(&mut self, krate: &str) {
+        // sort_by_key is stable, so the order of the other sources is preserved
+        self.sources.sort_by_key(|s| !s.has_crate(krate));
+        self.preferred_crate = Some(krate.to_owned());
+    }
+
+    /// Sets whether `find` returns items that are marked with `#[doc(hidden)]`, see the
+    /// `--show-hidden` option.
+    pub fn show_hidden(&mut self, show_hidden: bool) {
+        self.show_hidden = show_hidden;
+    }
+
+    /// Find the documentation for an item with the given name (exact matches only).  Hidden items
+    /// are ignored unless `show_hidden` is set.
     pub fn find(
         &self,
         name: &doc::Name,
         ty: Option<doc::ItemType>,
     ) -> anyhow::Result<Option<doc::Doc>> {
-        let fqn = name.clone().into();
-        for source in &self.0 {
-            if let Some(doc) = source.find_doc(&fqn, ty)? {
-                return Ok(Some(doc));
+        let fqn: doc::Fqn = name.clone().into();
+        // If a source fails, e. g. because of a file that is too large to parse, the other sources
+        // are still searched and the error is only returned if none of them contains the item.
+        let mut error = None;
+        for source in self.get_sources(fqn.krate(), SourceCapabilities::FIND) {
+            match source.find_doc(&fqn, ty) {
+                Ok(Some(doc)) => {
+                    if doc.hidden && !self.show_hidden {
+                        log::info!("Ignoring hidden item '{}'", fqn);
+                        return Ok(None);
+                    }
+                    return Ok(Some(doc));
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    log::info!("Could not look up '{}' in a source: {:#}", fqn, err);
+                    error.get_or_insert(err);
+                }
             }
         }
+        if let Some(err) = error {
+            return Err(err);
+        }
         log::info!("Could not find item '{}'", fqn);
         Ok(None)
     }
 
+    /// Returns the types of the other items that have the same name as the given item, for
+    /// example a macro with the same name as a module.
+    pub fn find_alternatives(&self, doc: &doc::Doc) -> anyhow::Result<Vec<doc::ItemType>> {
+        let mut types = Vec::new();
+        for source in self.get_sources(doc.name.krate(), SourceCapabilities::FIND) {
+            for ty in source.find_items(&doc.name)? {
+                if ty != doc.ty && !types.contains(&ty) {
+                    types.push(ty);
+                }
+            }
+        }
+        Ok(types)
+    }
+
+    /// Whether any of the sources contains the documentation for the given crate.
+    pub fn has_crate(&self, krate: &str) -> bool {
+        let krate = krate.replace('-', "_");
+        let has_crate = self
+            .get_sources(&krate, SourceCapabilities::empty())
+            .any(|source| source.has_crate(&krate));
+        has_crate
+    }
+
+    /// Returns the name of the item documented in the first source that is a single HTML file.
+    pub fn file_item(&self) -> Option<&doc::Fqn> {
+        self.sources.iter().find_map(|source| source.file_item())
+    }
+
+    /// Whether the documentation files of the given crate can be watched for changes, i. e.
+    /// whether the first source that contains the crate supports the `--watch` option.
+    pub fn can_watch(&self, krate: &str) -> bool {
+        let krate = krate.replace('-', "_");
+        let can_watch = self
+            .get_sources(&krate, SourceCapabilities::empty())
+            .find(|source| source.has_crate(&krate))
+            .map(|source| source.capabilities().contains(SourceCapabilities::WATCH))
+            .unwrap_or(false);
+        can_watch
+    }
+
+    /// Returns all items of the given crate from the search index of the first source that
+    /// contains the crate.
+    pub fn crate_items(&self, krate: &str) -> anyhow::Result<Vec<index::IndexItem>> {
+        let krate = krate.replace('-', "_");
+        for source in self.get_sources(&krate, SourceCapabilities::SEARCH) {
+            if !source.has_crate(&krate) {
+                continue;
+            }
+            if let Some(index) = source.load_index()? {
+                let items = index.crate_items(&krate);
+                if !items.is_empty() {
+                    return Ok(items);
+                }
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Returns the names of all crates in the sources and the URLs of their main pages, sorted by
+    /// the crate name.  If multiple sources contain the same crate, the URL from the source with
+    /// the highest priority is used.
+    pub fn crate_root_urls(&self) -> anyhow::Result<Vec<(String, String)>> {
+        let mut roots = collections::BTreeMap::new();
+        for (pattern, source) in self.get_all_sources(SourceCapabilities::LIST_CRATES) {
+            for (krate, url) in source.crate_roots()? {
+                if pattern.map(|p| matches_crate(p, &krate)).unwrap_or(true) {
+                    roots.entry(krate).or_insert(url);
+                }
+            }
+        }
+        Ok(roots.into_iter().collect())
+    }
+
+    /// Returns the members with the same name as the given member from the first source that
+    /// contains the member, see `Source::find_duplicate_members`.
+    pub fn find_duplicate_members(&self, name: &doc::Fqn) -> anyhow::Result<Vec<index::IndexItem>> {
+        for source in self.get_sources(name.krate(), SourceCapabilities::LIST_ITEMS) {
+            let members = source.find_duplicate_members(name)?;
+            if !members.is_empty() {
+                return Ok(members);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Returns the names of the members of the given item from the first source that contains
+    /// it, see `Source::list_members`.
+    pub fn list_members(&self, name: &doc::Fqn) -> anyhow::Result<Option<Vec<doc::Fqn>>> {
+        for source in self.get_sources(name.krate(), SourceCapabilities::LIST_ITEMS) {
+            if let Some(members) = source.list_members(name)? {
+                return Ok(Some(members));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Removes the items that are not public from the given search results.  The visibility is
+    /// read from the member lists on the documentation pages of the parent items, so every parent
+    /// is only loaded once.  At most `MAX_VISIBILITY_PARENTS` parents are loaded.  Items whose
+    /// visibility is unknown, for example because their parent could not be loaded, are kept.
+    pub fn retain_public(&self, items: &mut Vec<index::IndexItem>) {
+        let mut private_members = collections::BTreeMap::new();
+        for parent in items.iter().filter_map(|item| item.name.parent()) {
+            if private_members.contains_key(&parent) {
+                continue;
+            }
+            if private_members.len() >= MAX_VISIBILITY_PARENTS {
+                log::info!(
+                    "Not checking the visibility of the remaining items because there are more \
+                     than {} parents",
+                    MAX_VISIBILITY_PARENTS
+                );
+                break;
+            }
+            let members: Vec<(doc::Fqn, doc::ItemType)> = match self.find(&parent, None) {
+                Ok(doc) => doc
+                    .iter()
+                    .flat_map(|doc| doc.groups.values().flatten())
+                    .flat_map(|group| &group.members)
+                    .filter(|member| !member.is_public())
+                    .map(|member| (member.name.clone(), member.ty))
+                    .collect(),
+                Err(err) => {
+                    log::warn!(
+                        "Could not check the visibility of the members of {}: {}",
+                        parent,
+                        err
+                    );
+                    Vec::new()
+                }
+            };
+            private_members.insert(parent, members);
+        }
+        items.retain(|item| {
+            let private = item
+                .name
+                .parent()
+                .and_then(|parent| private_members.get(&parent))
+                .map(|members| members.contains(&(item.name.clone(), item.ty)))
+                .unwrap_or(false);
+            !private
+        });
+    }
+
     /// Use the search index to find an item that partially matches the given keyword.
+    ///
+    /// If multiple sources contain the same item, only the item from the source with the highest
+    /// priority, i. e. the source that is searched first, is returned.
     pub fn search(&self, name: &doc::Name) -> anyhow::Result<Vec<index::IndexItem>> {
-        let indexes = self
-            .0
-            .iter()
-            .filter_map(|s| s.load_index().transpose())
-            .collect::<anyhow::Result<Vec<_>>>()?;
-        let mut items = indexes
-            .iter()
-            .map(|i| i.find(name))
-            .collect::<Vec<_>>()
-            .concat();
+        self.search_index(|index| index.find(name))
+    }
+
+    /// Use the search index to find items that start with the given prefix, see
+    /// `index::Index::find_prefix`.  At most `limit` items are returned, starting with the
+    /// shortest names.
+    pub fn search_prefix(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<index::IndexItem>> {
+        let mut items = self.search_index(|index| index.find_prefix(prefix))?;
+        items.sort_by_key(|item| item.name.as_ref().len());
+        items.truncate(limit);
+        Ok(items)
+    }
+
+    /// Collects the matches returned by the given function for the search indexes of all sources.
+    /// If multiple sources contain the same item, only the item from the source with the highest
+    /// priority is returned.
+    fn search_index<F>(&self, find: F) -> anyhow::Result<Vec<index::IndexItem>>
+    where
+        F: Fn(&index::Index) -> Vec<index::IndexItem>,
+    {
+        let sources: Vec<_> = self.get_all_sources(SourceCapabilities::SEARCH).collect();
+        let n = sources.len();
+        let mut matches: collections::HashMap<_, index::IndexItem> = collections::HashMap::new();
+        for (i, (pattern, source)) in sources.into_iter().enumerate() {
+            if let Some(index) = source.load_index()? {
+                for mut item in find(&index) {
+                    // Sources set with route are only used for the matching crates.
+                    if let Some(pattern) = pattern {
+                        if !matches_crate(pattern, item.name.krate()) {
+                            continue;
+                        }
+                    }
+                    item.source_priority = n - i;
+                    let key = (
+                        item.name
+                            .parent()
+                            .map(|p| p.to_string())
+                            .unwrap_or_default(),
+                        item.name.last().to_owned(),
+                        item.ty,
+                    );
+                    match matches.entry(key) {
+                        collections::hash_map::Entry::Occupied(mut entry) => {
+                            if entry.get().source_priority < item.source_priority {
+                                entry.insert(item);
+                            }
+                        }
+                        collections::hash_map::Entry::Vacant(entry) => {
+                            entry.insert(item);
+                        }
+                    }
+                }
+            }
+        }
+        let mut items: Vec<_> = matches.into_values().collect();
         items.sort_unstable();
-        items.dedup();
+        if let Some(krate) = &self.preferred_crate {
+            let krate = krate.replace('-', "_");
+            items.sort_by_key(|item| item.name.krate() != krate);
+        }
         Ok(items)
     }
 }
@@ -77,7 +449,55 @@ impl Sources {
 impl DirSource {
     fn new(path: path::PathBuf) -> Self {
         log::info!("Created directory source at '{}'", path.display());
-        Self { path }
+        Self {
+            path,
+            remote: None,
+            index: None,
+            crate_versions: Default::default(),
+            loaded_index: Default::default(),
+            max_file_size: html::DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+
+    fn with_remote(path: path::PathBuf, remote: Remote) -> Self {
+        log::info!(
+            "Created directory source at '{}' for '{}'",
+            path.display(),
+            &remote.url
+        );
+        Self {
+            path,
+            remote: Some(remote),
+            index: None,
+            crate_versions: Default::default(),
+            loaded_index: Default::default(),
+            max_file_size: html::DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+
+    /// Parses the given HTML file unless it is larger than the limit set with `--max-file-size`.
+    fn parse_file(&self, path: impl AsRef<path::Path>) -> anyhow::Result<html::Parser> {
+        html::Parser::from_file_with_limit(path, self.max_file_size)
+    }
+
+    /// Returns the given path if it is a file in this directory.  If the file does not exist and
+    /// this source has a remote, the file is downloaded first.
+    fn get_file(&self, path: &path::Path) -> anyhow::Result<Option<path::PathBuf>> {
+        if path.is_file() {
+            return Ok(Some(path.to_owned()));
+        }
+        if let Some(remote) = &self.remote {
+            let relative = path.strip_prefix(&self.path)?;
+            let components: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            let url = format!("{}/{}", remote.url, components.join("/"));
+            if download(&url, path)? {
+                return Ok(Some(path.to_owned()));
+            }
+        }
+        Ok(None)
     }
 
     fn find_doc_html(
@@ -93,58 +513,115 @@ impl DirSource {
                 | doc::ItemType::Variant
                 | doc::ItemType::AssocType
                 | doc::ItemType::AssocConst
-                | doc::ItemType::Method => self.get_member(path, name),
-                _ => self.get_item(path, name),
+                | doc::ItemType::Method
+                | doc::ItemType::TyMethod => self.get_member(path, name),
+                _ => self.get_item(path, name, Some(ty)),
             }
         } else {
-            self.get_item(path, name)
-                .transpose()
-                .or_else(|| self.get_module(path, name).transpose())
-                .or_else(|| self.get_member(path, name).transpose())
-                .transpose()
+            // If one of the candidates cannot be parsed, the other candidates are still tried.
+            let candidates: [&dyn Fn() -> anyhow::Result<Option<doc::Doc>>; 3] = [
+                &|| self.get_item(path, name, None),
+                &|| self.get_module(path, name),
+                &|| self.get_member(path, name),
+            ];
+            let mut error = None;
+            for candidate in &candidates {
+                match candidate() {
+                    Ok(Some(doc)) => return Ok(Some(doc)),
+                    Ok(None) => {}
+                    Err(err) => {
+                        log::warn!("Skipping a candidate for '{}': {:#}", name, err);
+                        error.get_or_insert(err);
+                    }
+                }
+            }
+            error.map(Err).unwrap_or(Ok(None))
         }
     }
 
-    fn get_crate(&self, name: &str) -> Option<path::PathBuf> {
+    fn get_crate(&self, name: &str) -> anyhow::Result<Option<path::PathBuf>> {
         log::info!(
             "Searching crate '{}' in dir source '{}'",
             name,
             self.path.display()
         );
-        let crate_path = self.path.join(name.replace('-', "_"));
-        if crate_path.join("all.html").is_file() {
+        let name = name.replace('-', "_");
+        if let Some(remote) = &self.remote {
+            if !remote.crates.contains(&name) {
+                log::info!("Crate '{}' is not available at '{}'", name, &remote.url);
+                return Ok(None);
+            }
+        }
+        let crate_path = self.path.join(&name);
+        if self.get_file(&crate_path.join("all.html"))?.is_some() {
+            log::info!("Found crate '{}': '{}'", name, crate_path.display());
+            Ok(Some(crate_path))
+        } else if let Some(crate_path) = self.find_nested_crate(&name)? {
             log::info!("Found crate '{}': '{}'", name, crate_path.display());
-            Some(crate_path)
+            Ok(Some(crate_path))
         } else {
             log::info!("Did not find crate '{}' in '{}'", name, self.path.display());
-            None
+            Ok(None)
+        }
+    }
+
+    /// Searches the direct subdirectories of this directory for the given crate, for example if
+    /// the source is the parent of a `doc` directory generated by `cargo doc`.  Subdirectories
+    /// that are crate directories themselves are skipped so that modules are not mistaken for
+    /// crates.
+    fn find_nested_crate(&self, name: &str) -> anyhow::Result<Option<path::PathBuf>> {
+        // Nested crates cannot be downloaded from a remote.
+        if self.remote.is_some() {
+            return Ok(None);
+        }
+        Ok(self
+            .nested_dirs()?
+            .into_iter()
+            .map(|dir| dir.join(name))
+            .find(|crate_path| crate_path.join("index.html").is_file()))
+    }
+
+    /// Returns the subdirectories of this source that are not crate directories and may contain
+    /// nested crates, see `find_nested_crate`.
+    fn nested_dirs(&self) -> anyhow::Result<Vec<path::PathBuf>> {
+        let mut dirs = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if path.is_dir() && !path.join("all.html").is_file() {
+                dirs.push(path);
+            }
         }
+        dirs.sort_unstable();
+        Ok(dirs)
     }
 
-    fn get_item(&self, root: &path::Path, name: &doc::Fqn) -> anyhow::Result<Option<doc::Doc>> {
+    fn get_item(
+        &self,
+        root: &path::Path,
+        name: &doc::Fqn,
+        ty: Option<doc::ItemType>,
+    ) -> anyhow::Result<Option<doc::Doc>> {
         log::info!(
             "Searching item '{}' in directory '{}'",
             name,
             root.display()
         );
         if let Some(local_name) = name.rest() {
-            let parser = html::Parser::from_file(root.join("all.html"))?;
-            if let Some(path) = parser.find_item(local_name)? {
-                let file_name = path::Path::new(&path)
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap();
-                let ty: doc::ItemType = file_name.splitn(2, '.').next().unwrap().parse()?;
-                html::Parser::from_file(root.join(path))?
-                    .parse_item_doc(name, ty)
-                    .map(Some)
-            } else {
-                Ok(None)
+            let parser = self.parse_file(root.join("all.html"))?;
+            for path in parser.find_items(local_name)? {
+                let item_ty = get_item_type(&path)?;
+                if ty.map(|ty| ty != item_ty).unwrap_or(false) {
+                    continue;
+                }
+                if let Some(path) = self.get_file(&root.join(path))? {
+                    return self
+                        .parse_file(path)?
+                        .parse_item_doc(name, item_ty)
+                        .map(Some);
+                }
             }
-        } else {
-            Ok(None)
         }
+        Ok(None)
     }
 
     fn get_module(&self, root: &path::Path, name: &doc::Fqn) -> anyhow::Result<Option<doc::Doc>> {
@@ -153,21 +630,9 @@ impl DirSource {
             name,
             root.display()
         );
-        let module_path = if let Some(local_name) = name.rest() {
-            local_name
-                .split("::")
-                .fold(path::PathBuf::new(), |mut p, s| {
-                    p.push(s);
-                    p
-                })
-        } else {
-            path::PathBuf::new()
-        };
-        let path = root.join(module_path).join("index.html");
-        if path.is_file() {
-            html::Parser::from_file(path)?
-                .parse_module_doc(name)
-                .map(Some)
+        let module_path: path::PathBuf = name.components().skip(1).collect();
+        if let Some(path) = self.get_file(&root.join(module_path).join("index.html"))? {
+            self.parse_file(path)?.parse_module_doc(name).map(Some)
         } else {
             Ok(None)
         }
@@ -179,14 +644,38 @@ impl DirSource {
             name,
             root.display()
         );
+        if let Some(parser) = self.get_parent_parser(root, name)? {
+            if let Some(ty) = parser.find_member(name)? {
+                return parser.parse_member_doc(name, ty).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns a parser for the page of the parent item of the given member.
+    fn get_parent_parser(
+        &self,
+        root: &path::Path,
+        name: &doc::Fqn,
+    ) -> anyhow::Result<Option<html::Parser>> {
         if let Some(parent) = name.parent() {
-            if let Some(rest) = parent.rest() {
-                let parser = html::Parser::from_file(root.join("all.html"))?;
-                if let Some(path) = parser.find_item(rest)? {
-                    let parser = html::Parser::from_file(root.join(path))?;
-                    if let Some(ty) = parser.find_member(name)? {
-                        return parser.parse_member_doc(name, ty).map(Some);
-                    }
+            self.get_item_parser(root, &parent)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns a parser for the page of the given item as listed in `all.html`.
+    fn get_item_parser(
+        &self,
+        root: &path::Path,
+        name: &doc::Fqn,
+    ) -> anyhow::Result<Option<html::Parser>> {
+        if let Some(rest) = name.rest() {
+            let parser = self.parse_file(root.join("all.html"))?;
+            if let Some(path) = parser.find_item(rest)? {
+                if let Some(path) = self.get_file(&root.join(path))? {
+                    return self.parse_file(path).map(Some);
                 }
             }
         }
@@ -194,6 +683,98 @@ impl DirSource {
     }
 }
 
+impl DirSource {
+    /// Returns the version of the crate in the given directory as displayed on the crate root
+    /// page, if it is available.  The version is only looked up once per crate.
+    fn get_crate_version(&self, crate_path: &path::Path, krate: &str) -> Option<String> {
+        if let Some(version) = self.crate_versions.borrow().get(krate) {
+            return version.clone();
+        }
+        let version = match self.read_crate_version(crate_path) {
+            Ok(version) => version,
+            Err(err) => {
+                log::warn!("Could not read the version of crate '{}': {}", krate, err);
+                None
+            }
+        };
+        self.crate_versions
+            .borrow_mut()
+            .insert(krate.to_owned(), version.clone());
+        version
+    }
+
+    fn read_crate_version(&self, crate_path: &path::Path) -> anyhow::Result<Option<String>> {
+        if let Some(path) = self.get_file(&crate_path.join("index.html"))? {
+            self.parse_file(path)?.find_crate_version()
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the search index set in the configuration or the most recently modified
+    /// `search-index*.js` file in this directory or one of its direct subdirectories.
+    fn find_index(&self) -> anyhow::Result<Option<path::PathBuf>> {
+        if let Some(index) = &self.index {
+            return Ok(Some(self.path.join(index)));
+        }
+        let mut indexes = find_search_indexes(&self.path)?;
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                indexes.append(&mut find_search_indexes(&entry.path())?);
+            }
+        }
+        if indexes.len() > 1 {
+            log::info!(
+                "Found {} search indexes for '{}', using the newest one",
+                indexes.len(),
+                self.path.display()
+            );
+        }
+        Ok(indexes
+            .into_iter()
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, path)| path))
+    }
+
+    /// Searches and parses the search index of this source.  The result is cached by
+    /// `load_index`.
+    fn read_index(&self) -> anyhow::Result<Option<index::Index>> {
+        log::info!("Searching search index for '{}'", self.path.display());
+        if let Some(path) = self.find_index()? {
+            log::info!("Found search index '{}'", path.display());
+            return index::Index::load(path);
+        }
+        if let Some(path) = self.get_remote_index()? {
+            return index::Index::load(path);
+        }
+        log::info!("Could not find search index for '{}'", self.path.display());
+        Ok(None)
+    }
+
+    /// Downloads the search index from the remote, if any.  The file name of the search index is
+    /// read from the main page of the first crate of the remote.
+    fn get_remote_index(&self) -> anyhow::Result<Option<path::PathBuf>> {
+        if let Some(krate) = self.remote.as_ref().and_then(|r| r.crates.first()) {
+            let crate_path = self.path.join(krate);
+            if let Some(crate_index) = self.get_file(&crate_path.join("index.html"))? {
+                let index_path = self
+                    .parse_file(crate_index)?
+                    .find_search_index()?
+                    .unwrap_or_else(|| "../search-index.js".to_owned());
+                let path = normalize(&crate_path.join(index_path));
+                anyhow::ensure!(
+                    path.starts_with(&self.path),
+                    "Search index path '{}' is outside of the source directory",
+                    path.display()
+                );
+                return self.get_file(&path);
+            }
+        }
+        Ok(None)
+    }
+}
+
 impl Source for DirSource {
     fn find_doc(
         &self,
@@ -205,9 +786,10 @@ impl Source for DirSource {
             name,
             self.path.display()
         );
-        if let Some(crate_path) = self.get_crate(name.krate()) {
-            let doc = self.find_doc_html(&crate_path, name, ty)?;
-            if doc.is_some() {
+        if let Some(crate_path) = self.get_crate(name.krate())? {
+            let mut doc = self.find_doc_html(&crate_path, name, ty)?;
+            if let Some(doc) = &mut doc {
+                doc.crate_version = self.get_crate_version(&crate_path, name.krate());
                 log::info!(
                     "Found documentation for '{}' in dir source '{}'",
                     name,
@@ -231,28 +813,447 @@ impl Source for DirSource {
         }
     }
 
-    fn load_index(&self) -> anyhow::Result<Option<index::Index>> {
-        log::info!("Searching search index for '{}'", self.path.display());
-        // use the first file that matches the pattern search-index*.js
-        for entry in fs::read_dir(&self.path)? {
-            let entry = entry?;
-            if entry.file_type()?.is_file() {
-                if let Some(s) = entry.file_name().to_str() {
-                    if s.starts_with("search-index") && s.ends_with(".js") {
-                        log::info!("Found search index '{}'", &entry.path().display());
-                        return index::Index::load(&entry.path());
+    fn find_items(&self, name: &doc::Fqn) -> anyhow::Result<Vec<doc::ItemType>> {
+        let mut types = Vec::new();
+        let crate_path = if let Some(crate_path) = self.get_crate(name.krate())? {
+            crate_path
+        } else {
+            return Ok(types);
+        };
+        if let Some(local_name) = name.rest() {
+            let all_path = crate_path.join("all.html");
+            if all_path.is_file() {
+                let parser = self.parse_file(all_path)?;
+                for path in parser.find_items(local_name)? {
+                    types.push(get_item_type(&path)?);
+                }
+            }
+        }
+        let module_path: path::PathBuf = name.components().skip(1).collect();
+        if crate_path.join(module_path).join("index.html").is_file() {
+            types.push(doc::ItemType::Module);
+        }
+        Ok(types)
+    }
+
+    fn find_duplicate_members(&self, name: &doc::Fqn) -> anyhow::Result<Vec<index::IndexItem>> {
+        let mut items = Vec::new();
+        if let Some(crate_path) = self.get_crate(name.krate())? {
+            if let Some(parser) = self.get_parent_parser(&crate_path, name)? {
+                items = get_duplicate_members(&parser, name)?;
+            }
+        }
+        Ok(items)
+    }
+
+    fn list_members(&self, name: &doc::Fqn) -> anyhow::Result<Option<Vec<doc::Fqn>>> {
+        if let Some(crate_path) = self.get_crate(name.krate())? {
+            if let Some(parser) = self.get_item_parser(&crate_path, name)? {
+                let members = parser.list_members()?;
+                return Ok(Some(
+                    members
+                        .into_iter()
+                        .map(|(member, _)| name.child(&member))
+                        .collect(),
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    fn load_index(&self) -> anyhow::Result<Option<rc::Rc<index::Index>>> {
+        if let Some(index) = &*self.loaded_index.borrow() {
+            return Ok(index.clone());
+        }
+        let index = self.read_index()?.map(rc::Rc::new);
+        *self.loaded_index.borrow_mut() = Some(index.clone());
+        Ok(index)
+    }
+
+    fn has_crate(&self, name: &str) -> bool {
+        // Only the crate list of a remote is checked so that this never downloads files.
+        if let Some(remote) = &self.remote {
+            return remote.crates.contains(&name.replace('-', "_"));
+        }
+        match self.get_crate(name) {
+            Ok(path) => path.is_some(),
+            Err(err) => {
+                log::info!("Could not look up crate '{}': {}", name, err);
+                false
+            }
+        }
+    }
+
+    fn crate_roots(&self) -> anyhow::Result<Vec<(String, String)>> {
+        // The crates of a remote are not downloaded until they are used.
+        if let Some(remote) = &self.remote {
+            return Ok(remote
+                .crates
+                .iter()
+                .map(|krate| {
+                    (
+                        krate.clone(),
+                        format!("{}/{}/index.html", remote.url, krate),
+                    )
+                })
+                .collect());
+        }
+
+        // Crates in the source directory shadow nested crates with the same name, and nested
+        // crates in earlier subdirectories shadow those in later ones, as in `get_crate`.
+        let mut roots = get_crate_roots(&self.path)?;
+        for dir in self.nested_dirs()? {
+            for (krate, url) in get_crate_roots(&dir)? {
+                if !roots.iter().any(|(k, _)| *k == krate) {
+                    roots.push((krate, url));
+                }
+            }
+        }
+        roots.sort_unstable();
+        Ok(roots)
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        let capabilities = SourceCapabilities::all();
+        // Downloaded files are not updated, so there is nothing to watch.
+        if self.remote.is_some() {
+            capabilities - SourceCapabilities::WATCH
+        } else {
+            capabilities
+        }
+    }
+
+    fn file_item(&self) -> Option<&doc::Fqn> {
+        None
+    }
+}
+
+impl FileSource {
+    fn new(path: path::PathBuf, max_file_size: u64) -> anyhow::Result<Self> {
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .with_context(|| format!("Invalid file name: {}", path.display()))?;
+        let ty = if file_name == "index.html" {
+            doc::ItemType::Module
+        } else {
+            get_item_type(file_name)?
+        };
+        let parser = html::Parser::from_file_with_limit(&path, max_file_size)?;
+        let name = parser
+            .find_item_name()?
+            .with_context(|| format!("Could not find the item name in {}", path.display()))?;
+        let crate_version = parser.find_crate_version()?;
+        log::info!(
+            "Created file source for {} '{}' at '{}'",
+            ty.name(),
+            name,
+            path.display()
+        );
+        Ok(Self {
+            path,
+            name,
+            ty,
+            parser,
+            crate_version,
+        })
+    }
+
+    /// Returns the parser for the file if it documents the given item.
+    fn get_parser(&self, name: &doc::Fqn) -> Option<&html::Parser> {
+        if name == &self.name {
+            Some(&self.parser)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the parser for the file if it documents the parent of the given member.
+    fn get_parent_parser(&self, name: &doc::Fqn) -> Option<&html::Parser> {
+        name.parent().and_then(|parent| self.get_parser(&parent))
+    }
+}
+
+impl Source for FileSource {
+    fn find_doc(
+        &self,
+        name: &doc::Fqn,
+        ty: Option<doc::ItemType>,
+    ) -> anyhow::Result<Option<doc::Doc>> {
+        log::info!(
+            "Searching documentation for '{}' in file source '{}'",
+            name,
+            self.path.display()
+        );
+        let mut doc = None;
+        if ty.map(|ty| ty == self.ty).unwrap_or(true) {
+            if let Some(parser) = self.get_parser(name) {
+                doc = Some(if self.ty == doc::ItemType::Module {
+                    parser.parse_module_doc(name)?
+                } else {
+                    parser.parse_item_doc(name, self.ty)?
+                });
+            }
+        }
+        if doc.is_none() {
+            if let Some(parser) = self.get_parent_parser(name) {
+                if let Some(member_ty) = parser.find_member(name)? {
+                    if ty.map(|ty| ty == member_ty).unwrap_or(true) {
+                        doc = Some(parser.parse_member_doc(name, member_ty)?);
                     }
                 }
             }
         }
-        log::info!("Could not find search index for '{}'", self.path.display());
+        if let Some(doc) = &mut doc {
+            doc.crate_version = self.crate_version.clone();
+        }
+        Ok(doc)
+    }
+
+    fn find_items(&self, name: &doc::Fqn) -> anyhow::Result<Vec<doc::ItemType>> {
+        if name == &self.name {
+            Ok(vec![self.ty])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn find_duplicate_members(&self, name: &doc::Fqn) -> anyhow::Result<Vec<index::IndexItem>> {
+        if let Some(parser) = self.get_parent_parser(name) {
+            get_duplicate_members(parser, name)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn list_members(&self, name: &doc::Fqn) -> anyhow::Result<Option<Vec<doc::Fqn>>> {
+        if let Some(parser) = self.get_parser(name) {
+            let members = parser.list_members()?;
+            Ok(Some(
+                members
+                    .into_iter()
+                    .map(|(member, _)| name.child(&member))
+                    .collect(),
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn load_index(&self) -> anyhow::Result<Option<rc::Rc<index::Index>>> {
         Ok(None)
     }
+
+    fn has_crate(&self, name: &str) -> bool {
+        self.name.krate() == name.replace('-', "_")
+    }
+
+    fn crate_roots(&self) -> anyhow::Result<Vec<(String, String)>> {
+        // The file is only a crate root if it documents a crate.
+        if self.name.rest().is_some() {
+            return Ok(Vec::new());
+        }
+        let url = self
+            .path
+            .canonicalize()
+            .ok()
+            .and_then(|p| url::Url::from_file_path(p).ok());
+        Ok(url
+            .map(|url| (self.name.krate().to_owned(), url.to_string()))
+            .into_iter()
+            .collect())
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        // There is no search index for a single file.
+        SourceCapabilities::all() - SourceCapabilities::SEARCH
+    }
+
+    fn file_item(&self) -> Option<&doc::Fqn> {
+        Some(&self.name)
+    }
+}
+
+/// Returns the names and the URLs of the index pages of the crates in the given directory, see
+/// `Source::crate_roots`.
+fn get_crate_roots(dir: &path::Path) -> anyhow::Result<Vec<(String, String)>> {
+    let mut roots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let crate_path = entry.path();
+        if !crate_path.join("all.html").is_file() {
+            continue;
+        }
+        let index_path = crate_path.join("index.html").canonicalize();
+        let url = index_path
+            .ok()
+            .and_then(|p| url::Url::from_file_path(p).ok());
+        if let (Some(krate), Some(url)) = (entry.file_name().to_str(), url) {
+            roots.push((krate.to_owned(), url.to_string()));
+        }
+    }
+    Ok(roots)
+}
+
+/// Returns the members of the parent item that have the same name as the given member as index
+/// items, see `Source::find_duplicate_members`.
+fn get_duplicate_members(
+    parser: &html::Parser,
+    name: &doc::Fqn,
+) -> anyhow::Result<Vec<index::IndexItem>> {
+    Ok(parser
+        .find_duplicate_members(name)?
+        .into_iter()
+        .map(|(name, ty, title)| index::IndexItem {
+            name,
+            ty,
+            description: title.unwrap_or_default(),
+            source_priority: 0,
+            aliases: Vec::new(),
+        })
+        .collect())
+}
+
+/// Returns the item type for the given path of an item page, for example `macro.log.html`.
+fn get_item_type(path: &str) -> anyhow::Result<doc::ItemType> {
+    let file_name = path::Path::new(path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .with_context(|| format!("Invalid item path: {}", path))?;
+    file_name.split('.').next().unwrap().parse()
+}
+
+/// Removes `..` components from the given path.
+fn normalize(path: &path::Path) -> path::PathBuf {
+    let mut normalized = path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Downloads the file at the given URL to the given path using curl.  Returns `false` if the file
+/// does not exist.  The file is downloaded to a temporary file that is only moved to the given
+/// path if the download succeeded, so interrupted downloads do not leave truncated files.
+fn download(url: &str, path: &path::Path) -> anyhow::Result<bool> {
+    use anyhow::Context;
+
+    log::info!("Downloading '{}' to '{}'", url, path.display());
+    let dir = path
+        .parent()
+        .with_context(|| format!("Invalid download path '{}'", path.display()))?;
+    fs::create_dir_all(dir)?;
+    let file = tempfile::NamedTempFile::new_in(dir)?;
+    let status = process::Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .arg("--output")
+        .arg(file.path())
+        .arg(url)
+        .status()
+        .context("Could not execute curl")?;
+    if status.success() {
+        file.persist(path)?;
+        Ok(true)
+    } else if status.code() == Some(22) || status.code() == Some(37) {
+        // 22: HTTP error, 37: file:// URL that does not exist
+        log::info!("Could not find '{}'", url);
+        Ok(false)
+    } else {
+        Err(anyhow!("Could not download '{}'", url))
+    }
+}
+
+/// Returns the files in the given directory that match the pattern `search-index*.js` together
+/// with their modification time.
+fn find_search_indexes(
+    dir: &path::Path,
+) -> anyhow::Result<Vec<(Option<time::SystemTime>, path::PathBuf)>> {
+    let mut indexes = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            if let Some(s) = entry.file_name().to_str() {
+                if s.starts_with("search-index") && s.ends_with(".js") {
+                    let modified = entry.metadata()?.modified().ok();
+                    indexes.push((modified, entry.path()));
+                }
+            }
+        }
+    }
+    Ok(indexes)
+}
+
+/// Returns a source for the documentation of the standard library on doc.rust-lang.org.  The
+/// downloaded files are cached in the given cache directory.  HTML files that are larger than
+/// `max_file_size` bytes are not parsed.
+pub fn get_std_online_source(
+    cache_dir: &path::Path,
+    max_file_size: u64,
+) -> anyhow::Result<Box<dyn Source>> {
+    let path = cache_dir.join(cache::ONLINE_DIR).join("stable");
+    fs::create_dir_all(&path)
+        .with_context(|| format!("Could not create the cache directory {}", path.display()))?;
+    let remote = Remote {
+        url: "https://doc.rust-lang.org/stable".to_owned(),
+        crates: STD_CRATES.iter().map(|s| (*s).to_owned()).collect(),
+    };
+    let mut source = DirSource::with_remote(path, remote);
+    source.max_file_size = max_file_size;
+    Ok(Box::new(source))
+}
+
+/// Whether the given path is an HTML file that can be used as a single file source, see
+/// `FileSource`.
+fn is_html_file(path: &path::Path) -> bool {
+    path.is_file() && path.extension().map(|ext| ext == "html").unwrap_or(false)
 }
 
-pub fn get_source<P: AsRef<path::Path>>(path: P) -> anyhow::Result<Box<dyn Source>> {
+/// Checks whether the given crate matches the given pattern, see `Sources::route`.
+fn matches_crate(pattern: &str, krate: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix('*') {
+        krate.starts_with(prefix)
+    } else {
+        pattern == krate
+    }
+}
+
+/// Returns the source for the given path.  If it is a directory and `index` is set, the given
+/// search index is used instead of searching the directory, see `DirSource::find_index`.  HTML
+/// files in the directory that are larger than `max_file_size` bytes are not parsed.
+pub fn get_source<P: AsRef<path::Path>>(
+    path: P,
+    index: Option<&path::Path>,
+    max_file_size: u64,
+) -> anyhow::Result<Box<dyn Source>> {
     if path.as_ref().is_dir() {
-        Ok(Box::new(DirSource::new(path.as_ref().to_path_buf())))
+        let mut source = DirSource::new(path.as_ref().to_path_buf());
+        source.max_file_size = max_file_size;
+        if let Some(index) = index {
+            log::info!(
+                "Using search index '{}' for '{}'",
+                index.display(),
+                path.as_ref().display()
+            );
+            source.index = Some(index.to_owned());
+        }
+        Ok(Box::new(source))
+    } else if is_html_file(path.as_ref()) {
+        if let Some(index) = index {
+            log::warn!(
+                "Ignoring search index '{}' for the single file source '{}'",
+                index.display(),
+                path.as_ref().display()
+            );
+        }
+        Ok(Box::new(FileSource::new(
+            path.as_ref().to_path_buf(),
+            max_file_size,
+        )?))
     } else {
         Err(anyhow!(
             "This source is not supported: {}",
@@ -260,3 +1261,473 @@ pub fn get_source<P: AsRef<path::Path>>(path: P) -> anyhow::Result<Box<dyn Sourc
         ))
     }
 }
+
+/// Returns the documentation for the given keyword if it is the path of an HTML file generated by
+/// rustdoc or a `file://` URL, for example `target/doc/kuchiki/struct.NodeRef.html`.  The name of
+/// the item is read from the file name and the directory structure.  If the keyword has a
+/// fragment like `#method.children`, the documentation for this member is returned.  The file is
+/// not parsed if it is larger than `max_file_size` bytes.
+pub fn find_file_doc(keyword: &str, max_file_size: u64) -> anyhow::Result<Option<doc::Doc>> {
+    let (path, fragment) = if let Some(file) = parse_file_keyword(keyword)? {
+        file
+    } else {
+        return Ok(None);
+    };
+    anyhow::ensure!(path.is_file(), "File not found: {}", path.display());
+    let path = path.canonicalize()?;
+    log::info!("Opening documentation file '{}'", path.display());
+
+    let (name, ty) =
+        get_path_item(&path).with_context(|| format!("Not a rustdoc page: {}", path.display()))?;
+    let parser = html::Parser::from_file_with_limit(&path, max_file_size)?;
+    let doc = if let Some(fragment) = fragment {
+        let member = fragment.split_once('.').map(|(_, member)| member);
+        let member = name.child(member.unwrap_or(&fragment));
+        let ty = parser
+            .find_member(&member)?
+            .with_context(|| format!("Could not find member {} in {}", member, path.display()))?;
+        parser.parse_member_doc(&member, ty)
+    } else if ty == doc::ItemType::Module {
+        parser.parse_module_doc(&name)
+    } else {
+        parser.parse_item_doc(&name, ty)
+    };
+    doc.with_context(|| format!("Not a rustdoc page: {}", path.display()))
+        .map(Some)
+}
+
+/// Splits the given keyword into a path and a fragment if it is a `file://` URL or a path to an
+/// HTML file.
+fn parse_file_keyword(keyword: &str) -> anyhow::Result<Option<(path::PathBuf, Option<String>)>> {
+    if keyword.starts_with("file://") {
+        let url = url::Url::parse(keyword)?;
+        let path = url
+            .to_file_path()
+            .map_err(|_| anyhow!("Invalid file URL: {}", keyword))?;
+        Ok(Some((path, url.fragment().map(ToOwned::to_owned))))
+    } else {
+        let (path, fragment) = match keyword.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment.to_owned())),
+            None => (keyword, None),
+        };
+        if path.ends_with(".html") || path.contains('/') || path.contains(path::MAIN_SEPARATOR) {
+            Ok(Some((path.into(), fragment)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Returns the name and the type of the item documented in the given HTML file, e. g.
+/// `kuchiki::NodeRef` and `Struct` for `kuchiki/struct.NodeRef.html`.  The crate directory is the
+/// closest parent directory that contains an `all.html` file.
+fn get_path_item(path: &path::Path) -> anyhow::Result<(doc::Fqn, doc::ItemType)> {
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .with_context(|| format!("Invalid file name: {}", path.display()))?;
+    let dir = path.parent().unwrap_or(path);
+    let crate_dir = dir
+        .ancestors()
+        .find(|dir| dir.join("all.html").is_file())
+        .context("Could not find the crate directory")?;
+    let mut components = vec![crate_dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .context("Invalid crate directory")?];
+    for component in dir.strip_prefix(crate_dir)?.components() {
+        components.push(
+            component
+                .as_os_str()
+                .to_str()
+                .context("Invalid module directory")?,
+        );
+    }
+
+    if file_name == "index.html" {
+        Ok((components.join("::").into(), doc::ItemType::Module))
+    } else {
+        let parts: Vec<_> = file_name.split('.').collect();
+        anyhow::ensure!(
+            parts.len() == 3 && parts[2] == "html",
+            "Unexpected file name: {}",
+            file_name
+        );
+        let ty = parts[0].parse()?;
+        components.push(parts[1]);
+        Ok((components.join("::").into(), ty))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path;
+    use std::time;
+
+    use super::{
+        matches_crate, DirSource, FileSource, Remote, Source, SourceCapabilities, Sources,
+    };
+    use crate::doc;
+    use crate::index;
+    use crate::parser::html;
+    use crate::test_utils::{with_rustdoc, Format};
+
+    #[test]
+    fn test_matches_crate() {
+        assert!(matches_crate("serde", "serde"));
+        assert!(!matches_crate("serde", "serde_json"));
+        assert!(matches_crate("serde*", "serde_json"));
+        assert!(matches_crate("*", "log"));
+        assert!(!matches_crate("my*", "log"));
+    }
+
+    #[test]
+    fn test_route() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let cache = tempfile::tempdir().unwrap();
+            let remote = Remote {
+                url: format!("file://{}", path.canonicalize().unwrap().display()),
+                crates: vec!["log".to_owned()],
+            };
+            let mut sources = Sources::new(vec![Box::new(DirSource::new(path.to_owned()))]);
+            sources.route(
+                "log",
+                Box::new(DirSource::with_remote(cache.path().to_owned(), remote)),
+            );
+
+            let doc = sources
+                .find(&"log::Log".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            let cache_path = cache.path().canonicalize().unwrap();
+            assert!(doc.url.unwrap().contains(cache_path.to_str().unwrap()));
+
+            let doc = sources
+                .find(&"kuchiki::NodeRef".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert!(!doc.url.unwrap().contains(cache_path.to_str().unwrap()));
+        });
+    }
+
+    #[test]
+    fn test_find_alternatives() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let sources = Sources::new(vec![Box::new(DirSource::new(path.to_owned()))]);
+
+            let doc = sources
+                .find(&"log::log".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(doc::ItemType::Macro, doc.ty);
+            assert!(sources.find_alternatives(&doc).unwrap().is_empty());
+            let doc = sources
+                .find(&"log::log".to_owned().into(), Some(doc::ItemType::Trait))
+                .unwrap();
+            assert!(doc.is_none());
+
+            let doc = sources
+                .find(&"kuchiki::iter".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(doc::ItemType::Module, doc.ty);
+            assert!(sources.find_alternatives(&doc).unwrap().is_empty());
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let crate_path = dir.path().join("foo");
+        fs::create_dir_all(crate_path.join("bar")).unwrap();
+        fs::write(
+            crate_path.join("all.html"),
+            r#"<ul class="all-items"><li><a href="macro.bar.html">bar</a></li></ul>"#,
+        )
+        .unwrap();
+        fs::write(crate_path.join("bar").join("index.html"), "").unwrap();
+        let source = DirSource::new(dir.path().to_owned());
+        assert_eq!(
+            vec![doc::ItemType::Macro, doc::ItemType::Module],
+            source.find_items(&"foo::bar".to_owned().into()).unwrap()
+        );
+        assert!(source
+            .find_items(&"foo::baz".to_owned().into())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_retain_public() {
+        let dir = tempfile::tempdir().unwrap();
+        let crate_path = dir.path().join("foo");
+        fs::create_dir_all(&crate_path).unwrap();
+        fs::write(crate_path.join("all.html"), "").unwrap();
+        fs::write(
+            crate_path.join("index.html"),
+            concat!(
+                r#"<html><body><section id="main-content"><h2 id="structs">Structs</h2>"#,
+                r#"<div class="item-table">"#,
+                r#"<div class="module-item"><a class="struct" href="struct.Bar.html">Bar</a></div>"#,
+                r#"<div class="docblock-short">A bar.</div>"#,
+                r#"<div class="module-item"><a class="struct" href="struct.Baz.html">Baz</a> 🔒</div>"#,
+                r#"<div class="docblock-short">A baz.</div>"#,
+                r#"</div></section></body></html>"#,
+            ),
+        )
+        .unwrap();
+        let sources = Sources::new(vec![Box::new(DirSource::new(dir.path().to_owned()))]);
+
+        let item = |name: &str| index::IndexItem {
+            name: name.to_owned().into(),
+            ty: doc::ItemType::Struct,
+            description: String::new(),
+            source_priority: 0,
+            aliases: Vec::new(),
+        };
+        let mut items = vec![item("foo::Bar"), item("foo::Baz"), item("foo::Qux")];
+        sources.retain_public(&mut items);
+        let names: Vec<_> = items.iter().map(|item| item.name.as_ref()).collect();
+        assert_eq!(vec!["foo::Bar", "foo::Qux"], names);
+
+        // If the parent cannot be loaded, the visibility is unknown and all items are kept.
+        let mut source = DirSource::new(dir.path().to_owned());
+        source.max_file_size = 1;
+        let sources = Sources::new(vec![Box::new(source)]);
+        let mut items = vec![item("foo::Bar"), item("foo::Baz")];
+        sources.retain_public(&mut items);
+        assert_eq!(2, items.len());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let cache = tempfile::tempdir().unwrap();
+            let remote = Remote {
+                url: format!("file://{}", path.canonicalize().unwrap().display()),
+                crates: vec!["log".to_owned()],
+            };
+            let file = FileSource::new(
+                path.join("log").join("enum.Level.html"),
+                html::DEFAULT_MAX_FILE_SIZE,
+            )
+            .unwrap();
+            assert!(!file.capabilities().contains(SourceCapabilities::SEARCH));
+
+            let mut sources = Sources::new(vec![
+                Box::new(file),
+                Box::new(DirSource::new(path.to_owned())),
+            ]);
+            assert!(sources.can_watch("log"));
+            assert!(!sources
+                .search(&"Level".to_owned().into())
+                .unwrap()
+                .is_empty());
+
+            sources.route(
+                "log",
+                Box::new(DirSource::with_remote(cache.path().to_owned(), remote)),
+            );
+            assert!(!sources.can_watch("log"));
+            assert!(sources.can_watch("kuchiki"));
+            assert!(!sources.can_watch("serde"));
+        });
+    }
+
+    #[test]
+    fn test_crate_root_urls() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let cache = tempfile::tempdir().unwrap();
+            let remote = Remote {
+                url: "https://doc.rust-lang.org/stable".to_owned(),
+                crates: vec!["std".to_owned(), "log".to_owned()],
+            };
+            let mut sources = Sources::new(vec![Box::new(DirSource::new(path.to_owned()))]);
+            sources.route(
+                "std",
+                Box::new(DirSource::with_remote(cache.path().to_owned(), remote)),
+            );
+
+            let roots = sources.crate_root_urls().unwrap();
+            let names: Vec<_> = roots.iter().map(|(krate, _)| krate.as_str()).collect();
+            assert_eq!(vec!["anyhow", "kuchiki", "log", "rand_core", "std"], names);
+            assert!(roots[2].1.starts_with("file:///"));
+            assert!(roots[2].1.ends_with("/log/index.html"));
+            assert_eq!(
+                "https://doc.rust-lang.org/stable/std/index.html",
+                roots[4].1.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn test_remote() {
+        with_rustdoc(">=1.44.0", Format::all(), |_, _, path| {
+            let cache = tempfile::tempdir().unwrap();
+            let remote = Remote {
+                url: format!("file://{}", path.canonicalize().unwrap().display()),
+                crates: vec!["log".to_owned()],
+            };
+            let source = DirSource::with_remote(cache.path().to_owned(), remote);
+
+            assert!(source.has_crate("log"));
+            assert!(!source.has_crate("kuchiki"));
+            assert!(!cache.path().join("log").exists());
+            let doc = source
+                .find_doc(&"log::Log".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert!(doc.description.is_some());
+            assert!(cache.path().join("log").join("trait.Log.html").is_file());
+            assert!(source
+                .find_doc(&"log::Logg".to_owned().into(), None)
+                .unwrap()
+                .is_none());
+            assert!(!cache.path().join("log").join("trait.Logg.html").exists());
+            assert_eq!(
+                None,
+                fs::read_dir(cache.path().join("log"))
+                    .unwrap()
+                    .map(|entry| entry.unwrap().file_name())
+                    .find(|name| name.to_string_lossy().starts_with(".tmp"))
+            );
+
+            let index = source.load_index().unwrap().unwrap();
+            assert!(!index.find(&"Log".to_owned().into()).is_empty());
+        });
+    }
+
+    /// Rewrites the file at `path` until it is newer than the file at `other`.  The resolution of
+    /// the modification times depends on the file system, so one write might not be enough.
+    fn write_newer(path: &path::Path, other: &path::Path) {
+        let modified = |path: &path::Path| fs::metadata(path).unwrap().modified().unwrap();
+        loop {
+            fs::write(path, "").unwrap();
+            if modified(path) > modified(other) {
+                break;
+            }
+            std::thread::sleep(time::Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_find_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = DirSource::new(dir.path().to_owned());
+        assert_eq!(None, source.find_index().unwrap());
+
+        let nested = dir.path().join("doc").join("search-index-1.77.0.js");
+        fs::create_dir(dir.path().join("doc")).unwrap();
+        fs::write(&nested, "").unwrap();
+        assert_eq!(Some(nested.clone()), source.find_index().unwrap());
+
+        let root = dir.path().join("search-index.js");
+        fs::write(&root, "").unwrap();
+        write_newer(&nested, &root);
+        assert_eq!(Some(nested.clone()), source.find_index().unwrap());
+        write_newer(&root, &nested);
+        assert_eq!(Some(root), source.find_index().unwrap());
+
+        let mut source = source;
+        source.index = Some("static/search-index.js".into());
+        assert_eq!(
+            Some(dir.path().join("static").join("search-index.js")),
+            source.find_index().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nested_crates() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let dir = tempfile::tempdir().unwrap();
+            for krate in &["log", "kuchiki"] {
+                copy_dir(&path.join(krate), &dir.path().join("doc").join(krate));
+            }
+            let source = DirSource::new(dir.path().to_owned());
+
+            assert!(source.has_crate("log"));
+            assert!(!source.has_crate("iter"));
+            let roots = source.crate_roots().unwrap();
+            assert_eq!(
+                vec!["kuchiki", "log"],
+                roots.iter().map(|(krate, _)| krate).collect::<Vec<_>>()
+            );
+            assert!(roots[1].1.ends_with("/doc/log/index.html"));
+            let doc = source
+                .find_doc(&"log".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(doc::ItemType::Module, doc.ty);
+            assert!(doc.url.unwrap().ends_with("/log/index.html"));
+            let doc = source
+                .find_doc(&"log::Level".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(doc::ItemType::Enum, doc.ty);
+            assert_eq!(
+                vec![doc::ItemType::Module],
+                source
+                    .find_items(&"kuchiki::iter".to_owned().into())
+                    .unwrap()
+            );
+        });
+    }
+
+    fn copy_dir(from: &path::Path, to: &path::Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_file_source() {
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let source = FileSource::new(
+                path.join("log").join("enum.Level.html"),
+                html::DEFAULT_MAX_FILE_SIZE,
+            )
+            .unwrap();
+            let name: doc::Fqn = "log::Level".to_owned().into();
+            assert_eq!(Some(&name), source.file_item());
+            assert!(source.has_crate("log"));
+            assert!(!source.has_crate("kuchiki"));
+            assert_eq!(vec![doc::ItemType::Enum], source.find_items(&name).unwrap());
+            assert!(source.crate_roots().unwrap().is_empty());
+
+            let doc = source.find_doc(&name, None).unwrap().unwrap();
+            assert_eq!(doc::ItemType::Enum, doc.ty);
+            assert!(doc.description.is_some());
+            assert!(source
+                .find_doc(&name, Some(doc::ItemType::Struct))
+                .unwrap()
+                .is_none());
+            assert!(source
+                .find_doc(&"log::Log".to_owned().into(), None)
+                .unwrap()
+                .is_none());
+
+            let member: doc::Fqn = "log::Level::Error".to_owned().into();
+            let doc = source.find_doc(&member, None).unwrap().unwrap();
+            assert_eq!(doc::ItemType::Variant, doc.ty);
+            let members = source.list_members(&name).unwrap().unwrap();
+            assert!(members.contains(&member));
+
+            let source = FileSource::new(
+                path.join("log").join("index.html"),
+                html::DEFAULT_MAX_FILE_SIZE,
+            )
+            .unwrap();
+            assert_eq!("log", source.file_item().unwrap().as_ref());
+            assert_eq!(1, source.crate_roots().unwrap().len());
+            let doc = source
+                .find_doc(&"log".to_owned().into(), None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(doc::ItemType::Module, doc.ty);
+        });
+    }
+}
@@ -50,7 +50,7 @@ fn get_versions() -> &'static [(semver::Version, Format, path::PathBuf)] {
         }
     });
 
-    unsafe { VERSIONS.as_ref().unwrap() }
+    unsafe { (*std::ptr::addr_of!(VERSIONS)).as_ref().unwrap() }
 }
 
 fn find_rust_versions(format: Format) -> Vec<(semver::Version, Format, path::PathBuf)> {
@@ -83,10 +83,10 @@ fn generate_docs(format: Format) -> Option<(semver::Version, Format, path::PathB
     let mut path = tempfile::tempdir().unwrap().into_path();
     process::Command::new(env::var_os("CARGO").unwrap())
         .arg("doc")
-        .args(&["--package", "anyhow"])
-        .args(&["--package", "kuchiki"])
-        .args(&["--package", "log"])
-        .args(&["--package", "rand_core"])
+        .args(["--package", "anyhow"])
+        .args(["--package", "kuchiki"])
+        .args(["--package", "log"])
+        .args(["--package", "rand_core"])
         .arg("--target-dir")
         .arg(&path)
         .arg("--no-deps")
@@ -105,14 +105,14 @@ where
     let version_req = semver::VersionReq::parse(version).unwrap();
     let versions = get_versions();
     for (version, format, path) in versions {
-        if version_req.matches(&version) && formats.contains(format) {
+        if version_req.matches(version) && formats.contains(format) {
             log::warn!(
                 "Executing test case for version {} at {} (format {})",
                 version,
                 path.display(),
                 format
             );
-            f(version, *format, &path);
+            f(version, *format, path);
         }
     }
 }
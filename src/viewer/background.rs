@@ -0,0 +1,187 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! Detects whether the terminal has a light or a dark background to choose the default syntax
+//! highlighting theme.
+//!
+//! We first check the `COLORFGBG` environment variable that is set by some terminal emulators,
+//! for example rxvt and Konsole.  Otherwise, we ask the terminal for its background color using
+//! the OSC 11 control sequence.  As not all terminals answer this query, we also send a primary
+//! device attributes (DA1) request that is supported by virtually all terminals and stop reading
+//! once we receive its answer.  If the terminal does not answer at all, we give up after
+//! `QUERY_TIMEOUT`.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read as _, Write as _};
+use std::os::unix::io::AsRawFd as _;
+use std::time;
+
+/// The maximum time to wait for the answer of the terminal to the background color query.
+const QUERY_TIMEOUT: time::Duration = time::Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    Light,
+    Dark,
+}
+
+impl Background {
+    fn from_rgb(r: f64, g: f64, b: f64) -> Self {
+        let luminance = 0.299 * r + 0.587 * g + 0.114 * b;
+        if luminance > 0.5 {
+            Background::Light
+        } else {
+            Background::Dark
+        }
+    }
+}
+
+/// Returns the background of the terminal.  The detection is only performed once, later calls
+/// return the cached result.
+pub fn get() -> Background {
+    static BACKGROUND: once_cell::sync::OnceCell<Background> = once_cell::sync::OnceCell::new();
+    *BACKGROUND.get_or_init(detect)
+}
+
+fn detect() -> Background {
+    if let Some(background) = env::var("COLORFGBG").ok().and_then(|s| parse_colorfgbg(&s)) {
+        log::info!(
+            "Detected {:?} terminal background from COLORFGBG",
+            background
+        );
+        return background;
+    }
+
+    if termion::is_tty(&io::stdin()) && termion::is_tty(&io::stdout()) {
+        match query_background() {
+            Ok(Some(background)) => {
+                log::info!("Detected {:?} terminal background from OSC 11", background);
+                return background;
+            }
+            Ok(None) => log::info!("The terminal did not report its background color"),
+            Err(err) => log::info!("Could not query the terminal background color: {}", err),
+        }
+    }
+
+    log::info!("Could not detect the terminal background, assuming a dark background");
+    Background::Dark
+}
+
+/// Parses the value of the `COLORFGBG` environment variable, e. g. `15;0` or `0;default;15`.
+///
+/// The last field is the index of the background color in the 16-color palette.
+fn parse_colorfgbg(s: &str) -> Option<Background> {
+    let bg: u8 = s.rsplit(';').next()?.parse().ok()?;
+    match bg {
+        7 | 9..=15 => Some(Background::Light),
+        0..=6 | 8 => Some(Background::Dark),
+        _ => None,
+    }
+}
+
+/// Asks the terminal for its background color using OSC 11, followed by a DA1 request that marks
+/// the end of the answer.
+fn query_background() -> io::Result<Option<Background>> {
+    use termion::raw::IntoRawMode as _;
+
+    let mut tty = fs::File::open("/dev/tty")?;
+    let mut stdout = io::stdout().into_raw_mode()?;
+    write!(stdout, "\x1b]11;?\x07\x1b[c")?;
+    stdout.flush()?;
+
+    let deadline = time::Instant::now() + QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    let mut buffer = [0; 64];
+    while !is_da1_response(&response) {
+        let now = time::Instant::now();
+        if now >= deadline || !poll(&tty, deadline - now)? {
+            break;
+        }
+        let n = tty.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        response.extend_from_slice(&buffer[..n]);
+    }
+    Ok(parse_osc11_response(&String::from_utf8_lossy(&response)))
+}
+
+/// Waits until the given file is readable or the timeout expires and returns whether the file is
+/// readable.
+fn poll(file: &fs::File, timeout: time::Duration) -> io::Result<bool> {
+    let mut fd = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.as_millis().min(i32::MAX as u128) as libc::c_int;
+    // Safety: We pass a pointer to exactly one valid pollfd struct.
+    let result = unsafe { libc::poll(&mut fd, 1, timeout) };
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result > 0)
+    }
+}
+
+/// Whether the given terminal output ends with the answer to a DA1 request, e. g. `ESC [?62;22c`.
+fn is_da1_response(response: &[u8]) -> bool {
+    response.ends_with(b"c") && response.windows(3).any(|window| window == b"\x1b[?")
+}
+
+/// Parses the answer to an OSC 11 query, e. g. `ESC ]11;rgb:ffff/ffff/dddd BEL`.
+fn parse_osc11_response(s: &str) -> Option<Background> {
+    let start = s.find("\x1b]11;")? + 5;
+    let s = &s[start..];
+    let end = s.find(['\x07', '\x1b']).unwrap_or(s.len());
+    let mut components = s[..end].strip_prefix("rgb:")?.split('/').map(|c| {
+        let value = u16::from_str_radix(c, 16).ok()?;
+        let max = (1u32 << (4 * c.len().min(4))) - 1;
+        Some(f64::from(value) / f64::from(max))
+    });
+    let r = components.next()??;
+    let g = components.next()??;
+    let b = components.next()??;
+    Some(Background::from_rgb(r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_da1_response, parse_colorfgbg, parse_osc11_response, Background};
+
+    #[test]
+    fn test_parse_colorfgbg() {
+        assert_eq!(Some(Background::Dark), parse_colorfgbg("15;0"));
+        assert_eq!(Some(Background::Light), parse_colorfgbg("0;15"));
+        assert_eq!(Some(Background::Light), parse_colorfgbg("0;default;7"));
+        assert_eq!(Some(Background::Dark), parse_colorfgbg("7;8"));
+        assert_eq!(None, parse_colorfgbg("15;default"));
+        assert_eq!(None, parse_colorfgbg(""));
+    }
+
+    #[test]
+    fn test_parse_osc11_response() {
+        assert_eq!(
+            Some(Background::Light),
+            parse_osc11_response("\x1b]11;rgb:ffff/ffff/dddd\x07\x1b[?62;22c")
+        );
+        assert_eq!(
+            Some(Background::Dark),
+            parse_osc11_response("\x1b]11;rgb:2d2d/2d2d/2d2d\x1b\\")
+        );
+        assert_eq!(
+            Some(Background::Light),
+            parse_osc11_response("\x1b]11;rgb:fd/f6/e3\x07")
+        );
+        assert_eq!(None, parse_osc11_response("\x1b[?62;22c"));
+        assert_eq!(None, parse_osc11_response("\x1b]11;?\x07"));
+    }
+
+    #[test]
+    fn test_is_da1_response() {
+        assert!(is_da1_response(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c"));
+        assert!(!is_da1_response(b"\x1b]11;rgb:0/0/0\x07"));
+        assert!(!is_da1_response(b"\x1b[?62;2"));
+    }
+}
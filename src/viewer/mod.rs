@@ -1,15 +1,18 @@
 // SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: MIT
 
+mod background;
 mod text;
 mod tui;
 mod utils;
+mod watch;
 
 use std::fmt;
 use std::io;
 
 use crate::args;
 use crate::doc;
+use crate::index;
 use crate::source;
 
 pub trait Viewer: fmt::Debug {
@@ -27,12 +30,106 @@ pub trait Viewer: fmt::Debug {
         doc: &doc::Doc,
         examples: Vec<doc::Example>,
     ) -> anyhow::Result<()>;
+
+    /// Opens only the member groups of the given documentation item that match the given section
+    /// name.
+    fn open_with_section(
+        &self,
+        sources: source::Sources,
+        args: args::ViewerArgs,
+        doc: &doc::Doc,
+        section: &str,
+    ) -> anyhow::Result<()> {
+        let doc = select_section(doc, section)?;
+        self.open(sources, args, &doc)
+    }
+
+    /// Renders the documentation for the given item to a string instead of displaying it, see the
+    /// `--cache-render` option.  Returns `None` if the viewer does not support this.
+    fn render_to_string(
+        &self,
+        _args: &args::ViewerArgs,
+        _doc: &doc::Doc,
+    ) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Renders the documentation for the given item to the given writer instead of displaying it,
+    /// see the `--emit` option.  Returns `false` if the viewer does not support this.
+    fn render_to(
+        &self,
+        _args: &args::ViewerArgs,
+        _doc: &doc::Doc,
+        _output: &mut dyn io::Write,
+    ) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
+    /// Displays output that has been generated by `render_to_string`.
+    fn open_rendered(&self, _args: args::ViewerArgs, _output: &str) -> anyhow::Result<()> {
+        anyhow::bail!("This viewer does not support rendered output")
+    }
+
+    /// The picker that is used to select an item if a search returns multiple matches and the
+    /// `--picker` option is not set.
+    fn default_picker(&self) -> args::Picker {
+        args::Picker::Prompt
+    }
+}
+
+/// Returns a copy of the given documentation item that only contains the member groups matching
+/// the given section name.
+///
+/// The section name is compared case-insensitively with the group names of the member types (for
+/// example “Methods”) and with the titles of the member groups (for example “Trait
+/// Implementations”).
+fn select_section(doc: &doc::Doc, section: &str) -> anyhow::Result<doc::Doc> {
+    let lowercase = section.to_lowercase();
+    let mut selected = doc::Doc::new(doc.name.clone(), doc.ty);
+    selected.url = doc.url.clone();
+    for (ty, groups) in &doc.groups {
+        let groups: Vec<_> = if ty.group_name().to_lowercase() == lowercase {
+            groups.clone()
+        } else {
+            groups
+                .iter()
+                .filter(|g| g.title.as_ref().map(|t| t.to_lowercase()).as_ref() == Some(&lowercase))
+                .cloned()
+                .collect()
+        };
+        if !groups.is_empty() {
+            selected.groups.insert(*ty, groups);
+        }
+    }
+    anyhow::ensure!(
+        !selected.groups.is_empty(),
+        "Could not find section {} in the documentation for {}",
+        section,
+        doc.name
+    );
+    Ok(selected)
+}
+
+/// Lets the user select one of the given search results in an interactive dialog, see
+/// `args::Picker::Tui`.
+pub fn select_item(
+    sources: &source::Sources,
+    name: &doc::Name,
+    items: &[index::IndexItem],
+) -> anyhow::Result<Option<index::IndexItem>> {
+    tui::select_item(sources, name, items)
 }
 
+pub use text::print_theme_preview;
+pub use utils::get_line_length;
+pub use utils::get_theme_name;
+pub use utils::get_trait_name;
+
 pub fn get_viewer(s: &str) -> anyhow::Result<Box<dyn Viewer>> {
     let viewer: Box<dyn Viewer> = match s.to_lowercase().as_ref() {
         "plain" => Box::new(text::TextViewer::new(text::TextMode::Plain)),
         "rich" => Box::new(text::TextViewer::new(text::TextMode::Rich)),
+        "roff" => Box::new(text::TextViewer::new(text::TextMode::Roff)),
         "tui" => Box::new(tui::TuiViewer::new()),
         _ => anyhow::bail!("The viewer {} is not supported", s),
     };
@@ -3,12 +3,17 @@
 
 mod plain;
 mod rich;
+mod roff;
 
 use std::env;
-use std::io;
+use std::io::{self, Write as _};
+use std::process;
+
+use unicode_width::UnicodeWidthStr as _;
 
 use crate::args;
 use crate::doc;
+use crate::parser::html;
 use crate::source;
 use crate::viewer::{self, utils};
 
@@ -21,6 +26,7 @@ pub struct TextViewer {
 pub enum TextMode {
     Plain,
     Rich,
+    Roff,
 }
 
 impl TextViewer {
@@ -28,28 +34,193 @@ impl TextViewer {
         TextViewer { mode }
     }
 
+    /// Creates a renderer that writes to the given output, typically a buffered stdout.  Use
+    /// `ManRenderer::flush` to write the buffered output.
+    fn create_renderer<'a>(
+        &self,
+        args: &args::ViewerArgs,
+        output: &'a mut dyn io::Write,
+    ) -> anyhow::Result<Box<dyn utils::ManRenderer<Error = io::Error> + 'a>> {
+        Ok(match self.mode {
+            TextMode::Plain => Box::new(plain::PlainTextRenderer::new(args, output)),
+            TextMode::Rich => Box::new(rich::RichTextRenderer::new(args, output)?),
+            TextMode::Roff => Box::new(roff::RoffRenderer::new(args, output)),
+        })
+    }
+
     fn exec<F>(&self, args: args::ViewerArgs, op: F) -> anyhow::Result<()>
     where
-        F: FnOnce(Box<dyn utils::ManRenderer<Error = io::Error>>) -> io::Result<()>,
+        F: FnOnce(&mut dyn utils::ManRenderer<Error = io::Error>) -> io::Result<()>,
     {
-        let viewer: Box<dyn utils::ManRenderer<Error = io::Error>> = match self.mode {
-            TextMode::Plain => Box::new(plain::PlainTextRenderer::new(&args)),
-            TextMode::Rich => Box::new(rich::RichTextRenderer::new(&args)?),
-        };
+        let mut output = io::BufWriter::new(io::stdout());
+        let mut viewer = self.create_renderer(&args, &mut output)?;
+
+        // roff output is meant to be processed by man, so we don’t page it
+        if self.mode != TextMode::Roff {
+            spawn_pager(args.pager.as_deref());
+        }
+        op(viewer.as_mut())
+            .and_then(|_| viewer.flush())
+            .or_else(ignore_pipe_error)
+            .map_err(Into::into)
+    }
+
+    /// Displays the documentation for the given item and prompts for a link to follow after the
+    /// pager exits, see the `--follow` option.
+    fn follow(
+        &self,
+        sources: &source::Sources,
+        args: args::ViewerArgs,
+        doc: &doc::Doc,
+    ) -> anyhow::Result<()> {
+        let mut doc = doc.clone();
+        loop {
+            with_child_pager(&args, |output| {
+                let mut viewer = self.create_renderer(&args, output)?;
+                viewer
+                    .render_doc(&doc)
+                    .and_then(|_| viewer.flush())
+                    .or_else(ignore_pipe_error)
+                    .map_err(Into::into)
+            })?;
+
+            let links = get_links(&doc);
+            if links.is_empty() {
+                return Ok(());
+            }
+            println!("Links on the page for {}:", doc.name);
+            println!();
+            let width = links.len().to_string().len();
+            for (i, link) in links.iter().enumerate() {
+                println!("[ {:width$} ] {}", i + 1, link, width = width);
+            }
+            println!();
+
+            doc = loop {
+                let link = match prompt_link(&links)? {
+                    Some(link) => link,
+                    None => return Ok(()),
+                };
+                match link.open(sources) {
+                    Ok(Some(doc)) => break doc,
+                    Ok(None) => {}
+                    Err(err) => eprintln!("Error: {:#}", err),
+                }
+            };
+        }
+    }
+
+    /// Displays the documentation for the given item without a pager and displays it again
+    /// whenever the documentation files change, see the `--watch` option.
+    fn watch(
+        &self,
+        sources: &source::Sources,
+        args: args::ViewerArgs,
+        doc: &doc::Doc,
+    ) -> anyhow::Result<()> {
+        let watcher = viewer::watch::Watcher::new(doc)?;
+        let clear_screen = termion::is_tty(&io::stdout());
+        let mut doc = doc.clone();
+        loop {
+            if clear_screen {
+                print!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1));
+            }
+            let mut output = io::BufWriter::new(io::stdout());
+            let mut viewer = self.create_renderer(&args, &mut output)?;
+            viewer.render_doc(&doc)?;
+            viewer.flush()?;
+
+            // If the documentation is not available after the change, e. g. because cargo doc
+            // failed, we keep the last version and wait for the next change.
+            doc = loop {
+                watcher.wait()?;
+                match sources.find(&doc.name, Some(doc.ty)) {
+                    Ok(Some(doc)) => break doc,
+                    Ok(None) => eprintln!("Could not find documentation for {}", &doc.name),
+                    Err(err) => eprintln!("Error: {:#}", err),
+                }
+            };
+        }
+    }
+}
+
+/// Prompts for the number of a link.  Returns `None` if the input is empty or if stdin is closed.
+fn prompt_link(links: &[utils::ResolvedLink]) -> io::Result<Option<&utils::ResolvedLink>> {
+    loop {
+        print!("follow link #? ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            println!();
+            return Ok(None);
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match input.parse::<usize>() {
+            Ok(i) if i >= 1 && i <= links.len() => return Ok(Some(&links[i - 1])),
+            _ => eprintln!("Please enter a number between 1 and {}.", links.len()),
+        }
+    }
+}
 
-        spawn_pager(&args);
-        op(viewer).or_else(ignore_pipe_error).map_err(Into::into)
+/// Returns the links in the descriptions of the given item and its members, excluding links to
+/// the source code and to the Rust playground.
+fn get_links(doc: &doc::Doc) -> Vec<utils::ResolvedLink> {
+    let members = doc
+        .groups
+        .values()
+        .flatten()
+        .flat_map(|group| &group.members)
+        .filter_map(|member| member.description.as_ref());
+    let mut links: Vec<utils::ResolvedLink> = Vec::new();
+    for text in doc.description.iter().chain(members) {
+        let hrefs = html::Parser::from_string(&text.html).and_then(|parser| parser.find_links());
+        let hrefs = match hrefs {
+            Ok(hrefs) => hrefs,
+            Err(err) => {
+                log::warn!("Could not find links for {}: {}", doc.name, err);
+                continue;
+            }
+        };
+        for href in hrefs {
+            if href.contains("src/") || href.starts_with("javascript:") {
+                continue;
+            }
+            match utils::resolve_link(&doc.name, doc.ty, &href) {
+                Ok(utils::ResolvedLink::External(url)) if !list_link(&url) => {}
+                Ok(link) => {
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+                Err(err) => log::info!("Ignoring link '{}': {}", href, err),
+            }
+        }
     }
+    links
 }
 
 impl viewer::Viewer for TextViewer {
     fn open(
         &self,
-        _sources: source::Sources,
+        sources: source::Sources,
         args: args::ViewerArgs,
         doc: &doc::Doc,
     ) -> anyhow::Result<()> {
-        self.exec(args, |mut viewer| viewer.render_doc(doc))
+        if args.watch && self.mode != TextMode::Roff {
+            self.watch(&sources, args, doc)
+        } else if args.follow
+            && self.mode != TextMode::Roff
+            && termion::is_tty(&io::stdin())
+            && termion::is_tty(&io::stdout())
+        {
+            self.follow(&sources, args, doc)
+        } else {
+            self.exec(args, |viewer| viewer.render_doc(doc))
+        }
     }
 
     fn open_examples(
@@ -59,15 +230,88 @@ impl viewer::Viewer for TextViewer {
         doc: &doc::Doc,
         examples: Vec<doc::Example>,
     ) -> anyhow::Result<()> {
-        self.exec(args, |mut viewer| viewer.render_examples(doc, &examples))
+        self.exec(args, |viewer| viewer.render_examples(doc, &examples))
+    }
+
+    fn render_to_string(
+        &self,
+        args: &args::ViewerArgs,
+        doc: &doc::Doc,
+    ) -> anyhow::Result<Option<String>> {
+        if self.mode == TextMode::Roff {
+            return Ok(None);
+        }
+        let mut output = Vec::new();
+        self.render_to(args, doc, &mut output)?;
+        String::from_utf8(output).map(Some).map_err(From::from)
+    }
+
+    fn render_to(
+        &self,
+        args: &args::ViewerArgs,
+        doc: &doc::Doc,
+        output: &mut dyn io::Write,
+    ) -> anyhow::Result<bool> {
+        let mut viewer = self.create_renderer(args, output)?;
+        viewer.render_doc(doc)?;
+        viewer.flush()?;
+        Ok(true)
+    }
+
+    fn open_rendered(&self, args: args::ViewerArgs, output: &str) -> anyhow::Result<()> {
+        spawn_pager(args.pager.as_deref());
+        io::stdout()
+            .write_all(output.as_bytes())
+            .or_else(ignore_pipe_error)
+            .map_err(Into::into)
     }
 }
 
-pub fn spawn_pager(args: &args::ViewerArgs) {
+/// The code that is highlighted by `print_theme_preview`.  It should contain most of the syntax
+/// elements that are colored differently by the themes.
+const THEME_PREVIEW_CODE: &str = r#"/// Returns the longest of the given lines.
+#[inline]
+pub fn longest<'a>(lines: &[&'a str]) -> Option<&'a str> {
+    const MIN_WIDTH: usize = 1;
+    // count characters instead of bytes
+    let width = lines.iter().map(|l| l.chars().count()).max()?;
+    println!("width: {}", width.max(MIN_WIDTH));
+    lines.iter().copied().find(|l| l.chars().count() == width)
+}"#;
+
+/// Prints a code snippet highlighted with each of the available themes, see the `--theme-preview`
+/// option.
+pub fn print_theme_preview(mut args: args::ViewerArgs) -> anyhow::Result<()> {
+    use utils::ManRenderer as _;
+
+    let code = doc::Code::new(THEME_PREVIEW_CODE.to_owned());
+    let mut output = io::BufWriter::new(io::stdout());
+    args.no_syntax_highlight = false;
+    args.no_code_highlight = false;
+    for theme in utils::get_theme_names() {
+        args.theme = Some(theme.clone());
+        let mut renderer = rich::RichTextRenderer::new(&args, &mut output)?;
+        let result = renderer
+            .print_heading(1, &theme, None)
+            .and_then(|_| renderer.print_code(6, &code));
+        if let Err(err) = result {
+            return ignore_pipe_error(err).map_err(Into::into);
+        }
+    }
+    output
+        .flush()
+        .or_else(ignore_pipe_error)
+        .map_err(Into::into)
+}
+
+/// Redirects stdout to the given pager command, or to `$PAGER` or `less` if it is not set.  The
+/// pager is not used if stdout is not a terminal.
+pub fn spawn_pager(pager_cmd: Option<&str>) {
     if env::var_os("LESS").is_none() {
         env::set_var("LESS", "cR");
     }
-    let mut pager = if let Some(pager) = &args.pager {
+    // with_default_pager would let $PAGER take precedence over the --pager option.
+    let mut pager = if let Some(pager) = pager_cmd {
         pager::Pager::with_pager(pager)
     } else {
         pager::Pager::with_default_pager("less")
@@ -75,6 +319,48 @@ pub fn spawn_pager(args: &args::ViewerArgs) {
     pager.setup();
 }
 
+/// Spawns the pager as a child process, passes its input to the given function and waits for the
+/// pager to exit.
+///
+/// In contrast to `spawn_pager`, this makes it possible to continue after the pager exits.
+fn with_child_pager<F>(args: &args::ViewerArgs, op: F) -> anyhow::Result<()>
+where
+    F: FnOnce(&mut dyn io::Write) -> anyhow::Result<()>,
+{
+    use anyhow::Context as _;
+
+    let pager = args
+        .pager
+        .clone()
+        .or_else(|| env::var("PAGER").ok())
+        .unwrap_or_else(|| "less".to_owned());
+    // The pager command is run by the shell so that it may contain arguments and quotes.
+    let mut command = if cfg!(windows) {
+        let mut command = process::Command::new("cmd");
+        command.arg("/C").arg(&pager);
+        command
+    } else {
+        let mut command = process::Command::new("sh");
+        command.arg("-c").arg(&pager);
+        command
+    };
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "cR");
+    }
+    let mut child = command
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Could not start pager {}", pager))?;
+    let result = match child.stdin.take() {
+        // The pipe is closed when the writer is dropped so that the pager sees the end of the
+        // input.
+        Some(stdin) => op(&mut io::BufWriter::new(stdin)),
+        None => Err(anyhow::anyhow!("Could not open the stdin of the pager")),
+    };
+    child.wait()?;
+    result
+}
+
 fn ignore_pipe_error(error: io::Error) -> io::Result<()> {
     // If the pager is terminated before we can write everything to stdout, we will receive a
     // BrokenPipe error.  But we don’t want to report this error to the user.  See also:
@@ -103,9 +389,11 @@ pub fn format_title(line_length: usize, left: &str, middle: &str, right: &str) -
 
     s.push_str(left);
 
-    let mut idx = left.len();
+    // We use the display width instead of the number of bytes because the strings might contain
+    // multibyte or wide characters.
+    let mut idx = left.width();
     let middle_idx = line_length / 2;
-    let offset = middle.len() / 2;
+    let offset = middle.width() / 2;
 
     let spacing = if idx + offset >= middle_idx {
         1
@@ -114,10 +402,10 @@ pub fn format_title(line_length: usize, left: &str, middle: &str, right: &str) -
     };
     s.push_str(&" ".repeat(spacing));
     s.push_str(middle);
-    idx += middle.len() + spacing;
+    idx += middle.width() + spacing;
 
     let end_idx = line_length;
-    let offset = right.len();
+    let offset = right.width();
     let spacing = if idx + offset >= end_idx {
         1
     } else {
@@ -128,3 +416,86 @@ pub fn format_title(line_length: usize, left: &str, middle: &str, right: &str) -
 
     s
 }
+
+/// Whether the given line is an empty line that html2text inserts between a list item and a nested
+/// list, i. e. whether it is preceded by text and followed by an indented list item.
+pub fn is_nested_list_gap(previous: &str, line: &str, next: &str) -> bool {
+    let item = next.trim_start();
+    let is_list_item = item.starts_with("* ")
+        || item
+            .find(". ")
+            .map(|idx| idx > 0 && item[..idx].chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+    !previous.trim().is_empty() && line.trim().is_empty() && is_list_item && item.len() < next.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use unicode_width::UnicodeWidthStr as _;
+
+    use super::{format_title, get_links, is_nested_list_gap};
+    use crate::doc;
+    use crate::viewer::utils::ResolvedLink;
+
+    fn assert_title(line_length: usize, left: &str, middle: &str, right: &str) {
+        let title = format_title(line_length, left, middle, right);
+        assert_eq!(line_length, title.width(), "width of '{}'", title);
+        assert!(title.starts_with(left));
+        assert!(title.ends_with(right));
+
+        let middle_start = title.find(middle).unwrap();
+        let middle_col = title[..middle_start].width();
+        assert_eq!(line_length / 2 - middle.width() / 2, middle_col);
+    }
+
+    #[test]
+    fn test_format_title() {
+        assert_title(60, "log", "Trait log::Log", "rusty-man");
+        assert_title(60, "log", "Trait log::Log", "log 0.4.14 — rusty-man");
+        assert_title(60, "kuchiki", "Struct kuchiki::NodeRef", "rusty-man");
+    }
+
+    #[test]
+    fn test_get_links() {
+        let text = |html: &str| doc::Text {
+            plain: String::new(),
+            html: html.to_owned(),
+        };
+        let mut doc = doc::Doc::new("log::Level".to_owned().into(), doc::ItemType::Enum);
+        doc.description = Some(text(
+            r#"<a href="macro.log.html">log!</a> <a href="../src/log/lib.rs.html#1">src</a>
+            <a href="https://play.rust-lang.org/?code=x">Run</a>"#,
+        ));
+        let mut member = doc::Doc::new("log::Level::max".to_owned().into(), doc::ItemType::Method);
+        member.description = Some(text(
+            r#"<a href="macro.log.html">log!</a> <a href="https://docs.rs/log">docs.rs</a>"#,
+        ));
+        let mut group = doc::MemberGroup::new(None);
+        group.members.push(member);
+        doc.groups.insert(doc::ItemType::Method, vec![group]);
+
+        assert_eq!(
+            vec![
+                ResolvedLink::Doc(Some(doc::ItemType::Macro), "log::log".to_owned().into()),
+                ResolvedLink::External("https://docs.rs/log".to_owned()),
+            ],
+            get_links(&doc)
+        );
+    }
+
+    #[test]
+    fn test_is_nested_list_gap() {
+        assert!(is_nested_list_gap("2. Second step", "   ", "   * nested"));
+        assert!(is_nested_list_gap("* nested", "", "  10. deeply nested"));
+        assert!(!is_nested_list_gap("Some steps:", "", "1. First step"));
+        assert!(!is_nested_list_gap("", "", "  * nested"));
+        assert!(!is_nested_list_gap("* item", "", "  no list item"));
+        assert!(!is_nested_list_gap("* item", "text", "  * nested"));
+    }
+
+    #[test]
+    fn test_format_title_wide_chars() {
+        assert_title(60, "朽木", "Struct 朽木::ノード", "rusty-man");
+        assert_title(60, "crate", "Struct crate::Zürich", "crate 1.0 — man");
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: MIT
 
-use std::io::{self, Write};
+use std::io;
 
 use html2text::render::text_renderer;
 
@@ -9,9 +9,10 @@ use crate::args;
 use crate::doc;
 use crate::viewer::utils;
 
-#[derive(Clone, Debug)]
-pub struct PlainTextRenderer {
+pub struct PlainTextRenderer<'a> {
+    output: &'a mut dyn io::Write,
     line_length: usize,
+    options: utils::RenderOptions,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -20,38 +21,65 @@ struct Decorator {
     ignore_next_link: bool,
 }
 
-impl PlainTextRenderer {
-    pub fn new(args: &args::ViewerArgs) -> Self {
+impl<'a> PlainTextRenderer<'a> {
+    pub fn new(args: &args::ViewerArgs, output: &'a mut dyn io::Write) -> Self {
         Self {
+            output,
             line_length: utils::get_line_length(args),
+            options: utils::RenderOptions::new(args),
         }
     }
 }
 
-impl utils::ManRenderer for PlainTextRenderer {
+impl<'a> PlainTextRenderer<'a> {
+    fn write_line(&mut self, indent: u8, line: &str) -> io::Result<()> {
+        writeln!(self.output, "{}{}", " ".repeat(indent.into()), line)
+    }
+}
+
+impl<'a> utils::ManRenderer for PlainTextRenderer<'a> {
     type Error = io::Error;
 
-    fn print_title(&mut self, left: &str, middle: &str, right: &str) -> io::Result<()> {
-        let title = super::format_title(self.line_length, left, middle, right);
-        writeln!(io::stdout(), "{}", title)?;
-        writeln!(io::stdout())
+    fn print_title(
+        &mut self,
+        left: &str,
+        middle: &str,
+        right: &str,
+        version: Option<&str>,
+    ) -> io::Result<()> {
+        let right = utils::get_title_right(left, right, version);
+        let title = super::format_title(self.line_length, left, middle, &right);
+        let title = self.options.prepare_text(&title).into_owned();
+        self.write_line(0, &title)?;
+        writeln!(self.output)
     }
 
     fn print_text(&mut self, indent: u8, s: &doc::Text) -> io::Result<()> {
         let lines = html2text::from_read_with_decorator(
-            s.html.as_bytes(),
+            self.options.prepare_html(&s.html).as_bytes(),
             self.line_length - usize::from(indent),
             Decorator::new(),
         );
-        for line in lines.trim().split('\n') {
-            writeln!(io::stdout(), "{}{}", " ".repeat(indent.into()), line)?;
+        let lines: Vec<_> = lines.trim().split('\n').collect();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0
+                && i + 1 < lines.len()
+                && super::is_nested_list_gap(lines[i - 1], line, lines[i + 1])
+            {
+                continue;
+            }
+            if self.options.ascii {
+                self.write_line(indent, &utils::decorations_to_ascii(line))?;
+            } else {
+                self.write_line(indent, line)?;
+            }
         }
         Ok(())
     }
 
     fn print_code(&mut self, indent: u8, code: &doc::Code) -> io::Result<()> {
         for line in code.split('\n') {
-            writeln!(io::stdout(), "{}{}", " ".repeat(indent.into()), line)?;
+            self.write_line(indent, line)?;
         }
         Ok(())
     }
@@ -62,11 +90,28 @@ impl utils::ManRenderer for PlainTextRenderer {
         s: &str,
         _link: Option<utils::DocLink>,
     ) -> io::Result<()> {
-        writeln!(io::stdout(), "{}{}", " ".repeat(indent.into()), s)
+        let s = self.options.prepare_text(s).into_owned();
+        self.write_line(indent, &s)
     }
 
     fn println(&mut self) -> io::Result<()> {
-        writeln!(io::stdout())
+        writeln!(self.output)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn options(&self) -> &utils::RenderOptions {
+        &self.options
+    }
+
+    fn print_separator(&mut self) -> io::Result<()> {
+        writeln!(self.output, "{}", "-".repeat(self.line_length))
+    }
+
+    fn line_length(&self) -> Option<usize> {
+        Some(self.line_length)
     }
 }
 
@@ -151,3 +196,136 @@ impl text_renderer::TextDecorator for Decorator {
         Decorator::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kuchiki::traits::TendrilSink as _;
+
+    use super::PlainTextRenderer;
+    use crate::args;
+    use crate::doc;
+    use crate::parser::html;
+    use crate::test_utils::{with_rustdoc, Format};
+    use crate::viewer::utils::ManRenderer as _;
+
+    #[test]
+    fn test_print_text_lists() {
+        let html = r#"<div class="docblock"><p>Steps:</p>
+<ol>
+<li>First step with a description that wraps onto the next line</li>
+<li>Second step
+<ul>
+<li>nested</li>
+<li>another nested
+<ul>
+<li>deeply nested</li>
+</ul>
+</li>
+</ul>
+</li>
+</ol>
+<ol start="9">
+<li>nine</li>
+<li>ten</li>
+</ol>
+<ul>
+<li><input disabled="" type="checkbox" checked=""/>
+done</li>
+<li><input disabled="" type="checkbox"/>
+open</li>
+</ul>
+</div>"#;
+        let document = kuchiki::parse_html().one(html);
+        let docblock = document.select_first("div.docblock").unwrap();
+        let text = doc::Text::from(docblock);
+
+        let args = args::ViewerArgs {
+            width: Some(40),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PlainTextRenderer::new(&args, &mut output)
+            .print_text(2, &text)
+            .unwrap();
+        let expected = "  Steps:
+  
+  1. First step with a description that
+     wraps onto the next line
+  2. Second step
+     * nested
+     * another nested
+       * deeply nested
+  
+  9.  nine
+  10. ten
+  
+  * [x] done
+  * [ ] open
+";
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_render_doc_empty_groups() {
+        let render = |doc: &doc::Doc, show_empty_groups| {
+            let args = args::ViewerArgs {
+                show_empty_groups,
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            PlainTextRenderer::new(&args, &mut output)
+                .render_doc(doc)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        with_rustdoc("*", Format::all(), |_, _, path| {
+            let path = path.join("anyhow").join("struct.Error.html");
+            let name: doc::Fqn = "anyhow::Error".to_owned().into();
+            let mut doc = html::Parser::from_file(path)
+                .unwrap()
+                .parse_item_doc(&name, doc::ItemType::Struct)
+                .unwrap();
+            // The auto trait implementations have neither items nor a description.
+            let mut impls = doc.groups.remove(&doc::ItemType::Impl).unwrap();
+            impls.retain(|g| g.title.as_deref() == Some("Auto Trait Implementations"));
+            assert_eq!(1, impls.len());
+            doc.groups.clear();
+            doc.groups.insert(doc::ItemType::Impl, impls);
+
+            assert!(!render(&doc, false).contains("Auto Trait Implementations"));
+            assert!(render(&doc, true).contains("Auto Trait Implementations"));
+
+            doc.ty = doc::ItemType::Module;
+            assert!(render(&doc, false).contains("Auto Trait Implementations"));
+        });
+    }
+
+    #[test]
+    fn test_render_doc_deprecated_replacement() {
+        let mut doc = doc::Doc::new("foo::Foo".to_owned().into(), doc::ItemType::Struct);
+        let mut member = doc::Doc::new("foo::Foo::old".to_owned().into(), doc::ItemType::Method);
+        member.definition = Some(doc::Code::new("pub fn old(&self)".to_owned()));
+        member.deprecated = Some(doc::DeprecatedInfo {
+            since: Some("1.2.0".to_owned()),
+            note: Some("use new instead".to_owned()),
+            replacement: Some("new".to_owned()),
+        });
+        let mut group = doc::MemberGroup::new(None);
+        group.members.push(member);
+        doc.groups.insert(doc::ItemType::Method, vec![group]);
+
+        let mut output = Vec::new();
+        PlainTextRenderer::new(&Default::default(), &mut output)
+            .render_doc(&doc)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.contains(
+                "            ⚠ Deprecated since 1.2.0: use new instead\n              See: `new`\n"
+            ),
+            "{}",
+            output
+        );
+    }
+}
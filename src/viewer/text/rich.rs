@@ -1,36 +1,86 @@
 // SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: MIT
 
-use std::io::{self, Write};
+use std::borrow;
+use std::io;
 
 use html2text::render::text_renderer;
+use kuchiki::traits::TendrilSink as _;
 
 use crate::args;
 use crate::doc;
 use crate::viewer::utils;
 
-#[derive(Debug)]
-pub struct RichTextRenderer {
+/// The classes of admonition blocks like `<div class="warning">` with the prefix and the color
+/// used to display them.
+const ADMONITION_STYLES: &[(&str, char, text_style::AnsiColor)] = &[
+    ("note", 'ℹ', text_style::AnsiColor::Blue),
+    ("warning", '⚠', text_style::AnsiColor::Yellow),
+    ("danger", '⛔', text_style::AnsiColor::Red),
+];
+
+/// The text of the paragraphs inserted at the start (followed by the class) and at the end of an
+/// admonition block by `prepare_html`.
+const ADMONITION_MARKER: &str = "\u{e000}rusty-man-admonition\u{e000}";
+
+pub struct RichTextRenderer<'a> {
+    output: &'a mut dyn io::Write,
     line_length: usize,
+    options: utils::RenderOptions,
     highlighter: Option<utils::Highlighter>,
+    code_highlight: bool,
 }
 
-impl RichTextRenderer {
-    pub fn new(args: &args::ViewerArgs) -> anyhow::Result<Self> {
+impl<'a> RichTextRenderer<'a> {
+    pub fn new(args: &args::ViewerArgs, output: &'a mut dyn io::Write) -> anyhow::Result<Self> {
         Ok(Self {
+            output,
             line_length: utils::get_line_length(args),
+            options: utils::RenderOptions::new(args),
             highlighter: utils::get_highlighter(args)?,
+            code_highlight: !args.no_code_highlight,
         })
     }
 }
 
-impl utils::ManRenderer for RichTextRenderer {
+impl<'a> RichTextRenderer<'a> {
+    fn print_lines(&mut self, indent: usize, lines: &[utils::RichLine]) -> io::Result<()> {
+        let quote_gutter = self.options.prepare_text(utils::QUOTE_GUTTER);
+        let quote_gutter = quote_gutter.as_ref();
+        for line in utils::highlight_html(lines, self.highlighter.as_ref()) {
+            write!(self.output, "{}", " ".repeat(indent))?;
+            render_iter(
+                self.output,
+                line.into_iter().map(|s| match s {
+                    utils::HighlightedHtmlElement::RichString(s) => style_rich_string(s),
+                    utils::HighlightedHtmlElement::StyledString(s) => utils::reset_background(s),
+                    utils::HighlightedHtmlElement::QuoteGutter => {
+                        text_style::StyledStr::plain(quote_gutter)
+                            .with(text_style::AnsiColor::Black.light())
+                    }
+                }),
+            )?;
+            writeln!(self.output)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> utils::ManRenderer for RichTextRenderer<'a> {
     type Error = io::Error;
 
-    fn print_title(&mut self, left: &str, middle: &str, right: &str) -> io::Result<()> {
-        let title = super::format_title(self.line_length, left, middle, right);
-        render(text_style::StyledStr::plain(&title).bold())?;
-        writeln!(io::stdout(), "\n")
+    fn print_title(
+        &mut self,
+        left: &str,
+        middle: &str,
+        right: &str,
+        version: Option<&str>,
+    ) -> io::Result<()> {
+        let right = utils::get_title_right(left, right, version);
+        let title = super::format_title(self.line_length, left, middle, &right);
+        let title = self.options.prepare_text(&title);
+        render(self.output, text_style::StyledStr::plain(&title).bold())?;
+        writeln!(self.output, "\n")
     }
 
     fn print_text(&mut self, indent: u8, s: &doc::Text) -> io::Result<()> {
@@ -40,36 +90,71 @@ impl utils::ManRenderer for RichTextRenderer {
         } else {
             indent
         };
-        let decorator = utils::RichDecorator::new(super::list_link, utils::LinkMode::List);
-        let lines = html2text::parse(s.html.as_bytes())
+        let decorator =
+            utils::RichDecorator::new(super::list_link, utils::LinkMode::List, self.code_highlight);
+        let html = self.options.prepare_html(&s.html);
+        let html = prepare_html(&html);
+        let lines = html2text::parse(html.as_bytes())
             .render(self.line_length - indent, decorator)
             .into_lines();
-        for line in utils::highlight_html(&lines, self.highlighter.as_ref()) {
-            write!(io::stdout(), "{}", " ".repeat(indent))?;
-            render_iter(line.into_iter().map(|s| match s {
-                utils::HighlightedHtmlElement::RichString(s) => style_rich_string(s),
-                utils::HighlightedHtmlElement::StyledString(s) => utils::reset_background(s),
-            }))?;
-            writeln!(io::stdout())?;
+        let texts: Vec<String> = lines.iter().map(|line| line.chars().collect()).collect();
+        let mut lines: Vec<_> = lines
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                *i == 0
+                    || *i + 1 >= texts.len()
+                    || !super::is_nested_list_gap(&texts[i - 1], &texts[*i], &texts[i + 1])
+            })
+            .map(|(_, line)| line)
+            .collect();
+        if self.options.ascii {
+            utils::lines_to_ascii(&mut lines);
         }
-        Ok(())
+
+        // The admonition markers inserted by prepare_html are replaced with a styled prefix line.
+        let mut start = 0;
+        for (i, line) in lines.iter().enumerate() {
+            if let Some(admonition) = get_admonition(line) {
+                self.print_lines(indent, &lines[start..i])?;
+                if let Some((class, symbol, color)) = admonition {
+                    let title = format!("{} {}{}", symbol, class[..1].to_uppercase(), &class[1..]);
+                    let title = self.options.prepare_text(&title);
+                    write!(self.output, "{}", " ".repeat(indent))?;
+                    render(
+                        self.output,
+                        text_style::StyledStr::plain(&title)
+                            .with(color.dark())
+                            .bold(),
+                    )?;
+                    writeln!(self.output)?;
+                }
+                // skip the empty line after the marker paragraph
+                start = i + 1;
+                if lines.get(start).map(|l| l.is_empty()).unwrap_or(false) {
+                    start += 1;
+                }
+            }
+        }
+        self.print_lines(indent, &lines[start..])
     }
 
     fn print_code(&mut self, indent: u8, code: &doc::Code) -> io::Result<()> {
         let indent = usize::from(indent);
         if let Some(highlighter) = &self.highlighter {
-            for line in highlighter.highlight(code.as_ref()) {
-                write!(io::stdout(), "{}", " ".repeat(indent))?;
+            for line in highlighter.highlight(code) {
+                write!(self.output, "{}", " ".repeat(indent))?;
                 render_iter(
+                    self.output,
                     line.iter()
                         .map(text_style::StyledStr::from)
                         .map(utils::reset_background),
                 )?;
             }
-            writeln!(io::stdout())?;
+            writeln!(self.output)?;
         } else {
             for line in code.split('\n') {
-                writeln!(io::stdout(), "{}{}", " ".repeat(indent), line)?;
+                writeln!(self.output, "{}{}", " ".repeat(indent), line)?;
             }
         }
 
@@ -82,13 +167,157 @@ impl utils::ManRenderer for RichTextRenderer {
         s: &str,
         _link: Option<utils::DocLink>,
     ) -> io::Result<()> {
-        write!(io::stdout(), "{}", " ".repeat(usize::from(indent)))?;
-        render(text_style::StyledStr::plain(s).bold())?;
-        writeln!(io::stdout())
+        let s = self.options.prepare_text(s);
+        write!(self.output, "{}", " ".repeat(usize::from(indent)))?;
+        render(self.output, text_style::StyledStr::plain(&s).bold())?;
+        writeln!(self.output)
+    }
+
+    fn print_member_row(
+        &mut self,
+        indent: u8,
+        name: &str,
+        padding: usize,
+        description: &str,
+        _link: Option<utils::DocLink>,
+    ) -> io::Result<()> {
+        write!(self.output, "{}", " ".repeat(usize::from(indent)))?;
+        render(self.output, text_style::StyledStr::plain(name).bold())?;
+        if description.is_empty() {
+            writeln!(self.output)
+        } else {
+            let description = self.options.prepare_text(description);
+            writeln!(self.output, "{}{}", " ".repeat(padding), description)
+        }
+    }
+
+    fn print_platform(&mut self, platform: &str, status: utils::PlatformStatus) -> io::Result<()> {
+        use text_style::AnsiColor;
+
+        self.print_heading(0, "PLATFORM", None)?;
+        let message = status.message(platform);
+        let s = text_style::StyledStr::plain(&message);
+        let s = match status {
+            utils::PlatformStatus::Supported => s.with(AnsiColor::Green.dark()),
+            utils::PlatformStatus::Unknown => s.with(AnsiColor::Blue.dark()),
+            utils::PlatformStatus::Unsupported => s.with(AnsiColor::Yellow.dark()).bold(),
+            utils::PlatformStatus::Warning => s.with(AnsiColor::Red.dark()).bold(),
+        };
+        write!(self.output, "{}", " ".repeat(6))?;
+        render(self.output, s)?;
+        writeln!(self.output, "\n")
+    }
+
+    fn print_notice(&mut self, indent: u8, notice: &str) -> io::Result<()> {
+        let notice = format!("⚠ {}", notice);
+        let notice = self.options.prepare_text(&notice);
+        let s = text_style::StyledStr::plain(&notice)
+            .with(text_style::AnsiColor::Yellow.dark())
+            .bold();
+        write!(self.output, "{}", " ".repeat(usize::from(indent)))?;
+        render(self.output, s)?;
+        writeln!(self.output)
     }
 
     fn println(&mut self) -> io::Result<()> {
-        writeln!(io::stdout())
+        writeln!(self.output)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn options(&self) -> &utils::RenderOptions {
+        &self.options
+    }
+
+    fn print_separator(&mut self) -> io::Result<()> {
+        let separator = self.options.prepare_text("─").repeat(self.line_length);
+        render(
+            self.output,
+            text_style::StyledStr::plain(&separator).with(text_style::AnsiColor::Blue.dark()),
+        )?;
+        writeln!(self.output)
+    }
+
+    fn line_length(&self) -> Option<usize> {
+        Some(self.line_length)
+    }
+}
+
+/// Marks the start and the end of the admonition blocks in the given HTML with paragraphs that
+/// contain the `ADMONITION_MARKER` because html2text does not preserve the classes of the blocks.
+fn prepare_html(html: &str) -> borrow::Cow<'_, str> {
+    if !ADMONITION_STYLES
+        .iter()
+        .any(|(class, _, _)| html.contains(class))
+    {
+        return borrow::Cow::Borrowed(html);
+    }
+
+    let document = kuchiki::parse_html().one(html);
+    let selector = ADMONITION_STYLES
+        .iter()
+        .map(|(class, _, _)| format!("div.{}", class))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let blocks: Vec<_> = match document.select(&selector) {
+        Ok(blocks) => blocks.collect(),
+        Err(()) => return borrow::Cow::Borrowed(html),
+    };
+    if blocks.is_empty() {
+        return borrow::Cow::Borrowed(html);
+    }
+
+    let marker = |text: &str| {
+        let p = kuchiki::NodeRef::new_element(
+            markup5ever::QualName::new(
+                None,
+                markup5ever::Namespace::from("http://www.w3.org/1999/xhtml"),
+                markup5ever::local_name!("p"),
+            ),
+            None,
+        );
+        p.append(kuchiki::NodeRef::new_text(format!(
+            "{}{}",
+            ADMONITION_MARKER, text
+        )));
+        p
+    };
+    for block in blocks {
+        let attributes = block.attributes.borrow();
+        let class = ADMONITION_STYLES
+            .iter()
+            .map(|(class, _, _)| *class)
+            .find(|class| {
+                attributes
+                    .get("class")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .any(|c| c == *class)
+            });
+        if let Some(class) = class {
+            block.as_node().prepend(marker(class));
+            block.as_node().append(marker(""));
+        }
+    }
+    borrow::Cow::Owned(document.to_string())
+}
+
+/// Checks whether the given line is an admonition marker inserted by `prepare_html`.  Returns
+/// `Some(Some(style))` for the start and `Some(None)` for the end of an admonition block.
+fn get_admonition(
+    line: &utils::RichLine,
+) -> Option<Option<&'static (&'static str, char, text_style::AnsiColor)>> {
+    let text: String = line.chars().collect();
+    let class = text.trim().strip_prefix(ADMONITION_MARKER)?;
+    if class.is_empty() {
+        Some(None)
+    } else {
+        ADMONITION_STYLES
+            .iter()
+            .find(|(c, _, _)| *c == class)
+            .map(Some)
     }
 }
 
@@ -113,17 +342,43 @@ fn style_rich_string(ts: &utils::RichString) -> text_style::StyledStr<'_> {
     s
 }
 
-fn render<'a, S>(s: S) -> io::Result<()>
+fn render<'a, S>(output: &mut dyn io::Write, s: S) -> io::Result<()>
 where
     S: Into<text_style::StyledStr<'a>>,
 {
-    text_style::termion::render(io::stdout(), s)
+    text_style::termion::render(output, s)
 }
 
-fn render_iter<'a, I, S>(i: I) -> io::Result<()>
+fn render_iter<'a, I, S>(output: &mut dyn io::Write, i: I) -> io::Result<()>
 where
     I: IntoIterator<Item = S>,
     S: Into<text_style::StyledStr<'a>>,
 {
-    text_style::termion::render_iter(io::stdout(), i)
+    text_style::termion::render_iter(output, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_admonition, prepare_html};
+    use crate::viewer::utils;
+
+    #[test]
+    fn test_prepare_html() {
+        let html = "<p>Text</p>";
+        assert_eq!(html, prepare_html(html));
+
+        let html = r#"<p>Text</p><div class="warning"><p>Careful</p></div>"#;
+        let lines = html2text::parse(prepare_html(html).as_bytes())
+            .render(
+                80,
+                utils::RichDecorator::new(|_| false, utils::LinkMode::List, false),
+            )
+            .into_lines();
+        let admonitions: Vec<_> = lines
+            .iter()
+            .filter_map(get_admonition)
+            .map(|a| a.map(|(class, _, _)| *class))
+            .collect();
+        assert_eq!(vec![Some("warning"), None], admonitions);
+    }
 }
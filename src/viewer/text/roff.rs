@@ -0,0 +1,148 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! Renders the documentation as a roff manpage that can be displayed with `man -l`.
+
+use std::io;
+
+use crate::args;
+use crate::doc;
+use crate::viewer::utils;
+
+/// The manual section for the generated manpages.
+const SECTION: &str = "3rs";
+
+pub struct RoffRenderer<'a> {
+    output: &'a mut dyn io::Write,
+    line_length: usize,
+    options: utils::RenderOptions,
+}
+
+impl<'a> RoffRenderer<'a> {
+    pub fn new(args: &args::ViewerArgs, output: &'a mut dyn io::Write) -> Self {
+        Self {
+            output,
+            line_length: utils::get_line_length(args),
+            options: utils::RenderOptions::new(args),
+        }
+    }
+
+    fn print_lines(&mut self, indent: u8, s: &str) -> io::Result<()> {
+        // Our indentation is relative to the section content which is already indented by man.
+        let indent = indent.saturating_sub(6);
+        if indent > 0 {
+            writeln!(self.output, ".RS {}", indent)?;
+        }
+        writeln!(self.output, ".nf")?;
+        for line in s.split('\n') {
+            writeln!(self.output, "{}", escape_line(line))?;
+        }
+        writeln!(self.output, ".fi")?;
+        if indent > 0 {
+            writeln!(self.output, ".RE")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> utils::ManRenderer for RoffRenderer<'a> {
+    type Error = io::Error;
+
+    fn print_title(
+        &mut self,
+        left: &str,
+        middle: &str,
+        right: &str,
+        version: Option<&str>,
+    ) -> io::Result<()> {
+        // The crate version belongs into the source field of the title line.
+        let source = match version {
+            Some(version) => format!("{} {}", left, version),
+            None => left.to_owned(),
+        };
+        writeln!(
+            self.output,
+            ".TH {} {} \"\" {} {}",
+            quote(middle),
+            SECTION,
+            quote(&source),
+            quote(right)
+        )
+    }
+
+    fn print_name(&mut self, doc: &doc::Doc) -> io::Result<()> {
+        writeln!(self.output, ".SH NAME")?;
+        let summary = doc
+            .description
+            .as_ref()
+            .and_then(|d| d.plain.lines().map(str::trim).find(|l| !l.is_empty()));
+        if let Some(summary) = summary {
+            writeln!(
+                self.output,
+                "{} \\- {}",
+                escape(doc.name.as_ref()),
+                escape(summary)
+            )
+        } else {
+            writeln!(self.output, "{}", escape(doc.name.as_ref()))
+        }
+    }
+
+    fn print_text(&mut self, indent: u8, s: &doc::Text) -> io::Result<()> {
+        let width = self.line_length - usize::from(indent);
+        let text = html2text::from_read(s.html.as_bytes(), width);
+        self.print_lines(indent, text.trim())
+    }
+
+    fn print_code(&mut self, indent: u8, code: &doc::Code) -> io::Result<()> {
+        self.print_lines(indent, code)
+    }
+
+    fn print_heading(
+        &mut self,
+        indent: u8,
+        s: &str,
+        _link: Option<utils::DocLink>,
+    ) -> io::Result<()> {
+        match indent {
+            0 => writeln!(self.output, ".SH {}", quote(s)),
+            3 => writeln!(self.output, ".SS {}", quote(s)),
+            _ => {
+                writeln!(self.output, ".PP")?;
+                writeln!(self.output, ".B {}", quote(s))
+            }
+        }
+    }
+
+    fn println(&mut self) -> io::Result<()> {
+        writeln!(self.output, ".sp")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn options(&self) -> &utils::RenderOptions {
+        &self.options
+    }
+}
+
+/// Escapes backslashes so that roff prints them literally.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\e")
+}
+
+/// Escapes a line of text so that it is not interpreted as a roff request.
+fn escape_line(s: &str) -> String {
+    let s = escape(s);
+    if s.starts_with('.') || s.starts_with('\'') {
+        format!("\\&{}", s)
+    } else {
+        s
+    }
+}
+
+/// Quotes an argument of a roff request.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", escape(s).replace('"', "\"\""))
+}
@@ -1,26 +1,57 @@
 // SPDX-FileCopyrightText: 2020-2021 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: MIT
 
+mod session;
 mod views;
 
+use std::cell;
+use std::cmp;
 use std::convert;
+use std::rc;
+use std::sync::{self, atomic};
+use std::thread;
+use std::time;
 
 use anyhow::Context as _;
-use cursive::view::{Resizable as _, Scrollable as _};
+use cursive::view::{Nameable as _, Resizable as _, Scrollable as _};
 use cursive::views::{
-    Dialog, EditView, LinearLayout, OnEventView, PaddedView, Panel, SelectView, TextView,
+    Canvas, Dialog, EditView, LayerPosition, LinearLayout, NamedView, OnEventView, PaddedView,
+    Panel, ScrollView, SelectView, TextContent, TextView,
 };
 use cursive::{event, theme, utils::markup};
 use cursive_markup::MarkupView;
 
 use crate::args;
 use crate::doc;
+use crate::edit;
 use crate::index;
 use crate::source;
 use crate::viewer::{self, utils, utils::ManRenderer as _};
 
+use session::Session;
 use views::{CodeView, HtmlRenderer, LinkView};
 
+/// The name of the scrollable view that contains the rendered documentation.
+const DOC_VIEW_NAME: &str = "doc";
+/// The name of the container for the pinned documentation at the top of a documentation page.
+const PINNED_VIEW_NAME: &str = "pinned";
+/// The status message that is shown after copying a definition to the clipboard.
+const COPIED_MESSAGE: &str = "Copied!";
+/// The status message that is shown after closing all pages except the first one with the Z key.
+const NAVIGATION_CLEARED_MESSAGE: &str = "Navigation cleared";
+/// The entry in the theme selection that disables syntax highlighting.
+const THEME_NONE: &str = "none";
+/// The name of the edit view in the dialog for opening documentation.
+const OPEN_DOC_EDIT_NAME: &str = "open_doc_edit";
+/// The name of the list of completions in the dialog for opening documentation.
+const OPEN_DOC_COMPLETIONS_NAME: &str = "open_doc_completions";
+/// The maximum number of completions in the dialog for opening documentation.
+const MAX_COMPLETIONS: usize = 10;
+/// The time to wait after the last change of the input before searching for completions.
+const COMPLETION_DELAY: time::Duration = time::Duration::from_millis(200);
+
+type DocView = OnEventView<ScrollView<LinearLayout>>;
+
 #[derive(Clone, Debug)]
 pub struct TuiViewer {}
 
@@ -34,6 +65,7 @@ impl TuiViewer {
         sources: source::Sources,
         args: args::ViewerArgs,
         doc: &doc::Doc,
+        watch: bool,
         f: F,
     ) -> anyhow::Result<()>
     where
@@ -44,6 +76,9 @@ impl TuiViewer {
         f(&mut renderer)?;
         let view = renderer.into_view();
         s.add_fullscreen_layer(view);
+        if watch {
+            watch_doc(&mut s, doc)?;
+        }
         s.try_run_with(create_backend)?;
         Ok(())
     }
@@ -56,7 +91,10 @@ impl viewer::Viewer for TuiViewer {
         args: args::ViewerArgs,
         doc: &doc::Doc,
     ) -> anyhow::Result<()> {
-        self.render(sources, args, doc, |renderer| renderer.render_doc(doc))
+        let watch = args.watch;
+        self.render(sources, args, doc, watch, |renderer| {
+            renderer.render_doc(doc)
+        })
     }
 
     fn open_examples(
@@ -66,66 +104,193 @@ impl viewer::Viewer for TuiViewer {
         doc: &doc::Doc,
         examples: Vec<doc::Example>,
     ) -> anyhow::Result<()> {
-        self.render(sources, args, doc, |renderer| {
+        self.render(sources, args, doc, false, |renderer| {
             renderer.render_examples(doc, &examples)
         })
     }
+
+    fn default_picker(&self) -> args::Picker {
+        args::Picker::Tui
+    }
 }
 
 pub struct Context {
     pub sources: source::Sources,
     pub args: args::ViewerArgs,
     pub highlighter: Option<utils::Highlighter>,
+    pub history: Vec<HistoryEntry>,
+    pub count: CountPrefix,
+    /// The documentation item that is pinned to the top of the screen, see `toggle_pinned`.
+    pub pinned: Option<doc::Doc>,
+    pub session: Session,
+}
+
+/// The scroll position of a documentation page that was left by opening another page.
+#[derive(Clone, Copy, Debug)]
+pub struct HistoryEntry {
+    /// The number of layers on the screen if the page is the top layer.
+    depth: usize,
+    offset: cursive::Vec2,
 }
 
 impl Context {
-    pub fn new(sources: source::Sources, args: args::ViewerArgs) -> anyhow::Result<Context> {
+    pub fn new(sources: source::Sources, mut args: args::ViewerArgs) -> anyhow::Result<Context> {
+        // The theme selected with the T key is only used if the theme is not set explicitly with
+        // the command-line options or the configuration file.
+        let session = Session::load();
+        if let Some(theme) = &session.theme {
+            if args.theme.is_none() && !args.no_syntax_highlight {
+                apply_theme(&mut args, theme);
+            }
+        }
         let highlighter = utils::get_highlighter(&args)?;
         Ok(Context {
             sources,
             args,
             highlighter,
+            history: Vec::new(),
+            count: CountPrefix::new(),
+            pinned: None,
+            session,
         })
     }
 
-    pub fn create_renderer(&self, doc: &doc::Doc) -> TuiManRenderer {
+    pub fn create_renderer(&self, doc: &doc::Doc) -> TuiManRenderer<'_> {
+        let options = utils::RenderOptions {
+            // The siblings can be selected with the s key.
+            show_siblings: false,
+            ..utils::RenderOptions::new(&self.args)
+        };
         TuiManRenderer::new(
             doc,
             self.args.max_width.unwrap_or(100),
+            !self.args.no_code_highlight,
             self.highlighter.as_ref(),
+            options,
+            self.count.status.clone(),
         )
     }
 }
 
+/// The count prefix for vim-like motion commands, for example `5j`.
+///
+/// The count is shared between the callbacks for the digit and motion keys and displayed in the
+/// status line of the documentation pages.
+#[derive(Clone)]
+pub struct CountPrefix {
+    count: rc::Rc<cell::Cell<Option<usize>>>,
+    status: TextContent,
+}
+
+impl CountPrefix {
+    fn new() -> CountPrefix {
+        CountPrefix {
+            count: Default::default(),
+            status: TextContent::new(""),
+        }
+    }
+
+    fn push_digit(&self, digit: usize) {
+        let count = match self.count.get() {
+            Some(count) => count.saturating_mul(10).saturating_add(digit),
+            // a leading zero is not a count
+            None if digit == 0 => return,
+            None => digit,
+        };
+        self.count.set(Some(count));
+        self.status.set_content(count.to_string());
+    }
+
+    /// Returns the current count, or one if there is no count, and resets the count.
+    fn take(&self) -> usize {
+        let count = self.count.take();
+        if count.is_some() {
+            self.status.set_content("");
+        }
+        count.unwrap_or(1)
+    }
+
+    fn clear(&self) {
+        self.take();
+    }
+}
+
 pub struct TuiManRenderer<'s> {
     doc_name: doc::Fqn,
     doc_ty: doc::ItemType,
     layout: LinearLayout,
     max_width: usize,
+    code_highlight: bool,
     highlighter: Option<&'s utils::Highlighter>,
+    options: utils::RenderOptions,
+    status: TextContent,
+    doc: doc::Doc,
 }
 
 impl<'s> TuiManRenderer<'s> {
     pub fn new(
         doc: &doc::Doc,
         max_width: usize,
+        code_highlight: bool,
         highlighter: Option<&'s utils::Highlighter>,
+        options: utils::RenderOptions,
+        status: TextContent,
     ) -> TuiManRenderer<'s> {
         TuiManRenderer {
             doc_name: doc.name.clone(),
             doc_ty: doc.ty,
             layout: LinearLayout::vertical(),
             max_width,
+            code_highlight,
             highlighter,
+            options,
+            status,
+            doc: doc.clone(),
         }
     }
 
+    /// Renders only the trait implementations of the given item, see the `I` key.
+    pub fn render_implementations_only(
+        &mut self,
+        doc: &doc::Doc,
+    ) -> Result<(), convert::Infallible> {
+        let mut impls = doc.clone();
+        impls.groups.retain(|ty, _| *ty == doc::ItemType::Impl);
+        self.options.section_order = vec![args::SectionKind::Members];
+        // The members are only listed with a depth of at least two.
+        self.options.depth = self.options.depth.map(|depth| cmp::max(depth, 2));
+        self.render_doc(&impls)
+    }
+
     fn into_view(self) -> impl cursive::View {
         use cursive::view::scroll::Scroller as _;
         use cursive::With as _;
 
-        let title = format!("{} {}", self.doc_ty.name(), self.doc_name);
-        let scroll = self.layout.scrollable();
+        let mut title = format!("{} {}", self.doc_ty.name(), self.doc_name);
+        if let Some(version) = &self.doc.crate_version {
+            title.push_str(&format!(" ({} {})", self.doc_name.krate(), version));
+        }
+        let siblings: Vec<_> = self
+            .doc
+            .siblings
+            .iter()
+            .filter(|d| !self.options.public_only || d.is_public())
+            .map(|d| utils::DocLink {
+                name: d.name.clone(),
+                ty: Some(d.ty),
+            })
+            .collect();
+        let doc = rc::Rc::new(self.doc);
+        let pinned_doc = doc.clone();
+        let theme_doc = doc.clone();
+        let reload_doc = doc.clone();
+        let impls_doc = doc.clone();
+        let crate_doc = doc.clone();
+        let docs_rs_doc = doc.clone();
+        let scroll = self
+            .layout
+            .scrollable()
+            .show_scrollbars(!self.options.ascii);
         let wrapper = scroll
             .wrap_with(OnEventView::new)
             .on_pre_event_inner(event::Key::PageUp, |v, _| {
@@ -141,15 +306,50 @@ impl<'s> TuiManRenderer<'s> {
                     scroller.scroll_down(scroller.last_outer_size().y.saturating_sub(1));
                 }
                 Some(event::EventResult::Consumed(None))
-            });
-        Panel::new(wrapper.full_screen()).title(title)
+            })
+            .on_pre_event('s', move |s| select_sibling_dialog(s, siblings.clone()))
+            .on_pre_event('p', move |s| toggle_pinned(s, &pinned_doc))
+            .on_pre_event('T', move |s| select_theme_dialog(s, theme_doc.clone()))
+            .on_pre_event('R', move |s| reload_page(s, &reload_doc))
+            .on_pre_event('I', move |s| implementations_dialog(s, &impls_doc))
+            .on_pre_event('w', move |s| {
+                with_report_error(s, |_| open_crate_page(&crate_doc))
+            })
+            .on_pre_event('W', move |s| {
+                with_report_error(s, |_| open_docs_rs_page(&docs_rs_doc))
+            })
+            .on_pre_event('y', move |s| copy_definition_dialog(s, &doc));
+        let panel = Panel::new(wrapper.with_name(DOC_VIEW_NAME).full_screen()).title(title);
+        LinearLayout::vertical()
+            .child(LinearLayout::vertical().with_name(PINNED_VIEW_NAME))
+            .child(panel)
+            .child(TextView::new_with_content(self.status).h_align(cursive::align::HAlign::Right))
+    }
+
+    /// Returns a view with a fixed height that shows the rendered documentation in the pinned
+    /// panel.
+    fn into_pinned_view(self, height: usize) -> impl cursive::View {
+        let title = format!("Pinned: {} {}", self.doc_ty.name(), self.doc_name);
+        Panel::new(
+            self.layout
+                .scrollable()
+                .show_scrollbars(!self.options.ascii),
+        )
+        .title(title)
+        .fixed_height(height)
     }
 }
 
 impl<'s> utils::ManRenderer for TuiManRenderer<'s> {
     type Error = convert::Infallible;
 
-    fn print_title(&mut self, _left: &str, _center: &str, _right: &str) -> Result<(), Self::Error> {
+    fn print_title(
+        &mut self,
+        _left: &str,
+        _center: &str,
+        _right: &str,
+        _version: Option<&str>,
+    ) -> Result<(), Self::Error> {
         Ok(())
     }
 
@@ -159,7 +359,8 @@ impl<'s> utils::ManRenderer for TuiManRenderer<'s> {
         text: &str,
         link: Option<utils::DocLink>,
     ) -> Result<(), Self::Error> {
-        let text = markup::StyledString::styled(text, theme::Effect::Bold);
+        let text =
+            markup::StyledString::styled(self.options.prepare_text(text), theme::Effect::Bold);
         if let Some(link) = link {
             let heading = LinkView::new(text, move |s| {
                 if let Err(err) = open_link(s, link.clone().into()) {
@@ -175,11 +376,13 @@ impl<'s> utils::ManRenderer for TuiManRenderer<'s> {
     }
 
     fn print_code(&mut self, indent: u8, code: &doc::Code) -> Result<(), Self::Error> {
+        let width = self.max_width.saturating_sub(usize::from(indent));
+        let code = views::wrap_code(code, width);
         if let Some(highlighter) = self.highlighter {
-            let code = CodeView::new(&code.to_string(), highlighter);
+            let code = CodeView::new(&code, highlighter);
             self.layout.add_child(indent_view(indent, code));
         } else {
-            let text = TextView::new(code.to_string());
+            let text = TextView::new(code);
             self.layout.add_child(indent_view(indent, text));
         }
         Ok(())
@@ -187,7 +390,12 @@ impl<'s> utils::ManRenderer for TuiManRenderer<'s> {
 
     fn print_text(&mut self, indent: u8, text: &doc::Text) -> Result<(), Self::Error> {
         let indent = usize::from(indent);
-        let renderer = HtmlRenderer::new(&text.html, self.highlighter.cloned());
+        let renderer = HtmlRenderer::new(
+            &self.options.prepare_html(&text.html),
+            self.highlighter.cloned(),
+            self.code_highlight,
+            self.options.ascii,
+        );
         let mut view = MarkupView::with_renderer(renderer);
         view.set_maximum_width(self.max_width.saturating_sub(indent));
         let doc_name = self.doc_name.clone();
@@ -197,16 +405,185 @@ impl<'s> utils::ManRenderer for TuiManRenderer<'s> {
         Ok(())
     }
 
+    fn print_platform(
+        &mut self,
+        platform: &str,
+        status: utils::PlatformStatus,
+    ) -> Result<(), Self::Error> {
+        let mut text = format!("⚠ Platform: {} only", platform);
+        if status == utils::PlatformStatus::Warning {
+            text.push_str(&format!(" (not available on {})", std::env::consts::OS));
+        }
+        let style = match status {
+            utils::PlatformStatus::Supported => theme::Style::from(theme::BaseColor::Green.dark()),
+            utils::PlatformStatus::Unknown => theme::Style::from(theme::BaseColor::Blue.dark()),
+            utils::PlatformStatus::Unsupported => {
+                theme::Style::from(theme::BaseColor::Yellow.dark()).combine(theme::Effect::Bold)
+            }
+            utils::PlatformStatus::Warning => {
+                theme::Style::from(theme::BaseColor::Red.dark()).combine(theme::Effect::Bold)
+            }
+        };
+        let text = self.options.prepare_text(&text).into_owned();
+        self.layout
+            .add_child(TextView::new(markup::StyledString::styled(text, style)));
+        self.layout.add_child(TextView::new(" "));
+        Ok(())
+    }
+
+    fn print_notice(&mut self, indent: u8, notice: &str) -> Result<(), Self::Error> {
+        let style =
+            theme::Style::from(theme::BaseColor::Yellow.dark()).combine(theme::Effect::Bold);
+        let notice = format!("⚠ {}", notice);
+        let text = markup::StyledString::styled(self.options.prepare_text(&notice), style);
+        self.layout
+            .add_child(indent_view(indent, TextView::new(text)));
+        Ok(())
+    }
+
+    fn print_replacement(
+        &mut self,
+        indent: u8,
+        member: &doc::Fqn,
+        replacement: &str,
+    ) -> Result<(), Self::Error> {
+        // The replacement is often a relative path, so we fall back to searching for it if the
+        // link cannot be resolved.
+        let link = utils::get_replacement_link(member, replacement);
+        let keyword = doc::Name::from(replacement.to_owned());
+        let text =
+            markup::StyledString::styled(format!("See: {}", replacement), theme::Effect::Underline);
+        let view = LinkView::new(text, move |s| {
+            with_report_error(s, |s| {
+                match context(s).sources.find(&link.name, link.ty)? {
+                    Some(doc) => {
+                        open_doc(s, &doc);
+                        Ok(())
+                    }
+                    None => open_keyword(s, &keyword),
+                }
+            });
+        });
+        self.layout.add_child(indent_view(indent, view));
+        Ok(())
+    }
+
+    fn print_required_features(&mut self, doc: &doc::Doc) -> Result<(), Self::Error> {
+        // The feature names link to the feature list on docs.rs that explains how to enable them.
+        let url = utils::get_features_url(doc);
+        let links: Vec<_> = doc
+            .required_features
+            .iter()
+            .map(|feature| format!("<a href=\"{}\"><code>{}</code></a>", url, feature))
+            .collect();
+        let text = doc::Text {
+            plain: format!("[requires: {}]", doc.required_features.join(", ")),
+            html: format!("<p>[requires: {}]</p>", links.join(", ")),
+        };
+        self.print_text(6, &text)
+    }
+
     fn println(&mut self) -> Result<(), Self::Error> {
         self.layout.add_child(TextView::new(" "));
         Ok(())
     }
+
+    fn options(&self) -> &utils::RenderOptions {
+        &self.options
+    }
+
+    fn show_see_also(&self) -> bool {
+        // The links in the description are already interactive.
+        false
+    }
+
+    fn print_separator(&mut self) -> Result<(), Self::Error> {
+        let line = self.options.prepare_text("─").into_owned();
+        let separator = Canvas::new(line)
+            .with_draw(|line, printer| {
+                printer.with_color(theme::PaletteColor::Secondary.into(), |printer| {
+                    printer.print_hline((0, 0), printer.size.x, line);
+                })
+            })
+            .with_required_size(|_, constraint| (constraint.x, 1).into());
+        self.layout.add_child(separator);
+        Ok(())
+    }
 }
 
 fn indent_view<V>(indent: impl Into<usize>, view: V) -> PaddedView<V> {
     PaddedView::lrtb(indent.into(), 0, 0, 0, view)
 }
 
+/// Lets the user select one of the given search results.  The synopsis of the highlighted item is
+/// shown in a preview pane.  Returns `None` if the user cancelled the selection.
+pub fn select_item(
+    sources: &source::Sources,
+    name: &doc::Name,
+    items: &[index::IndexItem],
+) -> anyhow::Result<Option<index::IndexItem>> {
+    use cursive::event::Key;
+
+    let mut s = cursive::Cursive::new();
+    let highlighted = rc::Rc::new(cell::Cell::new(0));
+    let selected = rc::Rc::new(cell::Cell::new(None));
+
+    let mut select_view = SelectView::new();
+    select_view.add_all(
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.to_string(), i)),
+    );
+    let on_select = highlighted.clone();
+    select_view.set_on_select(move |_, i| on_select.set(*i));
+    let on_submit = selected.clone();
+    select_view.set_on_submit(move |s, i| {
+        on_submit.set(Some(*i));
+        s.quit();
+    });
+
+    let preview = TextContent::new("");
+    let layout = LinearLayout::vertical()
+        .child(select_view.scrollable().full_height())
+        .child(Panel::new(TextView::new_with_content(preview.clone())).title("Synopsis"));
+    let title = format!("Found multiple matches for {} – select one", name);
+    s.add_fullscreen_layer(Dialog::around(layout).title(title));
+    s.add_global_callback(Key::Esc, |s| s.quit());
+    s.add_global_callback('q', |s| s.quit());
+
+    let mut runner = s.runner(create_backend()?);
+    let mut previewed = None;
+    runner.refresh();
+    while runner.is_running() {
+        let i = highlighted.get();
+        if previewed != Some(i) {
+            previewed = Some(i);
+            preview.set_content(get_preview(sources, &items[i]));
+            runner.refresh();
+        }
+        runner.step();
+    }
+
+    Ok(selected.get().map(|i| items[i].clone()))
+}
+
+fn get_preview(sources: &source::Sources, item: &index::IndexItem) -> String {
+    match sources.find(&item.name, Some(item.ty)) {
+        Ok(Some(doc)) => {
+            if let Some(definition) = doc.definition {
+                definition.to_string()
+            } else if let Some(description) = doc.description {
+                description.plain
+            } else {
+                "No synopsis available".to_owned()
+            }
+        }
+        Ok(None) => format!("Could not find documentation for {}", item.name),
+        Err(err) => format!("Could not load documentation for {}: {}", item.name, err),
+    }
+}
+
 fn create_backend() -> anyhow::Result<Box<dyn cursive::backend::Backend>> {
     let termion =
         cursive::backends::termion::Backend::init().context("Could not create termion backend")?;
@@ -222,31 +599,75 @@ fn create_cursive(
 
     let mut cursive = cursive::Cursive::new();
 
-    cursive.set_user_data(Context::new(sources, args)?);
+    let context = Context::new(sources, args)?;
+    let count = context.count.clone();
+    let ascii = context.args.ascii;
+    cursive.set_user_data(context);
 
-    // vim-like keybindings
-    cursive.add_global_callback('j', |s| s.on_event(Key::Down.into()));
-    cursive.add_global_callback('k', |s| s.on_event(Key::Up.into()));
-    cursive.add_global_callback('h', |s| s.on_event(Key::Left.into()));
-    cursive.add_global_callback('l', |s| s.on_event(Key::Right.into()));
-    cursive.add_global_callback('G', |s| s.on_event(Key::End.into()));
-    cursive.add_global_callback('g', |s| s.on_event(Key::Home.into()));
+    // vim-like keybindings with an optional count prefix
+    let motions = [
+        ('j', Key::Down),
+        ('k', Key::Up),
+        ('h', Key::Left),
+        ('l', Key::Right),
+        ('G', Key::End),
+        ('g', Key::Home),
+    ];
+    for (c, key) in motions.iter().copied() {
+        let count = count.clone();
+        cursive.add_global_callback(c, move |s| {
+            for _ in 0..count.take() {
+                s.on_event(key.into());
+            }
+        });
+    }
+    for digit in 0..10 {
+        let c = std::char::from_digit(digit, 10).unwrap();
+        let count = count.clone();
+        cursive.add_global_callback(c, move |_| count.push_digit(digit as usize));
+    }
+    // Z clears the navigation, and ZZ quits like in vim.
+    let pending_quit = rc::Rc::new(cell::Cell::new(false));
+    let pending_quit_reset = pending_quit.clone();
+    cursive.add_global_callback('Z', move |s| {
+        if pending_quit.replace(true) {
+            s.quit();
+        } else {
+            clear_navigation(s);
+        }
+    });
+    // Any other key resets the count prefix and the pending Z.
+    let is_count_key =
+        move |c: char| c.is_ascii_digit() || motions.iter().any(|(motion, _)| *motion == c);
+    cursive.set_on_pre_event_inner(
+        event::EventTrigger::from_fn(move |e| match e {
+            Event::Char(c) => !is_count_key(*c),
+            Event::Key(_) | Event::CtrlChar(_) | Event::AltChar(_) => true,
+            _ => false,
+        }),
+        move |e| {
+            count.clear();
+            if *e != Event::Char('Z') {
+                pending_quit_reset.set(false);
+            }
+            None
+        },
+    );
     cursive.add_global_callback(Event::CtrlChar('f'), |s| s.on_event(Key::PageDown.into()));
     cursive.add_global_callback(Event::CtrlChar('b'), |s| s.on_event(Key::PageUp.into()));
 
     cursive.add_global_callback('q', |s| s.quit());
-    cursive.add_global_callback(event::Key::Backspace, |s| {
-        let screen = s.screen_mut();
-        if screen.len() > 1 {
-            screen.pop_layer();
-        }
-    });
+    cursive.add_global_callback(event::Key::Backspace, go_back);
     cursive.add_global_callback('o', open_doc_dialog);
 
     let mut theme = theme::Theme {
         shadow: false,
         ..Default::default()
     };
+    // cursive can only draw borders with box-drawing characters.
+    if ascii {
+        theme.borders = theme::BorderStyle::None;
+    }
     theme.palette[theme::PaletteColor::Background] = theme::Color::TerminalDefault;
     theme.palette[theme::PaletteColor::View] = theme::Color::TerminalDefault;
     theme.palette[theme::PaletteColor::Primary] = theme::Color::TerminalDefault;
@@ -264,7 +685,7 @@ fn report_error(s: &mut cursive::Cursive, error: anyhow::Error) {
     let context: Vec<_> = error
         .chain()
         .skip(1)
-        .map(|e| format!("    {}", e.to_string()))
+        .map(|e| format!("    {}", e))
         .collect();
 
     let mut msg = error.to_string();
@@ -287,32 +708,163 @@ where
 }
 
 fn open_doc_dialog(s: &mut cursive::Cursive) {
-    let mut edit_view = EditView::new();
+    // The number of changes of the input, used to discard outdated completion requests.
+    let generation = sync::Arc::new(atomic::AtomicUsize::new(0));
+    let mut edit_view =
+        EditView::new().on_edit(move |s, text, _| schedule_completions(s, text, &generation));
     edit_view.set_on_submit(|s, val| {
         with_report_error(s, |s| {
             s.pop_layer();
-            let sources = &context(s).sources;
-            let name = doc::Name::from(val.to_owned());
-            let mut doc = sources.find(&name, None)?;
-            if doc.is_none() {
-                let items = sources.search(&name)?;
-                if items.len() > 1 {
-                    select_doc_dialog(s, items);
-                    return Ok(());
-                } else if !items.is_empty() {
-                    doc = sources.find(&items[0].name, Some(items[0].ty))?;
+            open_keyword(s, &doc::Name::from(val.to_owned()))
+        });
+    });
+    let edit_view = OnEventView::new(edit_view.with_name(OPEN_DOC_EDIT_NAME))
+        .on_pre_event(event::Key::Tab, complete_common_prefix);
+    let mut completions = SelectView::<index::IndexItem>::new();
+    completions.set_on_submit(|s, item| {
+        s.pop_layer();
+        open_index_item(s, item);
+    });
+    let layout = LinearLayout::vertical()
+        .child(edit_view.min_width(40))
+        .child(completions.with_name(OPEN_DOC_COMPLETIONS_NAME));
+    let dialog = Dialog::around(layout).title("Open documentation");
+    s.add_layer(dialog);
+}
+
+/// Opens the documentation for the given keyword.  If there is no exact match, the search index is
+/// used, and the user can select one of the results if there are multiple matches.
+fn open_keyword(s: &mut cursive::Cursive, name: &doc::Name) -> anyhow::Result<()> {
+    let context = context(s);
+    let sources = &context.sources;
+    let mut doc = sources.find(name, None)?;
+    if doc.is_none() {
+        let mut items = sources.search(name)?;
+        if context.args.public_only {
+            sources.retain_public(&mut items);
+        }
+        if items.len() > 1 {
+            select_doc_dialog(s, items);
+            return Ok(());
+        } else if !items.is_empty() {
+            doc = sources.find(&items[0].name, Some(items[0].ty))?;
+        }
+    }
+    if let Some(doc) = doc {
+        open_doc(s, &doc);
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Could not find documentation for {}", name))
+    }
+}
+
+/// Updates the completions in the dialog for opening documentation if the input has not been
+/// changed for `COMPLETION_DELAY`.
+fn schedule_completions(
+    s: &mut cursive::Cursive,
+    text: &str,
+    generation: &sync::Arc<atomic::AtomicUsize>,
+) {
+    let current = generation.fetch_add(1, atomic::Ordering::SeqCst) + 1;
+    let generation = generation.clone();
+    let text = text.trim().to_owned();
+    let cb_sink = s.cb_sink().clone();
+    thread::spawn(move || {
+        thread::sleep(COMPLETION_DELAY);
+        if generation.load(atomic::Ordering::SeqCst) == current {
+            let result = cb_sink.send(Box::new(move |s| {
+                if generation.load(atomic::Ordering::SeqCst) == current {
+                    update_completions(s, &text);
                 }
+            }));
+            if result.is_err() {
+                log::warn!("Could not send the completion callback");
             }
-            if let Some(doc) = doc {
-                open_doc(s, &doc);
-                Ok(())
-            } else {
-                Err(anyhow::anyhow!("Could not find documentation for {}", name))
+        }
+    });
+}
+
+/// Replaces the completions in the dialog for opening documentation with the search results for
+/// the given prefix.
+fn update_completions(s: &mut cursive::Cursive, prefix: &str) {
+    let items = if prefix.is_empty() {
+        Vec::new()
+    } else {
+        match context(s).sources.search_prefix(prefix, MAX_COMPLETIONS) {
+            Ok(items) => items,
+            Err(err) => {
+                log::warn!("Could not search completions for {}: {}", prefix, err);
+                Vec::new()
             }
+        }
+    };
+    s.call_on_name(
+        OPEN_DOC_COMPLETIONS_NAME,
+        |view: &mut SelectView<index::IndexItem>| {
+            view.clear();
+            view.add_all(items.into_iter().map(|item| {
+                let label = format!("{} ({})", item.name, item.ty.name());
+                (label, item)
+            }));
+        },
+    );
+}
+
+/// Replaces the input in the dialog for opening documentation with the longest common prefix of
+/// the completions.
+fn complete_common_prefix(s: &mut cursive::Cursive) {
+    let text = s
+        .call_on_name(OPEN_DOC_EDIT_NAME, |view: &mut EditView| view.get_content())
+        .unwrap_or_default();
+    let prefix = s
+        .call_on_name(
+            OPEN_DOC_COMPLETIONS_NAME,
+            |view: &mut SelectView<index::IndexItem>| {
+                let matches = view
+                    .iter()
+                    .filter_map(|(_, item)| index::match_prefix(item.name.as_ref(), text.trim()));
+                get_common_prefix(matches).map(ToOwned::to_owned)
+            },
+        )
+        .flatten();
+    if let Some(prefix) = prefix.filter(|prefix| prefix.len() > text.trim().len()) {
+        let callback = s.call_on_name(OPEN_DOC_EDIT_NAME, |view: &mut EditView| {
+            view.set_content(prefix)
         });
+        if let Some(callback) = callback {
+            callback(s);
+        }
+    }
+}
+
+/// Returns the longest common prefix of the given strings, or `None` if there are no strings.
+fn get_common_prefix<'a>(mut strings: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let first = strings.next()?;
+    Some(strings.fold(first, |prefix, s| {
+        let len = prefix
+            .char_indices()
+            .zip(s.chars())
+            .find(|((_, c1), c2)| c1 != c2)
+            .map(|((idx, _), _)| idx)
+            .unwrap_or_else(|| cmp::min(prefix.len(), s.len()));
+        &prefix[..len]
+    }))
+}
+
+/// Opens the documentation for the given search result.
+fn open_index_item(s: &mut cursive::Cursive, item: &index::IndexItem) {
+    with_report_error(s, |s| {
+        let doc = context(s).sources.find(&item.name, Some(item.ty))?;
+        if let Some(doc) = doc {
+            open_doc(s, &doc);
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Could not find documentation for {}",
+                item.name
+            ))
+        }
     });
-    let dialog = Dialog::around(edit_view.min_width(40)).title("Open documentation");
-    s.add_layer(dialog);
 }
 
 fn select_doc_dialog(s: &mut cursive::Cursive, items: Vec<index::IndexItem>) {
@@ -322,168 +874,413 @@ fn select_doc_dialog(s: &mut cursive::Cursive, items: Vec<index::IndexItem>) {
             .into_iter()
             .map(|item| (item.name.as_ref().to_owned(), item)),
     );
-    select_view.set_on_submit(|s, item| {
-        with_report_error(s, |s| {
-            let doc = context(s).sources.find(&item.name, Some(item.ty))?;
-            if let Some(doc) = doc {
-                open_doc(s, &doc);
-                Ok(())
-            } else {
-                Err(anyhow::anyhow!(
-                    "Could not find documentation for {}",
-                    item.name
-                ))
-            }
-        });
-    });
+    select_view.set_on_submit(open_index_item);
     let dialog = Dialog::around(select_view.scrollable()).title("Select documentation item");
     s.add_layer(dialog);
 }
 
-fn open_doc(s: &mut cursive::Cursive, doc: &doc::Doc) {
+fn select_sibling_dialog(s: &mut cursive::Cursive, siblings: Vec<utils::DocLink>) {
+    if siblings.is_empty() {
+        s.add_layer(Dialog::info("There are no other items in this module.").title("Siblings"));
+        return;
+    }
+    let mut select_view = SelectView::new();
+    select_view.add_all(siblings.into_iter().map(|link| {
+        let label = if let Some(ty) = link.ty {
+            format!("{} {}", ty.name(), link.name.last())
+        } else {
+            link.name.last().to_owned()
+        };
+        (label, link)
+    }));
+    select_view.set_on_submit(|s, link| {
+        s.pop_layer();
+        if let Err(err) = open_link(s, link.clone().into()) {
+            report_error(s, err);
+        }
+    });
+    let dialog = Dialog::around(select_view.scrollable()).title("Jump to sibling");
+    s.add_layer(dialog);
+}
+
+/// Shows the trait implementations of the given item in a scrollable dialog.
+fn implementations_dialog(s: &mut cursive::Cursive, doc: &doc::Doc) {
+    if !doc.groups.contains_key(&doc::ItemType::Impl) {
+        s.add_layer(
+            Dialog::info("This item has no trait implementations.").title("Implementations"),
+        );
+        return;
+    }
     let mut renderer = context(s).create_renderer(doc);
-    renderer.render_doc(doc).unwrap();
-    let view = renderer.into_view();
-    s.add_fullscreen_layer(view);
+    renderer.render_implementations_only(doc).unwrap();
+    let title = format!("Implementations of {}", doc.name.last());
+    let dialog = Dialog::around(renderer.layout.scrollable())
+        .title(title)
+        .dismiss_button("Close");
+    s.add_layer(dialog);
 }
 
-fn handle_link(s: &mut cursive::Cursive, doc_name: &doc::Fqn, doc_ty: doc::ItemType, link: &str) {
-    let result = resolve_link(doc_name, doc_ty, link).and_then(|link| open_link(s, link));
+/// Opens the manifest of the crate of the given item if it is a member of the local workspace, or
+/// its page on crates.io otherwise, see the `w` key.
+fn open_crate_page(doc: &doc::Doc) -> anyhow::Result<()> {
+    if let Some(manifest) = edit::find_manifest(doc) {
+        open::that(&manifest).with_context(|| format!("Failed to open {}", manifest.display()))
+    } else {
+        let url = format!("https://crates.io/crates/{}", doc.name.krate());
+        webbrowser::open(&url)
+            .map(|_| ())
+            .context("Failed to open web browser")
+    }
+}
+
+/// Opens the page of the crate of the given item on docs.rs, see the `W` key.
+fn open_docs_rs_page(doc: &doc::Doc) -> anyhow::Result<()> {
+    let url = format!("https://docs.rs/{}", doc.name.krate());
+    webbrowser::open(&url)
+        .map(|_| ())
+        .context("Failed to open web browser")
+}
+
+/// Copies the definition of the given item to the clipboard if it has no members, or lets the
+/// user select the member whose definition should be copied.
+fn copy_definition_dialog(s: &mut cursive::Cursive, doc: &doc::Doc) {
+    if doc.groups.is_empty() {
+        if let Some(definition) = &doc.definition {
+            copy_definition(s, definition);
+        } else {
+            s.add_layer(Dialog::info("This item has no definition.").title("Copy definition"));
+        }
+        return;
+    }
+
+    let mut members = Vec::new();
+    for group in doc.groups.values().flatten() {
+        members.extend(
+            group
+                .members
+                .iter()
+                .filter(|member| member.definition.is_some())
+                .cloned(),
+        );
+    }
+    if members.is_empty() {
+        s.add_layer(
+            Dialog::info("There are no members with a definition.").title("Copy definition"),
+        );
+        return;
+    }
+
+    let mut select_view = SelectView::new();
+    select_view.add_all(members.into_iter().map(|member| {
+        let label = format!("{} {}", member.ty.name(), member.name.last());
+        (label, member)
+    }));
+    select_view.set_on_submit(|s, member: &doc::Doc| {
+        s.pop_layer();
+        if let Some(definition) = &member.definition {
+            copy_definition(s, definition);
+        }
+    });
+    let dialog = Dialog::around(select_view.scrollable()).title("Copy definition");
+    s.add_layer(dialog);
+}
+
+/// Copies the given definition to the clipboard and shows a message in the status line for two
+/// seconds.
+fn copy_definition(s: &mut cursive::Cursive, definition: &doc::Code) {
+    let result = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(definition.to_string()))
+        .context("Could not copy the definition to the clipboard");
     if let Err(err) = result {
         report_error(s, err);
+        return;
     }
+
+    show_status_message(s, COPIED_MESSAGE);
 }
 
-fn open_link(s: &mut cursive::Cursive, link: ResolvedLink) -> anyhow::Result<()> {
-    match link {
-        ResolvedLink::Doc(ty, name) => {
-            let doc = context(s)
-                .sources
-                .find(&name, ty)?
-                .with_context(|| format!("Could not find documentation for item: {}", name))?;
-            open_doc(s, &doc);
-            Ok(())
-        }
-        ResolvedLink::External(link) => webbrowser::open(&link)
-            .map(|_| {})
-            .context("Failed to open web browser"),
+/// Shows the given message in the status line of the documentation pages for two seconds.
+fn show_status_message(s: &mut cursive::Cursive, message: &'static str) {
+    let status = context(s).count.status.clone();
+    status.set_content(message);
+    let cb_sink = s.cb_sink().clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(2));
+        // The status line is shared with the count prefix, so only clear our own message.
+        let _ = cb_sink.send(Box::new(move |_| {
+            if status.get_content().source() == message {
+                status.set_content("");
+            }
+        }));
+    });
+}
+
+/// Lets the user select the syntax highlighting theme and renders the given item again with the
+/// selected theme.  The theme is stored in the session so that it is also used for the next
+/// sessions.
+fn select_theme_dialog(s: &mut cursive::Cursive, doc: rc::Rc<doc::Doc>) {
+    let args = &context(s).args;
+    let current = if args.no_syntax_highlight {
+        THEME_NONE
+    } else {
+        utils::get_theme_name(args)
+    };
+    let mut themes = utils::get_theme_names();
+    themes.push(THEME_NONE.to_owned());
+    let selected = themes.iter().position(|theme| theme == current);
+
+    let mut select_view = SelectView::new();
+    select_view.add_all_str(themes);
+    if let Some(selected) = selected {
+        select_view.set_selection(selected);
     }
+    select_view.set_on_submit(move |s, theme: &String| {
+        s.pop_layer();
+        let result = set_theme(s, theme);
+        rerender_doc(s, &doc);
+        if let Err(err) = result {
+            report_error(s, err);
+        }
+    });
+    let dialog = Dialog::around(select_view.scrollable()).title("Select theme");
+    s.add_layer(dialog);
 }
 
-enum ResolvedLink {
-    Doc(Option<doc::ItemType>, doc::Fqn),
-    External(String),
+fn set_theme(s: &mut cursive::Cursive, theme: &str) -> anyhow::Result<()> {
+    let context = context(s);
+    apply_theme(&mut context.args, theme);
+    context.highlighter = utils::get_highlighter(&context.args)?;
+    context.session.theme = Some(theme.to_owned());
+    context.session.save()
 }
 
-impl From<utils::DocLink> for ResolvedLink {
-    fn from(link: utils::DocLink) -> ResolvedLink {
-        ResolvedLink::Doc(link.ty, link.name)
+/// Sets the syntax highlighting theme in the viewer arguments, or disables syntax highlighting
+/// if the theme is `none`.
+fn apply_theme(args: &mut args::ViewerArgs, theme: &str) {
+    if theme == THEME_NONE {
+        args.no_syntax_highlight = true;
+    } else {
+        args.no_syntax_highlight = false;
+        args.theme = Some(theme.to_owned());
     }
 }
 
-fn resolve_link(
-    doc_name: &doc::Fqn,
-    doc_ty: doc::ItemType,
-    link: &str,
-) -> anyhow::Result<ResolvedLink> {
-    // TODO: support docs.rs and doc.rust-lang.org links
-    match url::Url::parse(link) {
-        Ok(_) => Ok(ResolvedLink::External(link.to_owned())),
-        Err(url::ParseError::RelativeUrlWithoutBase) => resolve_doc_link(doc_name, doc_ty, link)
-            .with_context(|| format!("Could not parse relative link URL: {}", link)),
-        Err(e) => Err(anyhow::Error::new(e).context(format!("Could not parse link URL: {}", link))),
+/// Replaces the documentation page on top of the screen with a newly rendered page for the given
+/// item and keeps the scroll position.
+fn rerender_doc(s: &mut cursive::Cursive, doc: &doc::Doc) {
+    let offset = get_scroll_offset(s, LayerPosition::FromFront(0));
+    s.pop_layer();
+    let mut renderer = context(s).create_renderer(doc);
+    renderer.render_doc(doc).unwrap();
+    let view = renderer.into_view();
+    s.add_fullscreen_layer(view);
+    update_pinned_view(s, LayerPosition::FromFront(0));
+    if let Some(offset) = offset {
+        with_doc_view(s, LayerPosition::FromFront(0), |v| {
+            v.get_inner_mut().set_offset(offset);
+        });
     }
 }
 
-fn resolve_doc_link(
-    doc_name: &doc::Fqn,
-    doc_ty: doc::ItemType,
-    link: &str,
-) -> anyhow::Result<ResolvedLink> {
-    // TODO: use a proper URL parser instead of manually parsing the URL
-    let (link, fragment) = {
-        let parts: Vec<_> = link.splitn(2, '#').collect();
-        if parts.len() > 1 {
-            (parts[0], Some(parts[1]))
-        } else {
-            (parts[0], None)
+/// Loads the documentation for the given item again and replaces the page on top of the screen
+/// with it, see the `R` key.
+fn reload_page(s: &mut cursive::Cursive, doc: &doc::Doc) {
+    match context(s).sources.find(&doc.name, Some(doc.ty)) {
+        Ok(Some(doc)) => rerender_doc(s, &doc),
+        Ok(None) => report_error(
+            s,
+            anyhow::anyhow!("Could not find documentation for {}", &doc.name),
+        ),
+        Err(err) => report_error(s, err),
+    }
+}
+
+/// Reloads the documentation page on top of the screen whenever the documentation files of the
+/// given item change, see the `--watch` option.
+fn watch_doc(s: &mut cursive::Cursive, doc: &doc::Doc) -> anyhow::Result<()> {
+    let watcher = viewer::watch::Watcher::new(doc)?;
+    let cb_sink = s.cb_sink().clone();
+    thread::spawn(move || {
+        while watcher.wait().is_ok() {
+            if cb_sink.send(Box::new(reload_top_page)).is_err() {
+                // the cursive application has been stopped
+                break;
+            }
         }
-    };
-    let parts: Vec<_> = link
-        .split('/')
-        .filter(|s| !s.is_empty())
-        .filter(|s| *s != ".")
-        .collect();
+    });
+    Ok(())
+}
 
-    let (mut ty, mut name) = if doc_ty != doc::ItemType::Module && !parts.is_empty() {
-        (None, doc_name.parent())
-    } else {
-        (Some(doc_ty), Some(doc_name.to_owned()))
-    };
+/// Reloads the documentation page on top of the screen.  If a dialog is open, the page is not
+/// reloaded.
+fn reload_top_page(s: &mut cursive::Cursive) {
+    let mut is_doc_page = false;
+    with_doc_view(s, LayerPosition::FromFront(0), |_| is_doc_page = true);
+    if is_doc_page {
+        s.on_event(event::Event::Char('R'));
+    }
+}
 
-    for part in parts {
-        // We support "..", "index.html", "<module>" and "<type>.<name>.html".
-        match part {
-            ".." => {
-                ty = None;
-                name = name.context("Exceeded root level")?.parent();
-            }
-            "index.html" => {}
-            _ => {
-                if let Some((part_ty, part_name)) = parse_url_part(part, Some(".html")) {
-                    // part == "type.name.html"
-                    ty = Some(part_ty.parse()?);
-                    name = if let Some(name) = name {
-                        Some(name.child(part_name))
-                    } else {
-                        Some(part_name.to_owned().into())
-                    };
-                } else {
-                    // part == "<module>"
-                    ty = Some(doc::ItemType::Module);
-                    name = if let Some(name) = name {
-                        Some(name.child(part))
-                    } else {
-                        Some(part.to_owned().into())
-                    };
+fn open_doc(s: &mut cursive::Cursive, doc: &doc::Doc) {
+    save_scroll_offset(s);
+    let mut renderer = context(s).create_renderer(doc);
+    renderer.render_doc(doc).unwrap();
+    let view = renderer.into_view();
+    s.add_fullscreen_layer(view);
+    update_pinned_view(s, LayerPosition::FromFront(0));
+}
+
+/// Pins the given documentation item to the top of the screen, or unpins it if it is already
+/// pinned.  Only one item can be pinned at a time.
+fn toggle_pinned(s: &mut cursive::Cursive, doc: &doc::Doc) {
+    let context = context(s);
+    let is_pinned = context
+        .pinned
+        .as_ref()
+        .map(|pinned| pinned.name == doc.name && pinned.ty == doc.ty)
+        .unwrap_or(false);
+    context.pinned = if is_pinned { None } else { Some(doc.clone()) };
+
+    for i in 0..s.screen().len() {
+        update_pinned_view(s, LayerPosition::FromFront(i));
+    }
+}
+
+/// Shows the currently pinned documentation item on the documentation page at the given
+/// position, or clears the pinned panel if no item is pinned.
+fn update_pinned_view(s: &mut cursive::Cursive, position: LayerPosition) {
+    let mut view = context(s)
+        .pinned
+        .clone()
+        .map(|doc| create_pinned_view(s, &doc));
+    if let Some(layer) = s.screen_mut().get_mut(position) {
+        let selector = cursive::view::Selector::Name(PINNED_VIEW_NAME);
+        layer.call_on_any(&selector, &mut |v| {
+            if let Some(v) = v.downcast_mut::<NamedView<LinearLayout>>() {
+                let mut layout = v.get_mut();
+                while layout.remove_child(0).is_some() {}
+                if let Some(view) = view.take() {
+                    layout.add_child(view);
                 }
             }
+        });
+    }
+}
+
+/// Renders the synopsis and the description of the given item for the pinned panel.  The panel
+/// uses a third of the screen height.
+fn create_pinned_view(s: &mut cursive::Cursive, doc: &doc::Doc) -> impl cursive::View {
+    let height = cmp::max(s.screen_size().y / 3, 5);
+    let mut renderer = context(s).create_renderer(doc);
+    renderer.options.section_order =
+        vec![args::SectionKind::Synopsis, args::SectionKind::Description];
+    renderer.render_doc(doc).unwrap();
+    renderer.into_pinned_view(height)
+}
+
+fn go_back(s: &mut cursive::Cursive) {
+    if s.screen().len() > 1 {
+        s.pop_layer();
+        restore_scroll_offset(s);
+    }
+}
+
+/// Closes all layers except for the first documentation page and clears the history, see the Z
+/// key.  This is equivalent to pressing Backspace until the first page is reached.
+fn clear_navigation(s: &mut cursive::Cursive) {
+    while s.screen().len() > 1 {
+        s.pop_layer();
+    }
+    restore_scroll_offset(s);
+    context(s).history.clear();
+    show_status_message(s, NAVIGATION_CLEARED_MESSAGE);
+}
+
+/// Stores the scroll offset of the topmost documentation page in the history.
+fn save_scroll_offset(s: &mut cursive::Cursive) {
+    let depth = s.screen().len();
+    for i in 0..depth {
+        if let Some(offset) = get_scroll_offset(s, LayerPosition::FromFront(i)) {
+            context(s).history.push(HistoryEntry {
+                depth: depth - i,
+                offset,
+            });
+            break;
         }
     }
+}
 
-    if let Some(fragment) = fragment {
-        // If the fragment is "<type>.:name>", we add it to the name, otherwise we ignore it
-        // because it just points to some other element on the page.
-        if let Some((fragment_ty, fragment_name)) = parse_url_part(fragment, None) {
-            ty = Some(fragment_ty.parse()?);
-            name = if let Some(name) = name {
-                Some(name.child(fragment_name))
-            } else {
-                Some(fragment_name.to_owned().into())
-            };
+/// Restores the scroll offset of the top layer if it is a documentation page from the history.
+fn restore_scroll_offset(s: &mut cursive::Cursive) {
+    let depth = s.screen().len();
+    let history = &mut context(s).history;
+    while history.last().map(|e| e.depth > depth).unwrap_or(false) {
+        history.pop();
+    }
+    if history.last().map(|e| e.depth == depth).unwrap_or(false) {
+        if let Some(entry) = history.pop() {
+            with_doc_view(s, LayerPosition::FromFront(0), |v| {
+                v.get_inner_mut().set_offset(entry.offset);
+            });
         }
     }
+}
 
-    Ok(ResolvedLink::Doc(
-        ty,
-        name.context("Cannot handle link to root")?,
-    ))
+fn get_scroll_offset(s: &mut cursive::Cursive, position: LayerPosition) -> Option<cursive::Vec2> {
+    let mut offset = None;
+    with_doc_view(s, position, |v| {
+        offset = Some(v.get_inner().content_viewport().top_left());
+    });
+    offset
 }
 
-fn parse_url_part<'s>(s: &'s str, suffix: Option<&str>) -> Option<(&'s str, &'s str)> {
-    let s = if let Some(suffix) = suffix {
-        if s.ends_with(suffix) {
-            &s[..s.len() - suffix.len()]
-        } else {
-            return None;
-        }
-    } else {
-        s
-    };
-    let parts: Vec<_> = s.split('.').collect();
-    if parts.len() == 2 {
-        Some((parts[0], parts[1]))
-    } else {
-        None
+fn with_doc_view<F>(s: &mut cursive::Cursive, position: LayerPosition, mut f: F)
+where
+    F: FnMut(&mut DocView),
+{
+    if let Some(layer) = s.screen_mut().get_mut(position) {
+        let selector = cursive::view::Selector::Name(DOC_VIEW_NAME);
+        layer.call_on_any(&selector, &mut |v| {
+            if let Some(v) = v.downcast_mut::<NamedView<DocView>>() {
+                f(&mut v.get_mut());
+            }
+        });
+    }
+}
+
+fn handle_link(s: &mut cursive::Cursive, doc_name: &doc::Fqn, doc_ty: doc::ItemType, link: &str) {
+    let result = utils::resolve_link(doc_name, doc_ty, link).and_then(|link| open_link(s, link));
+    if let Err(err) = result {
+        report_error(s, err);
+    }
+}
+
+fn open_link(s: &mut cursive::Cursive, link: utils::ResolvedLink) -> anyhow::Result<()> {
+    if let Some(doc) = link.open(&context(s).sources)? {
+        open_doc(s, &doc);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_common_prefix;
+
+    #[test]
+    fn test_get_common_prefix() {
+        assert_eq!(None, get_common_prefix(std::iter::empty()));
+        assert_eq!(
+            Some("Vec::new"),
+            get_common_prefix(vec!["Vec::new"].into_iter())
+        );
+        assert_eq!(
+            Some("Vec::new"),
+            get_common_prefix(vec!["Vec::new", "Vec::new_in"].into_iter())
+        );
+        assert_eq!(
+            Some("Vec::"),
+            get_common_prefix(vec!["Vec::new_in", "Vec::new", "Vec::push"].into_iter())
+        );
+        assert_eq!(Some("ä"), get_common_prefix(vec!["äb", "äc"].into_iter()));
     }
 }
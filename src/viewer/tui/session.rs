@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! State of the tui viewer that is kept across sessions.
+//!
+//! The state is stored in the `tui-session.toml` file in the `rusty-man` subdirectory of the user
+//! data directory.  If it cannot be read, the default state is used.
+
+use std::fs;
+use std::path;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Session {
+    /// The syntax highlighting theme selected with the `T` key, or `none` if syntax highlighting
+    /// is disabled.
+    pub theme: Option<String>,
+}
+
+impl Session {
+    /// Loads the session from the user data directory.
+    pub fn load() -> Session {
+        match get_path().and_then(|path| Session::read(&path)) {
+            Ok(session) => session,
+            Err(err) => {
+                log::warn!("Could not load the tui session: {}", err);
+                Default::default()
+            }
+        }
+    }
+
+    /// Saves the session to the user data directory.
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.write(&get_path()?)
+    }
+
+    fn read(path: &path::Path) -> anyhow::Result<Session> {
+        if path.is_file() {
+            Ok(toml::from_str(&fs::read_to_string(path)?)?)
+        } else {
+            Ok(Default::default())
+        }
+    }
+
+    fn write(&self, path: &path::Path) -> anyhow::Result<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+fn get_path() -> anyhow::Result<path::PathBuf> {
+    let dirs = xdg::BaseDirectories::with_prefix("rusty-man")?;
+    Ok(dirs.place_data_file("tui-session.toml")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+
+    #[test]
+    fn test_read_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tui-session.toml");
+        assert_eq!(Session::default(), Session::read(&path).unwrap());
+
+        let session = Session {
+            theme: Some("InspiredGitHub".to_owned()),
+        };
+        session.write(&path).unwrap();
+        assert_eq!(session, Session::read(&path).unwrap());
+    }
+}
@@ -12,29 +12,50 @@ use crate::viewer::utils;
 pub struct HtmlRenderer {
     render_tree: html2text::RenderTree,
     highlighter: Option<utils::Highlighter>,
+    code_highlight: bool,
+    ascii: bool,
 }
 
 impl HtmlRenderer {
-    pub fn new(html: &str, highlighter: Option<utils::Highlighter>) -> HtmlRenderer {
+    pub fn new(
+        html: &str,
+        highlighter: Option<utils::Highlighter>,
+        code_highlight: bool,
+        ascii: bool,
+    ) -> HtmlRenderer {
         HtmlRenderer {
             render_tree: html2text::parse(html.as_bytes()),
             highlighter,
+            code_highlight,
+            ascii,
         }
     }
 }
 
 impl cursive_markup::Renderer for HtmlRenderer {
     fn render(&self, constraint: cursive::XY<usize>) -> cursive_markup::RenderedDocument {
-        let decorator = utils::RichDecorator::new(show_link, utils::LinkMode::Annotate);
-        let raw_lines = self
+        let decorator =
+            utils::RichDecorator::new(show_link, utils::LinkMode::Annotate, self.code_highlight);
+        let mut raw_lines = self
             .render_tree
             .clone()
             .render(constraint.x, decorator)
             .into_lines();
+        if self.ascii {
+            utils::lines_to_ascii(&mut raw_lines);
+        }
         let highlighted_lines = utils::highlight_html(&raw_lines, self.highlighter.as_ref());
         let mut doc = cursive_markup::RenderedDocument::new(constraint);
         for line in highlighted_lines {
-            doc.push_line(line.into_iter().map(From::from))
+            doc.push_line(line.into_iter().map(|e| match e {
+                utils::HighlightedHtmlElement::QuoteGutter if self.ascii => {
+                    cursive_markup::Element::styled(
+                        utils::to_ascii(utils::QUOTE_GUTTER).into_owned(),
+                        theme::PaletteColor::Tertiary.into(),
+                    )
+                }
+                e => e.into(),
+            }))
         }
         doc
     }
@@ -54,6 +75,10 @@ impl<'s> From<utils::HighlightedHtmlElement<'s>> for cursive_markup::Element {
                     s.style.map_or_else(Default::default, From::from),
                 )
             }
+            utils::HighlightedHtmlElement::QuoteGutter => cursive_markup::Element::styled(
+                utils::QUOTE_GUTTER.to_owned(),
+                theme::PaletteColor::Tertiary.into(),
+            ),
         }
     }
 }
@@ -173,6 +198,48 @@ impl CodeView {
     }
 }
 
+/// Breaks the lines of the given code that are wider than the given width after commas and `+`
+/// and before `where`, so that long signatures can be read without scrolling horizontally.
+/// Continuation lines are indented by four spaces.
+pub fn wrap_code(code: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+    for line in code.lines() {
+        if line.chars().count() <= width {
+            lines.push(line.to_owned());
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        let indent = format!("{}    ", &line[..indent]);
+        let mut current = String::new();
+        for segment in split_code_line(line) {
+            let is_break = segment.starts_with("where")
+                || current.chars().count() + segment.chars().count() > width;
+            if is_break && !current.trim().is_empty() {
+                lines.push(current.trim_end().to_owned());
+                current = indent.clone();
+            }
+            current.push_str(segment);
+        }
+        lines.push(current.trim_end().to_owned());
+    }
+    lines.join("\n")
+}
+
+/// Splits a line of code after `, ` and `+ ` and before ` where`.
+fn split_code_line(line: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (i, _) in line.match_indices(' ') {
+        let (before, after) = (&line[..i], &line[i + 1..]);
+        if before.ends_with(',') || before.ends_with(" +") || after.starts_with("where") {
+            segments.push(&line[start..=i]);
+            start = i + 1;
+        }
+    }
+    segments.push(&line[start..]);
+    segments
+}
+
 impl cursive::View for CodeView {
     fn draw(&self, printer: &cursive::Printer) {
         for (y, line) in self.lines.iter().enumerate() {
@@ -184,3 +251,22 @@ impl cursive::View for CodeView {
         (self.width, self.lines.len()).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::wrap_code;
+
+    #[test]
+    fn test_wrap_code() {
+        let code = "fn short()";
+        assert_eq!(code, wrap_code(code, 20));
+
+        let code = "pub fn with_context<C, F>(self, f: F) -> Result<T, Error> where C: Display + \
+                    Send + Sync + 'static, F: FnOnce() -> C";
+        assert_eq!(
+            "pub fn with_context<C, F>(self, f: F) -> Result<T, Error>\n    \
+             where C: Display + Send + Sync + 'static,\n    F: FnOnce() -> C",
+            wrap_code(code, 60)
+        );
+    }
+}
@@ -1,13 +1,20 @@
 // SPDX-FileCopyrightText: 2020 Robin Krahl <robin.krahl@ireas.org>
 // SPDX-License-Identifier: MIT
 
+use std::borrow;
 use std::cmp;
+use std::collections;
+use std::iter;
+use std::mem;
 
 use anyhow::Context as _;
 use html2text::render::text_renderer;
 
 use crate::args;
 use crate::doc;
+use crate::link;
+use crate::source;
+use crate::viewer::background;
 
 pub type RichString = text_renderer::TaggedString<Vec<text_renderer::RichAnnotation>>;
 pub type RichLine = text_renderer::TaggedLine<Vec<text_renderer::RichAnnotation>>;
@@ -38,12 +45,67 @@ impl Highlighter {
         )
     }
 
+    /// Highlights the lines of a code block, preserving the parser state between the lines.
+    ///
+    /// Each line is given as a list of parts, for example the strings with different annotations
+    /// in a line of rendered HTML.  The parts are highlighted as one line, and the highlighted
+    /// segments are split at the part boundaries and returned as slices of the parts.
+    pub fn highlight_with_context<'s>(
+        &self,
+        lines: &[Vec<&'s str>],
+    ) -> Vec<Vec<(syntect::highlighting::Style, &'s str)>> {
+        let mut highlight_lines = self.get_highlight_lines("rs");
+        lines
+            .iter()
+            .map(|parts| {
+                // syntect expects the lines to end with a newline
+                let mut line = parts.concat();
+                line.push('\n');
+                let segments = highlight_lines.highlight(&line, &self.syntax_set);
+                split_segments(&segments, parts)
+            })
+            .collect()
+    }
+
     pub fn get_highlight_lines(&self, syntax: &str) -> syntect::easy::HighlightLines<'_> {
         let syntax = self.syntax_set.find_syntax_by_extension(syntax).unwrap();
         syntect::easy::HighlightLines::new(syntax, &self.theme)
     }
 }
 
+/// Splits the highlighted segments of the concatenation of the given parts at the part boundaries
+/// and maps them to slices of the parts.  Segments after the end of the last part, e. g. the
+/// trailing newline, are dropped.
+fn split_segments<'s, S>(segments: &[(S, &str)], parts: &[&'s str]) -> Vec<(S, &'s str)>
+where
+    S: Copy,
+{
+    let mut result = Vec::new();
+    let mut parts = parts.iter();
+    let mut part = parts.next().copied();
+    for (style, segment) in segments {
+        let mut len = segment.len();
+        while len > 0 {
+            let current = if let Some(current) = part {
+                current
+            } else {
+                return result;
+            };
+            let n = cmp::min(len, current.len());
+            if n > 0 {
+                result.push((*style, &current[..n]));
+            }
+            len -= n;
+            part = if n == current.len() {
+                parts.next().copied()
+            } else {
+                Some(&current[n..])
+            };
+        }
+    }
+    result
+}
+
 /// An iterator over lines highlighted using syntect.
 pub struct HighlightedLines<'s, 'ss, 't, I: Iterator<Item = &'s str>> {
     iter: I,
@@ -75,9 +137,115 @@ impl<'s, 'ss, 't, I: Iterator<Item = &'s str>> Iterator for HighlightedLines<'s,
     }
 }
 
+/// The gutter that is shown instead of the `> ` prefix of blockquote lines, see
+/// `HighlightedHtmlElement::QuoteGutter`.
+pub const QUOTE_GUTTER: &str = "│ ";
+
+/// The ASCII replacements for the non-ASCII characters that are used by rusty-man and html2text
+/// to decorate the output, see the `--ascii` option.  The replacements have the same width as the
+/// original characters so that they can be applied after the text has been wrapped.
+const DECORATION_REPLACEMENTS: &[(char, &str)] = &[
+    ('│', "|"),
+    ('─', "-"),
+    ('┬', "+"),
+    ('┴', "+"),
+    ('┼', "+"),
+    ('⚠', "!"),
+    ('ℹ', "i"),
+    ('⛔', "x"),
+];
+
+/// The ASCII replacements for common non-ASCII characters in the prose of the documentation, see
+/// the `--ascii` option.  As the ellipsis is wider than the original character, these
+/// replacements have to be applied before the text is wrapped.
+const PROSE_REPLACEMENTS: &[(char, &str)] =
+    &[('\u{a0}', " "), ('—', "-"), ('–', "-"), ('…', "...")];
+
+/// Replaces the characters listed in `DECORATION_REPLACEMENTS` and `PROSE_REPLACEMENTS` in the
+/// given string.  Other non-ASCII characters are not changed.
+pub fn to_ascii(s: &str) -> std::borrow::Cow<'_, str> {
+    replace_chars(s, &[DECORATION_REPLACEMENTS, PROSE_REPLACEMENTS])
+}
+
+/// Replaces the characters listed in `DECORATION_REPLACEMENTS` in the given string.
+pub fn decorations_to_ascii(s: &str) -> std::borrow::Cow<'_, str> {
+    replace_chars(s, &[DECORATION_REPLACEMENTS])
+}
+
+fn replace_chars<'s>(s: &'s str, replacements: &[&[(char, &'static str)]]) -> borrow::Cow<'s, str> {
+    let get_replacement = |c: char| {
+        replacements
+            .iter()
+            .flat_map(|r| r.iter())
+            .find(|(original, _)| *original == c)
+            .map(|(_, replacement)| *replacement)
+    };
+    if s.is_ascii() || !s.chars().any(|c| get_replacement(c).is_some()) {
+        return s.into();
+    }
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match get_replacement(c) {
+            Some(replacement) => result.push_str(replacement),
+            None => result.push(c),
+        }
+    }
+    result.into()
+}
+
+/// Applies `decorations_to_ascii` to the text of the given lines rendered by html2text.  Code is
+/// not changed.
+pub fn lines_to_ascii(lines: &mut [RichLine]) {
+    for line in lines {
+        let elements: Vec<_> = line.drain_all().collect();
+        for element in elements {
+            match element {
+                text_renderer::TaggedLineElement::Str(mut ts) => {
+                    if !ts.tag.iter().any(is_code_annotation) {
+                        ts.s = decorations_to_ascii(&ts.s).into_owned();
+                    }
+                    line.push(text_renderer::TaggedLineElement::Str(ts));
+                }
+                element => line.push(element),
+            }
+        }
+    }
+}
+
+fn is_code_annotation(annotation: &text_renderer::RichAnnotation) -> bool {
+    matches!(
+        annotation,
+        text_renderer::RichAnnotation::Code | text_renderer::RichAnnotation::Preformat(_)
+    )
+}
+
+/// Applies `to_ascii` to the text nodes of the given HTML that are not part of a code element.
+fn html_to_ascii(html: &str) -> String {
+    use kuchiki::traits::TendrilSink as _;
+
+    let document = kuchiki::parse_html().one(html);
+    for node in document.descendants() {
+        if let Some(text) = node.as_text() {
+            let is_code = node.ancestors().any(|node| {
+                node.as_element()
+                    .map(|e| matches!(e.name.local.as_ref(), "code" | "pre"))
+                    .unwrap_or(false)
+            });
+            if !is_code {
+                let replaced = to_ascii(&text.borrow()).into_owned();
+                *text.borrow_mut() = replaced;
+            }
+        }
+    }
+    document.to_string()
+}
+
 pub enum HighlightedHtmlElement<'s> {
     RichString(&'s RichString),
     StyledString(text_style::StyledStr<'s>),
+    /// A `> ` prefix that html2text adds to the lines of a blockquote.  The viewers should render
+    /// it as a styled `QUOTE_GUTTER`.
+    QuoteGutter,
 }
 
 impl<'s> From<&'s RichString> for HighlightedHtmlElement<'s> {
@@ -92,77 +260,107 @@ impl<'s> From<text_style::StyledStr<'s>> for HighlightedHtmlElement<'s> {
     }
 }
 
+/// An iterator over lines of rendered HTML where the preformatted text is highlighted using
+/// syntect.
+///
+/// Consecutive lines that only contain preformatted text are treated as one code block and
+/// highlighted with `Highlighter::highlight_with_context` so that the parser state is preserved
+/// between the lines.
 pub struct HighlightedHtml<'h, 's, I: Iterator<Item = &'s RichLine>> {
-    iter: I,
+    iter: iter::Peekable<I>,
     highlighter: Option<&'h Highlighter>,
-    highlight_lines: Option<syntect::easy::HighlightLines<'h>>,
+    buffer: collections::VecDeque<Vec<HighlightedHtmlElement<'s>>>,
 }
 
 impl<'h, 's, I: Iterator<Item = &'s RichLine>> HighlightedHtml<'h, 's, I> {
     fn new(iter: I, highlighter: Option<&'h Highlighter>) -> HighlightedHtml<'h, 's, I> {
         HighlightedHtml {
-            iter,
+            iter: iter.peekable(),
             highlighter,
-            highlight_lines: None,
+            buffer: Default::default(),
         }
     }
 
-    fn get_highlighted_line(
-        &mut self,
-        highlighter: &'h Highlighter,
-        line: &'s RichLine,
-    ) -> Vec<HighlightedHtmlElement<'s>> {
-        let mut elements = Vec::new();
-
-        for ts in line.iter().filter_map(|tle| match tle {
-            text_renderer::TaggedLineElement::Str(ts) => Some(ts),
-            _ => None,
-        }) {
-            if is_pre(ts) {
-                let h = self
-                    .highlight_lines
-                    .get_or_insert_with(|| highlighter.get_highlight_lines("rs"));
-
-                // TODO: syntect expects a newline
-
-                let strings = h.highlight(&ts.s, &highlighter.syntax_set);
-                elements.extend(
-                    strings
-                        .iter()
-                        .map(text_style::StyledStr::from)
-                        .map(HighlightedHtmlElement::from),
-                );
-            } else {
-                self.highlight_lines = None;
-                elements.push(ts.into());
+    fn highlight_block(&mut self, highlighter: &'h Highlighter, first_line: &'s RichLine) {
+        let mut lines = vec![get_line_parts(first_line)];
+        while let Some(line) = self.iter.peek() {
+            if !is_pre_line(line) {
+                break;
             }
+            lines.push(get_line_parts(line));
+            self.iter.next();
         }
+        let highlighted = highlighter.highlight_with_context(&lines);
+        self.buffer.extend(highlighted.into_iter().map(|line| {
+            line.iter()
+                .map(text_style::StyledStr::from)
+                .map(HighlightedHtmlElement::from)
+                .collect()
+        }));
+    }
+}
 
-        elements
+/// Highlights the preformatted strings in a line that also contains other text.  As these
+/// strings are not part of a code block, each string is highlighted separately.
+fn highlight_line<'s>(
+    highlighter: &Highlighter,
+    line: &'s RichLine,
+) -> Vec<HighlightedHtmlElement<'s>> {
+    let mut elements = Vec::new();
+    for ts in get_line_strings(line) {
+        if is_pre(ts) {
+            let highlighted = highlighter.highlight_with_context(&[vec![ts.s.as_str()]]);
+            elements.extend(
+                highlighted
+                    .iter()
+                    .flatten()
+                    .map(text_style::StyledStr::from)
+                    .map(HighlightedHtmlElement::from),
+            );
+        } else {
+            elements.push(ts.into());
+        }
     }
+    elements
 }
 
 impl<'h, 's, I: Iterator<Item = &'s RichLine>> Iterator for HighlightedHtml<'h, 's, I> {
     type Item = Vec<HighlightedHtmlElement<'s>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(line) = self.iter.next() {
-            let elements = if let Some(highlighter) = self.highlighter {
-                self.get_highlighted_line(highlighter, line)
+        if let Some(elements) = self.buffer.pop_front() {
+            return Some(elements);
+        }
+
+        let line = self.iter.next()?;
+        let elements = if let Some(highlighter) = self.highlighter {
+            if is_pre_line(line) {
+                self.highlight_block(highlighter, line);
+                self.buffer.pop_front().unwrap_or_default()
             } else {
-                line.iter()
-                    .filter_map(|tle| match tle {
-                        text_renderer::TaggedLineElement::Str(ts) => Some(ts),
-                        _ => None,
-                    })
-                    .map(From::from)
-                    .collect()
-            };
-            Some(elements)
+                highlight_line(highlighter, line)
+            }
         } else {
-            None
+            get_line_strings(line).map(From::from).collect()
+        };
+        Some(mark_quote_prefixes(elements))
+    }
+}
+
+/// Replaces the blockquote prefixes at the start of the given line with
+/// `HighlightedHtmlElement::QuoteGutter`.
+fn mark_quote_prefixes(
+    mut elements: Vec<HighlightedHtmlElement<'_>>,
+) -> Vec<HighlightedHtmlElement<'_>> {
+    for element in &mut elements {
+        match element {
+            HighlightedHtmlElement::RichString(ts) if ts.s == "> " => {
+                *element = HighlightedHtmlElement::QuoteGutter;
+            }
+            _ => break,
         }
     }
+    elements
 }
 
 pub fn highlight_html<'h, 's, I, Iter>(
@@ -182,17 +380,277 @@ fn is_pre(ts: &RichString) -> bool {
         .any(|tag| matches!(tag, text_renderer::RichAnnotation::Preformat(_)))
 }
 
+fn get_line_strings(line: &RichLine) -> impl Iterator<Item = &RichString> {
+    line.iter().filter_map(|tle| match tle {
+        text_renderer::TaggedLineElement::Str(ts) => Some(ts),
+        _ => None,
+    })
+}
+
+fn get_line_parts(line: &RichLine) -> Vec<&str> {
+    get_line_strings(line).map(|ts| ts.s.as_str()).collect()
+}
+
+/// Whether the given line is part of a code block, i. e. whether it only contains preformatted
+/// text.
+fn is_pre_line(line: &RichLine) -> bool {
+    let mut strings = get_line_strings(line).peekable();
+    strings.peek().is_some() && strings.all(is_pre)
+}
+
+/// Whether an item with a platform restriction is available on the current platform.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlatformStatus {
+    /// The item is available on the current platform.
+    Supported,
+    /// The restriction does not refer to a known platform, e. g. for crate features.
+    Unknown,
+    /// The item is not available on the current platform.
+    Unsupported,
+    /// The item is not available on the current platform and `--platform-warnings` is set.
+    Warning,
+}
+
+/// The operating systems and families that rustdoc mentions in portability notices.
+const PLATFORMS: &[&str] = &[
+    "android",
+    "dragonfly",
+    "emscripten",
+    "freebsd",
+    "fuchsia",
+    "haiku",
+    "hermit",
+    "illumos",
+    "ios",
+    "linux",
+    "macos",
+    "netbsd",
+    "openbsd",
+    "redox",
+    "solaris",
+    "unix",
+    "vxworks",
+    "wasi",
+    "wasm",
+    "windows",
+];
+
+impl PlatformStatus {
+    pub fn new(platform: &str, warnings: bool) -> PlatformStatus {
+        let platform = platform.to_lowercase();
+        if platform.contains(std::env::consts::OS) || platform.contains(std::env::consts::FAMILY) {
+            PlatformStatus::Supported
+        } else if !PLATFORMS.iter().any(|p| platform.contains(p)) {
+            PlatformStatus::Unknown
+        } else if warnings {
+            PlatformStatus::Warning
+        } else {
+            PlatformStatus::Unsupported
+        }
+    }
+
+    /// Returns the notice for the given platform restriction.
+    pub fn message(self, platform: &str) -> String {
+        match self {
+            PlatformStatus::Warning => format!(
+                "Warning: This item is not available on {}.  Available on {} only.",
+                std::env::consts::OS,
+                platform
+            ),
+            _ => format!("Available on {} only.", platform),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct DocLink {
     pub name: doc::Fqn,
     pub ty: Option<doc::ItemType>,
 }
 
+/// The target of a link in the documentation, see `resolve_link`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResolvedLink {
+    Doc(Option<doc::ItemType>, doc::Fqn),
+    External(String),
+}
+
+impl ResolvedLink {
+    /// Opens the link target:  Documentation items are looked up in the given sources and
+    /// returned, external links are opened in the web browser.
+    pub fn open(&self, sources: &source::Sources) -> anyhow::Result<Option<doc::Doc>> {
+        match self {
+            ResolvedLink::Doc(ty, name) => sources
+                .find(name, *ty)?
+                .with_context(|| format!("Could not find documentation for item: {}", name))
+                .map(Some),
+            ResolvedLink::External(link) => webbrowser::open(link)
+                .map(|_| None)
+                .context("Failed to open web browser"),
+        }
+    }
+}
+
+impl From<DocLink> for ResolvedLink {
+    fn from(link: DocLink) -> ResolvedLink {
+        ResolvedLink::Doc(link.ty, link.name)
+    }
+}
+
+impl std::fmt::Display for ResolvedLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolvedLink::Doc(_, name) => name.fmt(f),
+            ResolvedLink::External(link) => link.fmt(f),
+        }
+    }
+}
+
+/// Returns the link to the replacement of the given deprecated member, see
+/// `doc::DeprecatedInfo::replacement`.  If the replacement does not start with the crate name, a
+/// single name is resolved relative to the parent of the member, e. g. a sibling method, and a
+/// path relative to the crate.
+pub fn get_replacement_link(member: &doc::Fqn, replacement: &str) -> DocLink {
+    let krate = member.krate();
+    let name: doc::Fqn = if replacement == krate || replacement.starts_with(&format!("{}::", krate))
+    {
+        replacement.to_owned().into()
+    } else if !replacement.contains("::") {
+        member
+            .parent()
+            .map(|parent| parent.child(replacement))
+            .unwrap_or_else(|| replacement.to_owned().into())
+    } else {
+        format!("{}::{}", krate, replacement).into()
+    };
+    DocLink { name, ty: None }
+}
+
+/// Resolves a link on the documentation page for the given item.  Absolute URLs are external
+/// links, relative URLs point to other documentation items.
+pub fn resolve_link(
+    doc_name: &doc::Fqn,
+    doc_ty: doc::ItemType,
+    link: &str,
+) -> anyhow::Result<ResolvedLink> {
+    // TODO: support docs.rs and doc.rust-lang.org links
+    match url::Url::parse(link) {
+        Ok(_) => Ok(ResolvedLink::External(link.to_owned())),
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            let (ty, name) = link::resolve_doc_link(doc_name, doc_ty, link)
+                .with_context(|| format!("Could not parse relative link URL: {}", link))?;
+            Ok(ResolvedLink::Doc(ty, name))
+        }
+        Err(e) => Err(anyhow::Error::new(e).context(format!("Could not parse link URL: {}", link))),
+    }
+}
+
+/// The options that control which parts of the documentation are rendered and how, see
+/// `ManRenderer::options`.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Whether the notable traits of the return type of a function should be displayed.
+    pub notable_traits: bool,
+    /// Whether a prominent warning should be displayed if an item is not available on the current
+    /// platform.
+    pub platform_warnings: bool,
+    /// Whether the other items in the parent module should be listed at the end of the page.
+    pub show_siblings: bool,
+    /// The order of the trait implementations, see `group_impls`.
+    pub impl_order: args::ImplOrder,
+    /// The order of the members within their groups, see `Doc::sort_members`.
+    pub sort_order: args::SortOrder,
+    /// The level of detail of the documentation page, see the `--depth` option.  `None` means that
+    /// all details are shown.
+    pub depth: Option<u8>,
+    /// The maximum number of lines of a definition, see the `--max-definition-lines` option.
+    /// `None` means that definitions are not truncated.
+    pub max_definition_lines: Option<usize>,
+    /// Whether the members of modules should be listed in compact rows, see the `--compact`
+    /// option.  `None` means that the compact layout is used for modules with more than
+    /// `COMPACT_THRESHOLD` members.
+    pub compact: Option<bool>,
+    /// Whether members that are not public should be hidden, see the `--public-only` option.
+    pub public_only: bool,
+    /// Whether member groups without a documented member should be shown, see the
+    /// `--show-empty-groups` option and `MemberGroup::has_documented_members`.
+    pub show_empty_groups: bool,
+    /// Whether the names of the members should be prefixed with their type, see the
+    /// `--type-prefix` option and `get_type_prefix`.
+    pub type_prefix: bool,
+    /// Whether long definitions should be broken into multiple lines, see the
+    /// `--no-format-signatures` option and `doc::Code::formatted`.
+    pub format_signatures: bool,
+    /// Whether comment-only lines should be removed from examples, see the `--compact-examples`
+    /// option and `doc::Code::compacted`.
+    pub compact_examples: bool,
+    /// Whether a separator line should be printed before the top-level sections, see the
+    /// `--no-separators` option.
+    pub separators: bool,
+    /// Whether the output should only use ASCII characters for decorations, see the `--ascii`
+    /// option and `to_ascii`.
+    pub ascii: bool,
+    /// The order of the sections of the documentation page, see `ManRenderer::render_doc`.
+    pub section_order: Vec<args::SectionKind>,
+}
+
+impl RenderOptions {
+    pub fn new(args: &args::ViewerArgs) -> RenderOptions {
+        RenderOptions {
+            notable_traits: args.notable_traits,
+            platform_warnings: args.platform_warnings,
+            show_siblings: args.show_siblings,
+            impl_order: get_impl_order(args),
+            sort_order: get_sort_order(args),
+            depth: args.depth,
+            max_definition_lines: get_max_definition_lines(args),
+            compact: get_compact(args),
+            compact_examples: args.compact_examples,
+            public_only: args.public_only,
+            show_empty_groups: args.show_empty_groups,
+            type_prefix: args.type_prefix,
+            format_signatures: !args.no_format_signatures,
+            separators: !args.no_separators,
+            ascii: args.ascii,
+            section_order: get_section_order(args),
+        }
+    }
+
+    /// Replaces the non-ASCII decoration characters in the given decoration or prose if the
+    /// `ascii` option is set, see `to_ascii`.  This must not be used for code.
+    pub fn prepare_text<'s>(&self, s: &'s str) -> borrow::Cow<'s, str> {
+        if self.ascii {
+            to_ascii(s)
+        } else {
+            s.into()
+        }
+    }
+
+    /// Replaces the non-ASCII characters in the prose of the given HTML if the `ascii` option is
+    /// set, see `to_ascii`.  This has to be called before the text is wrapped.  The decorations
+    /// added while rendering the HTML have to be replaced with `lines_to_ascii`.
+    pub fn prepare_html<'s>(&self, html: &'s str) -> borrow::Cow<'s, str> {
+        if self.ascii && (!html.is_ascii() || html.contains('&')) {
+            html_to_ascii(html).into()
+        } else {
+            html.into()
+        }
+    }
+}
+
 /// A trait for viewer implementations that display the documentation in a man-like style.
 pub trait ManRenderer {
     type Error: std::error::Error + Sized + Send;
 
-    fn print_title(&mut self, left: &str, center: &str, right: &str) -> Result<(), Self::Error>;
+    /// Prints the title line.  `version` is the version of the crate `left`, if it is known, see
+    /// `get_title_right`.
+    fn print_title(
+        &mut self,
+        left: &str,
+        center: &str,
+        right: &str,
+        version: Option<&str>,
+    ) -> Result<(), Self::Error>;
     fn print_heading(
         &mut self,
         indent: u8,
@@ -203,56 +661,149 @@ pub trait ManRenderer {
     fn print_text(&mut self, indent: u8, text: &doc::Text) -> Result<(), Self::Error>;
     fn println(&mut self) -> Result<(), Self::Error>;
 
-    fn render_doc(&mut self, doc: &doc::Doc) -> Result<(), Self::Error> {
-        print_title(self, doc)?;
+    /// The options that control which parts of the documentation are rendered, see
+    /// `RenderOptions`.
+    fn options(&self) -> &RenderOptions;
 
-        if let Some(text) = &doc.definition {
-            print_heading(self, 1, "Synopsis", None)?;
-            self.print_code(6, text)?;
-            self.println()?;
-        }
+    /// Writes buffered output, if any.  This is called after the documentation has been rendered.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 
-        if let Some(text) = &doc.description {
-            print_heading(self, 1, "Description", None)?;
-            self.print_text(6, text)?;
-            self.println()?;
-        }
+    /// Prints the name and a short description of the item after the title.  Per default, this
+    /// does nothing as the name is already part of the title.
+    fn print_name(&mut self, _doc: &doc::Doc) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Prints the platform restriction of the item.  Per default, this prints a section with the
+    /// message for the status.
+    fn print_platform(
+        &mut self,
+        platform: &str,
+        status: PlatformStatus,
+    ) -> Result<(), Self::Error> {
+        print_heading(self, 1, "Platform", None)?;
+        self.print_code(6, &doc::Code::new(status.message(platform)))?;
+        self.println()
+    }
 
-        for (ty, groups) in &doc.groups {
-            print_heading(self, 1, ty.group_name(), None)?;
+    /// Prints the crate features that are required for the item.  Per default, this prints a
+    /// line like `[requires: std, alloc]`.
+    fn print_required_features(&mut self, doc: &doc::Doc) -> Result<(), Self::Error> {
+        let features = format!("[requires: {}]", doc.required_features.join(", "));
+        self.print_code(6, &doc::Code::new(features))
+    }
 
-            for group in groups {
-                if let Some(title) = &group.title {
-                    print_heading(self, 2, title, None)?;
-                }
+    /// Prints a notice for a member, for example that it is deprecated or only available on some
+    /// platforms.  Per default, this prints the notice with a warning sign.
+    fn print_notice(&mut self, indent: u8, notice: &str) -> Result<(), Self::Error> {
+        self.print_code(indent, &doc::Code::new(format!("⚠ {}", notice)))
+    }
 
-                for member in &group.members {
-                    let link = if doc::ItemType::Module == doc.ty {
-                        Some(DocLink {
-                            name: member.name.clone(),
-                            ty: Some(*ty),
-                        })
-                    } else {
-                        None
-                    };
-                    // TODO: use something link strip_prefix instead of last()
-                    print_heading(self, 3, member.name.last(), link)?;
-                    if let Some(definition) = &member.definition {
-                        self.print_code(12, definition)?;
-                    }
-                    if member.definition.is_some() && member.description.is_some() {
-                        self.println()?;
+    /// Prints the replacement of a deprecated member, see `doc::DeprecatedInfo::replacement`.  Per
+    /// default, this prints a reference to the replacement.
+    fn print_replacement(
+        &mut self,
+        indent: u8,
+        _member: &doc::Fqn,
+        replacement: &str,
+    ) -> Result<(), Self::Error> {
+        self.print_code(indent, &doc::Code::new(format!("See: `{}`", replacement)))
+    }
+
+    /// Whether the items that are linked from the description should be listed in a “See also”
+    /// section.  Viewers with interactive links can disable this section.
+    fn show_see_also(&self) -> bool {
+        true
+    }
+
+    /// The line length that is used to truncate the descriptions in the compact layout.  Viewers
+    /// without a fixed line length cannot use the compact layout.
+    fn line_length(&self) -> Option<usize> {
+        None
+    }
+
+    /// Prints a row of the compact module layout:  the name of the member, followed by `padding`
+    /// spaces and the description of the member.
+    fn print_member_row(
+        &mut self,
+        indent: u8,
+        name: &str,
+        padding: usize,
+        description: &str,
+        link: Option<DocLink>,
+    ) -> Result<(), Self::Error> {
+        let row = format!("{}{}{}", name, " ".repeat(padding), description);
+        self.print_heading(indent, row.trim_end(), link)
+    }
+
+    /// Prints a line that separates two top-level sections.  This is called between the sections
+    /// if the `separators` option is set.  Per default, this does nothing.
+    fn print_separator(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn render_doc(&mut self, doc: &doc::Doc) -> Result<(), Self::Error> {
+        let public_doc;
+        let sort = self.options().sort_order == args::SortOrder::Alpha;
+        let doc = if self.options().public_only || sort {
+            let mut d = doc.clone();
+            if self.options().public_only {
+                d.retain_public_members();
+            }
+            if sort {
+                d.sort_members();
+            }
+            public_doc = d;
+            &public_doc
+        } else {
+            doc
+        };
+        let order = self.options().section_order.to_vec();
+        let depth = self.options().depth.unwrap_or(u8::MAX);
+        let mut has_sections = false;
+        for section in order {
+            if depth == 0 && section != args::SectionKind::Title {
+                continue;
+            }
+            match section {
+                args::SectionKind::Title => print_title(self, doc)?,
+                args::SectionKind::Platform => {
+                    if let Some(platform) = &doc.platform {
+                        let status =
+                            PlatformStatus::new(platform, self.options().platform_warnings);
+                        start_section(self, &mut has_sections)?;
+                        self.print_platform(platform, status)?;
                     }
-                    if let Some(description) = &member.description {
-                        self.print_text(12, description)?;
+                }
+                args::SectionKind::Synopsis => print_synopsis(self, doc, &mut has_sections)?,
+                args::SectionKind::Description => print_description(self, doc, &mut has_sections)?,
+                args::SectionKind::Members if depth >= 2 => {
+                    print_members(self, doc, depth >= 3, &mut has_sections)?
+                }
+                args::SectionKind::Members => {}
+                args::SectionKind::Examples => match doc.find_examples() {
+                    Ok(examples) if !examples.is_empty() => {
+                        print_examples(self, &examples, &mut has_sections)?
                     }
-                    if member.definition.is_some() || member.description.is_some() {
-                        self.println()?;
+                    Ok(_) => {}
+                    Err(err) => log::warn!("Could not find examples for {}: {}", doc.name, err),
+                },
+                args::SectionKind::SeeAlso if self.show_see_also() => match doc.find_see_also() {
+                    Ok(items) if !items.is_empty() => {
+                        print_see_also(self, &items, &mut has_sections)?
                     }
-                }
+                    Ok(_) => {}
+                    Err(err) => log::warn!("Could not find links for {}: {}", doc.name, err),
+                },
+                args::SectionKind::SeeAlso => {}
+                args::SectionKind::Notes => print_notes(self, doc, &mut has_sections)?,
             }
         }
-
+        if self.options().show_siblings && depth > 0 {
+            print_siblings(self, doc, &mut has_sections)?;
+        }
         Ok(())
     }
 
@@ -262,28 +813,556 @@ pub trait ManRenderer {
         examples: &[doc::Example],
     ) -> Result<(), Self::Error> {
         print_title(self, doc)?;
-        print_heading(self, 1, "Examples", None)?;
+        print_examples(self, examples, &mut false)
+    }
+}
+
+fn print_synopsis<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    doc: &doc::Doc,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    if doc.definition.is_none() && doc.required_features.is_empty() {
+        return Ok(());
+    }
+    print_section_heading(viewer, "Synopsis", has_sections)?;
+    if let Some(text) = &doc.definition {
+        print_definition(viewer, 6, text)?;
+        viewer.println()?;
+        if viewer.options().notable_traits {
+            if let Some(notable_traits) = &doc.notable_traits {
+                viewer.print_code(8, notable_traits)?;
+                viewer.println()?;
+            }
+        }
+    }
+    if !doc.required_features.is_empty() {
+        viewer.print_required_features(doc)?;
+        viewer.println()?;
+    }
+    Ok(())
+}
+
+/// Returns the heading for a member of the given group.  If other members in the groups have the
+/// same name, for example methods from different impl blocks, the title of the group is appended,
+/// e. g. `foo (from Deref<Target = Bar>)`.
+fn get_member_heading(
+    groups: &[doc::MemberGroup],
+    group: &doc::MemberGroup,
+    member: &doc::Doc,
+) -> String {
+    // TODO: use something link strip_prefix instead of last()
+    let name = member.name.last();
+    let count = groups
+        .iter()
+        .flat_map(|group| &group.members)
+        .filter(|m| m.name.last() == name)
+        .count();
+    match &group.title {
+        Some(title) if count > 1 => {
+            let title = title.strip_prefix("Methods from ").unwrap_or(title);
+            format!("{} (from {})", name, title)
+        }
+        _ => name.to_owned(),
+    }
+}
+
+/// Prints the given definition, formatted if enabled and truncated to the maximum number of lines
+/// set for the viewer.
+fn print_definition<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    indent: u8,
+    definition: &doc::Code,
+) -> Result<(), M::Error> {
+    let formatted;
+    let definition = if viewer.options().format_signatures {
+        let width = viewer
+            .line_length()
+            .map(|line_length| line_length.saturating_sub(indent.into()));
+        formatted = definition.formatted(width);
+        &formatted
+    } else {
+        definition
+    };
+    if let Some(max_lines) = viewer.options().max_definition_lines {
+        if let Some((truncated, lines)) = truncate_definition(definition, max_lines) {
+            viewer.print_code(indent, &truncated)?;
+            let note = format!(
+                "({} of {} lines shown, use --no-max-definition-lines to see all)",
+                max_lines, lines
+            );
+            return viewer.print_code(indent, &doc::Code::new(note));
+        }
+    }
+    viewer.print_code(indent, definition)
+}
+
+/// Truncates the definition to the given number of lines and appends `…`.  Returns the truncated
+/// definition and the number of lines of the full definition, or `None` if the definition does
+/// not have more than `max_lines` lines.
+fn truncate_definition(definition: &doc::Code, max_lines: usize) -> Option<(doc::Code, usize)> {
+    let lines: Vec<_> = definition.lines().collect();
+    if lines.len() <= max_lines {
+        return None;
+    }
+    let mut truncated = lines[..max_lines].join("\n");
+    truncated.push('…');
+    Some((doc::Code::new(truncated), lines.len()))
+}
+
+/// Returns the URL of the feature list of the crate of the given item on docs.rs.
+pub fn get_features_url(doc: &doc::Doc) -> String {
+    format!(
+        "https://docs.rs/crate/{}/{}/features",
+        doc.name.krate().replace('_', "-"),
+        doc.crate_version.as_deref().unwrap_or("latest")
+    )
+}
+
+fn print_description<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    doc: &doc::Doc,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    if let Some(text) = &doc.description {
+        print_section_heading(viewer, "Description", has_sections)?;
+        viewer.print_text(6, text)?;
+        viewer.println()?;
+    }
+    Ok(())
+}
+
+/// Prints the notices for the item, for example that it is deprecated.
+fn print_notes<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    doc: &doc::Doc,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    if let Some(deprecated) = &doc.deprecated {
+        print_section_heading(viewer, "Notes", has_sections)?;
+        viewer.print_notice(6, &deprecated.to_string())?;
+        if let Some(replacement) = &deprecated.replacement {
+            viewer.print_replacement(8, &doc.name, replacement)?;
+        }
+        viewer.println()?;
+    }
+    Ok(())
+}
+
+/// Prints the members of the given item.  If `bodies` is false, only the names of the members are
+/// printed.
+fn print_members<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    doc: &doc::Doc,
+    bodies: bool,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    let line_length = viewer
+        .line_length()
+        .filter(|_| bodies && use_compact(viewer, doc));
+    for (ty, groups) in &doc.groups {
+        // The members of modules are links to their own pages, so we list them even if they are
+        // not documented.
+        if !viewer.options().show_empty_groups
+            && doc.ty != doc::ItemType::Module
+            && !groups.iter().any(doc::MemberGroup::has_documented_members)
+        {
+            continue;
+        }
+        print_section_heading(viewer, ty.group_name(), has_sections)?;
+
+        for group in groups {
+            if let Some(title) = &group.title {
+                print_heading(viewer, 2, title, None)?;
+            }
+            if let Some(description) = group.description.as_ref().filter(|_| bodies) {
+                viewer.print_text(6, description)?;
+                viewer.println()?;
+            }
 
-        let n = examples.len();
-        for (i, example) in examples.iter().enumerate() {
-            if n > 1 {
-                print_heading(self, 2, &format!("Example {} of {}", i + 1, n), None)?;
+            if let Some(line_length) = line_length {
+                print_member_rows(viewer, *ty, group, line_length)?;
+                continue;
             }
-            if let Some(description) = &example.description {
-                self.print_text(6, description)?;
-                self.println()?;
+
+            if *ty == doc::ItemType::Impl && viewer.options().impl_order == args::ImplOrder::Grouped
+            {
+                for subgroup in group_impls(&group.members) {
+                    print_subgroup(viewer, &subgroup, bodies)?;
+                }
+                continue;
+            }
+
+            for member in &group.members {
+                let link = if doc::ItemType::Module == doc.ty {
+                    Some(DocLink {
+                        name: member.name.clone(),
+                        ty: Some(*ty),
+                    })
+                } else {
+                    None
+                };
+                let mut heading = get_member_heading(groups, group, member);
+                if viewer.options().type_prefix && *ty != doc::ItemType::Impl {
+                    heading.insert_str(0, &get_type_prefix(member.ty));
+                }
+                print_heading(viewer, 3, &heading, link)?;
+                if !bodies {
+                    continue;
+                }
+                if let Some(definition) = &member.definition {
+                    print_definition(viewer, 12, definition)?;
+                    if viewer.options().notable_traits {
+                        if let Some(notable_traits) = &member.notable_traits {
+                            viewer.print_code(14, notable_traits)?;
+                        }
+                    }
+                }
+                let notices = get_member_notices(member, viewer.options().platform_warnings);
+                if member.definition.is_some()
+                    && (member.description.is_some() || !notices.is_empty())
+                {
+                    viewer.println()?;
+                }
+                for notice in &notices {
+                    viewer.print_notice(12, notice)?;
+                }
+                if let Some(replacement) = member
+                    .deprecated
+                    .as_ref()
+                    .and_then(|deprecated| deprecated.replacement.as_ref())
+                {
+                    viewer.print_replacement(14, &member.name, replacement)?;
+                }
+                if let Some(description) = &member.description {
+                    viewer.print_text(12, description)?;
+                }
+                if member.definition.is_some()
+                    || member.description.is_some()
+                    || !notices.is_empty()
+                {
+                    viewer.println()?;
+                }
             }
-            self.print_code(6, &example.code)?;
-            self.println()?;
         }
+        if !bodies || line_length.is_some() {
+            viewer.println()?;
+        }
+    }
+    Ok(())
+}
 
-        Ok(())
+/// The number of members of a module above which the compact layout is used if it is not set
+/// explicitly, see `RenderOptions::compact`.
+const COMPACT_THRESHOLD: usize = 30;
+
+/// The number of spaces between the name and the description in the compact layout.
+const COMPACT_SPACING: usize = 2;
+
+/// Whether the members of the given item should be listed in compact rows.
+fn use_compact<M: ManRenderer + ?Sized>(viewer: &M, doc: &doc::Doc) -> bool {
+    if doc.ty != doc::ItemType::Module {
+        return false;
     }
+    viewer.options().compact.unwrap_or_else(|| {
+        let count: usize = doc
+            .groups
+            .values()
+            .flatten()
+            .map(|group| group.members.len())
+            .sum();
+        count > COMPACT_THRESHOLD
+    })
+}
+
+/// Prints the members of the given module member group as rows with the name, padded to the
+/// longest name in the group, and the description, truncated to the line length.
+fn print_member_rows<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    ty: doc::ItemType,
+    group: &doc::MemberGroup,
+    line_length: usize,
+) -> Result<(), M::Error> {
+    use unicode_width::UnicodeWidthStr as _;
+
+    let indent = 6;
+    let prefix = if viewer.options().type_prefix {
+        get_type_prefix(ty)
+    } else {
+        String::new()
+    };
+    let name_width = group
+        .members
+        .iter()
+        .map(|member| prefix.width() + member.name.last().width())
+        .max()
+        .unwrap_or_default();
+    let description_width = line_length.saturating_sub(indent + name_width + COMPACT_SPACING);
+    for member in &group.members {
+        let name = format!("{}{}", prefix, member.name.last());
+        let name = name.as_str();
+        let description = member
+            .description
+            .as_ref()
+            .map(|d| d.plain.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+        let link = DocLink {
+            name: member.name.clone(),
+            ty: Some(ty),
+        };
+        viewer.print_member_row(
+            indent as u8,
+            name,
+            name_width - name.width() + COMPACT_SPACING,
+            &truncate_to_width(
+                &viewer.options().prepare_text(&description),
+                description_width,
+                viewer.options().ascii,
+            ),
+            Some(link),
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the tag that is prepended to the names of members of the given type if the
+/// `--type-prefix` option is set, e. g. `[struct] `.
+fn get_type_prefix(ty: doc::ItemType) -> String {
+    format!("[{}] ", ty.name().to_lowercase())
+}
+
+/// Truncates the given string to the given display width and appends `…`, or `...` if `ascii` is
+/// set, if it is truncated.
+fn truncate_to_width(s: &str, width: usize, ascii: bool) -> std::borrow::Cow<'_, str> {
+    use unicode_width::{UnicodeWidthChar as _, UnicodeWidthStr as _};
+
+    if s.width() <= width {
+        return s.into();
+    }
+    let ellipsis = if ascii { "..." } else { "…" };
+    let ellipsis_width = ellipsis.width();
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    for c in s.chars() {
+        let c_width = c.width().unwrap_or_default();
+        if truncated_width + c_width + ellipsis_width > width {
+            break;
+        }
+        truncated.push(c);
+        truncated_width += c_width;
+    }
+    if width >= ellipsis_width {
+        truncated.push_str(ellipsis);
+    }
+    truncated.into()
+}
+
+/// Returns the notices from the item info of the given member, i. e. the deprecation notice and
+/// the platform and crate feature restrictions.
+fn get_member_notices(member: &doc::Doc, platform_warnings: bool) -> Vec<String> {
+    let mut notices = Vec::new();
+    if let Some(deprecated) = &member.deprecated {
+        notices.push(deprecated.to_string());
+    }
+    if let Some(platform) = &member.platform {
+        let status = PlatformStatus::new(platform, platform_warnings);
+        notices.push(status.message(platform));
+    }
+    if !member.required_features.is_empty() {
+        notices.push(format!(
+            "Requires the crate features: {}",
+            member.required_features.join(", ")
+        ));
+    }
+    notices
+}
+
+/// Prints a nested member group:  The title of the group is used as the heading, followed by the
+/// definitions and descriptions of the members.
+fn print_subgroup<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    group: &doc::MemberGroup,
+    bodies: bool,
+) -> Result<(), M::Error> {
+    if let Some(title) = &group.title {
+        print_heading(viewer, 3, title, None)?;
+    }
+    if !bodies {
+        return Ok(());
+    }
+    for member in &group.members {
+        if let Some(definition) = &member.definition {
+            print_definition(viewer, 12, definition)?;
+        }
+        if let Some(description) = &member.description {
+            viewer.print_text(12, description)?;
+        }
+    }
+    viewer.println()
+}
+
+/// Groups the given trait implementations by the name of the implemented trait.
+///
+/// The groups are ordered by the first implementation of the trait.  Within a group, the
+/// implementations keep their order.
+pub fn group_impls(impls: &[doc::Doc]) -> Vec<doc::MemberGroup> {
+    let mut groups: Vec<doc::MemberGroup> = Vec::new();
+    for member in impls {
+        let name = member
+            .definition
+            .as_ref()
+            .and_then(|d| get_trait_name(d))
+            .unwrap_or_else(|| member.name.last());
+        if let Some(group) = groups.iter_mut().find(|g| g.title.as_deref() == Some(name)) {
+            group.members.push(member.clone());
+        } else {
+            let mut group = doc::MemberGroup::new(Some(name.to_owned()));
+            group.members.push(member.clone());
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Returns the name of the trait from the given impl header, for example `From` for
+/// `impl<T> From<T> for Foo`.  Negative implementations like `impl !Send for Foo` are grouped with
+/// the positive ones.
+pub fn get_trait_name(definition: &str) -> Option<&str> {
+    let s = definition.trim_start();
+    let s = s.strip_prefix("unsafe ").unwrap_or(s);
+    let mut s = s.strip_prefix("impl")?;
+
+    // skip the generic parameters of the impl
+    if s.starts_with('<') {
+        let mut depth = 0;
+        let mut end = None;
+        let mut previous = ' ';
+        for (i, c) in s.char_indices() {
+            match c {
+                '<' => depth += 1,
+                // ignore the arrow in Fn() -> T
+                '>' if previous != '-' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            previous = c;
+        }
+        s = &s[end? + 1..];
+    }
+
+    let s = s.trim_start();
+    let s = s.strip_prefix('!').unwrap_or(s);
+    let end = s
+        .find(|c: char| c == '<' || c.is_whitespace())
+        .unwrap_or(s.len());
+    let path = &s[..end];
+    path.rsplit("::").next().filter(|name| !name.is_empty())
+}
+
+fn print_see_also<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    items: &[doc::Doc],
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    print_section_heading(viewer, "See also", has_sections)?;
+    for item in items {
+        let link = DocLink {
+            name: item.name.clone(),
+            ty: Some(item.ty),
+        };
+        print_heading(viewer, 3, item.name.as_ref(), Some(link))?;
+    }
+    viewer.println()
+}
+
+fn print_siblings<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    doc: &doc::Doc,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    let parent = if let Some(parent) = doc.name.parent() {
+        parent
+    } else {
+        return Ok(());
+    };
+    if doc.siblings.is_empty() {
+        return Ok(());
+    }
+
+    print_section_heading(viewer, &format!("Other items in {}", parent), has_sections)?;
+    let mut last_ty = None;
+    for sibling in &doc.siblings {
+        if last_ty != Some(sibling.ty) {
+            print_heading(viewer, 2, sibling.ty.group_name(), None)?;
+            last_ty = Some(sibling.ty);
+        }
+        let link = DocLink {
+            name: sibling.name.clone(),
+            ty: Some(sibling.ty),
+        };
+        print_heading(viewer, 3, sibling.name.last(), Some(link))?;
+    }
+    viewer.println()
+}
+
+fn print_examples<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    examples: &[doc::Example],
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    print_section_heading(viewer, "Examples", has_sections)?;
+
+    let n = examples.len();
+    for (i, example) in examples.iter().enumerate() {
+        if n > 1 {
+            print_heading(viewer, 2, &format!("Example {} of {}", i + 1, n), None)?;
+        }
+        if let Some(description) = &example.description {
+            viewer.print_text(6, description)?;
+            viewer.println()?;
+        }
+        if !example.tags.is_empty() {
+            let label = format!("({})", example.tags.join(", "));
+            viewer.print_code(6, &doc::Code::new(label))?;
+        }
+        if viewer.options().compact_examples {
+            viewer.print_code(6, &example.code.compacted())?;
+        } else {
+            viewer.print_code(6, &example.code)?;
+        }
+        viewer.println()?;
+    }
+    Ok(())
 }
 
 fn print_title<M: ManRenderer + ?Sized>(viewer: &mut M, doc: &doc::Doc) -> Result<(), M::Error> {
     let title = format!("{} {}", doc.ty.name(), doc.name);
-    viewer.print_title(doc.name.krate(), &title, "rusty-man")
+    viewer.print_title(
+        doc.name.krate(),
+        &title,
+        "rusty-man",
+        doc.crate_version.as_deref(),
+    )?;
+    viewer.print_name(doc)
+}
+
+/// Returns the right part of the title line for viewers that print it as a single line:  If the
+/// crate version is known, the crate and its version are prepended to `right`.
+pub fn get_title_right<'a>(
+    krate: &str,
+    right: &'a str,
+    version: Option<&str>,
+) -> borrow::Cow<'a, str> {
+    if let Some(version) = version {
+        format!("{} {} — {}", krate, version, right).into()
+    } else {
+        right.into()
+    }
 }
 
 fn print_heading<M: ManRenderer + ?Sized>(
@@ -304,6 +1383,28 @@ fn print_heading<M: ManRenderer + ?Sized>(
     viewer.print_heading(indent, text.as_ref(), link)
 }
 
+/// Prints the top-level heading of a section, see `start_section`.
+fn print_section_heading<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    text: &str,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    start_section(viewer, has_sections)?;
+    print_heading(viewer, 1, text, None)
+}
+
+/// Starts a new top-level section.  If a section has already been printed, i. e. if
+/// `has_sections` is set, and the `separators` option is set, a separator is printed.
+fn start_section<M: ManRenderer + ?Sized>(
+    viewer: &mut M,
+    has_sections: &mut bool,
+) -> Result<(), M::Error> {
+    if mem::replace(has_sections, true) && viewer.options().separators {
+        viewer.print_separator()?;
+    }
+    Ok(())
+}
+
 /// Link handling mode for the [`RichDecorator`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum LinkMode {
@@ -318,15 +1419,21 @@ pub enum LinkMode {
 pub struct RichDecorator {
     link_filter: fn(&str) -> bool,
     link_mode: LinkMode,
+    annotate_code: bool,
     ignore_next_link: bool,
     links: Vec<String>,
 }
 
 impl RichDecorator {
-    pub fn new(link_filter: fn(&str) -> bool, link_mode: LinkMode) -> RichDecorator {
+    pub fn new(
+        link_filter: fn(&str) -> bool,
+        link_mode: LinkMode,
+        annotate_code: bool,
+    ) -> RichDecorator {
         RichDecorator {
             link_filter,
             link_mode,
+            annotate_code,
             ignore_next_link: false,
             links: Vec::new(),
         }
@@ -388,7 +1495,11 @@ impl text_renderer::TextDecorator for RichDecorator {
     }
 
     fn decorate_code_start(&mut self) -> (String, Self::Annotation) {
-        ("".to_string(), text_renderer::RichAnnotation::Code)
+        if self.annotate_code {
+            ("".to_string(), text_renderer::RichAnnotation::Code)
+        } else {
+            ("".to_string(), text_renderer::RichAnnotation::Default)
+        }
     }
 
     fn decorate_code_end(&mut self) -> String {
@@ -427,7 +1538,42 @@ impl text_renderer::TextDecorator for RichDecorator {
     }
 
     fn make_subblock_decorator(&self) -> Self {
-        RichDecorator::new(self.link_filter, self.link_mode)
+        RichDecorator::new(self.link_filter, self.link_mode, self.annotate_code)
+    }
+}
+
+pub fn get_impl_order(args: &args::ViewerArgs) -> args::ImplOrder {
+    args.impl_order.unwrap_or(args::ImplOrder::Grouped)
+}
+
+pub fn get_sort_order(args: &args::ViewerArgs) -> args::SortOrder {
+    args.sort.unwrap_or(args::SortOrder::Source)
+}
+
+pub fn get_section_order(args: &args::ViewerArgs) -> Vec<args::SectionKind> {
+    if args.section_order.is_empty() {
+        args::SectionKind::DEFAULT_ORDER.to_vec()
+    } else {
+        args.section_order.clone()
+    }
+}
+
+pub fn get_max_definition_lines(args: &args::ViewerArgs) -> Option<usize> {
+    if args.no_max_definition_lines {
+        None
+    } else {
+        args.max_definition_lines
+    }
+}
+
+/// Returns whether the compact layout should be used for modules, see `RenderOptions::compact`.
+pub fn get_compact(args: &args::ViewerArgs) -> Option<bool> {
+    if args.no_compact {
+        Some(false)
+    } else if args.compact {
+        Some(true)
+    } else {
+        None
     }
 }
 
@@ -442,7 +1588,7 @@ pub fn get_line_length(args: &args::ViewerArgs) -> usize {
 }
 
 pub fn get_highlighter(args: &args::ViewerArgs) -> anyhow::Result<Option<Highlighter>> {
-    if args.no_syntax_highlight {
+    if args.no_syntax_highlight || args.no_code_highlight {
         Ok(None)
     } else {
         Highlighter::new(args).map(Some)
@@ -454,11 +1600,386 @@ pub fn reset_background(mut s: text_style::StyledStr<'_>) -> text_style::StyledS
     s
 }
 
+/// The syntax highlighting theme that is used for dark terminals if the `--theme` option is not
+/// set.
+pub const DEFAULT_DARK_THEME: &str = "base16-eighties.dark";
+
+/// The syntax highlighting theme that is used for light terminals if the `--theme` option is not
+/// set.
+pub const DEFAULT_LIGHT_THEME: &str = "InspiredGitHub";
+
+/// Returns the name of the syntax highlighting theme to use.  If the `--theme` option is not set,
+/// the theme depends on the background of the terminal, see the `background` module.
+pub fn get_theme_name(args: &args::ViewerArgs) -> &str {
+    if let Some(theme) = &args.theme {
+        return theme;
+    }
+    match background::get() {
+        background::Background::Light => args.theme_light.as_deref().unwrap_or(DEFAULT_LIGHT_THEME),
+        background::Background::Dark => args.theme_dark.as_deref().unwrap_or(DEFAULT_DARK_THEME),
+    }
+}
+
+/// Returns the names of the syntax highlighting themes included in rusty-man.
+pub fn get_theme_names() -> Vec<String> {
+    syntect::highlighting::ThemeSet::load_defaults()
+        .themes
+        .into_keys()
+        .collect()
+}
+
 fn get_syntect_theme(args: &args::ViewerArgs) -> anyhow::Result<syntect::highlighting::Theme> {
     let mut theme_set = syntect::highlighting::ThemeSet::load_defaults();
-    let theme_name = args.theme.as_deref().unwrap_or("base16-eighties.dark");
+    let theme_name = get_theme_name(args);
+    log::info!("Using the syntax highlighting theme {}", theme_name);
     theme_set
         .themes
         .remove(theme_name)
         .with_context(|| format!("Could not find theme {}", theme_name))
 }
+
+/// A `ManRenderer` implementation that collects the rendered documentation in a string.
+///
+/// The text is not wrapped and does not contain any formatting, so the output only depends on the
+/// documentation item and not on the terminal or the options.
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub struct StringManRenderer {
+    output: String,
+    options: RenderOptions,
+}
+
+#[cfg(test)]
+impl StringManRenderer {
+    pub fn new() -> StringManRenderer {
+        StringManRenderer {
+            output: String::new(),
+            options: RenderOptions::new(&Default::default()),
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        self.output
+    }
+
+    fn write_line(&mut self, indent: u8, line: &str) -> std::fmt::Result {
+        use std::fmt::Write as _;
+
+        writeln!(
+            self.output,
+            "{}{}",
+            " ".repeat(indent.into()),
+            line.trim_end()
+        )
+    }
+}
+
+#[cfg(test)]
+impl ManRenderer for StringManRenderer {
+    type Error = std::fmt::Error;
+
+    fn print_title(
+        &mut self,
+        left: &str,
+        center: &str,
+        right: &str,
+        version: Option<&str>,
+    ) -> std::fmt::Result {
+        let right = get_title_right(left, right, version);
+        self.write_line(0, &format!("{} | {} | {}", left, center, right))?;
+        self.println()
+    }
+
+    fn print_heading(
+        &mut self,
+        indent: u8,
+        text: &str,
+        _link: Option<DocLink>,
+    ) -> std::fmt::Result {
+        self.write_line(indent, text)
+    }
+
+    fn print_code(&mut self, indent: u8, code: &doc::Code) -> std::fmt::Result {
+        for line in code.split('\n') {
+            self.write_line(indent, line)?;
+        }
+        Ok(())
+    }
+
+    fn print_text(&mut self, indent: u8, text: &doc::Text) -> std::fmt::Result {
+        for line in text.plain.trim().split('\n') {
+            self.write_line(indent, line)?;
+        }
+        Ok(())
+    }
+
+    fn println(&mut self) -> std::fmt::Result {
+        self.output.push('\n');
+        Ok(())
+    }
+
+    fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decorations_to_ascii, html_to_ascii, split_segments, to_ascii, truncate_to_width,
+        HighlightedHtmlElement, Highlighter,
+    };
+    use super::{get_replacement_link, get_trait_name, group_impls, resolve_link};
+    use super::{ManRenderer as _, PlatformStatus, ResolvedLink, StringManRenderer};
+    use crate::args;
+    use crate::doc;
+
+    #[test]
+    fn test_get_replacement_link() {
+        let member: doc::Fqn = "rand_core::Error::cause".to_owned().into();
+        let link = |s: &str| get_replacement_link(&member, s).name.to_string();
+        assert_eq!("rand_core::Error::source", link("source"));
+        assert_eq!("rand_core::Error::source", link("Error::source"));
+        assert_eq!("rand_core::RngCore", link("rand_core::RngCore"));
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let name: doc::Fqn = "kuchiki::NodeRef".to_owned().into();
+        let ty = doc::ItemType::Struct;
+        assert_eq!(
+            ResolvedLink::Doc(
+                Some(doc::ItemType::Struct),
+                "kuchiki::NodeDataRef".to_owned().into()
+            ),
+            resolve_link(&name, ty, "struct.NodeDataRef.html").unwrap()
+        );
+        assert_eq!(
+            ResolvedLink::Doc(
+                Some(doc::ItemType::Method),
+                "kuchiki::NodeRef::select".to_owned().into()
+            ),
+            resolve_link(&name, ty, "#method.select").unwrap()
+        );
+        assert_eq!(
+            ResolvedLink::External("https://docs.rs/html5ever".to_owned()),
+            resolve_link(&name, ty, "https://docs.rs/html5ever").unwrap()
+        );
+        assert!(resolve_link(&name, ty, "../../struct.NodeRef.html").is_err());
+    }
+
+    #[test]
+    fn test_split_segments() {
+        let segments = [(1, "fn foo"), (2, "() {"), (3, "}"), (4, "\n")];
+        assert_eq!(
+            vec![(1, "fn "), (1, "foo"), (2, "()"), (2, " {"), (3, "}")],
+            split_segments(&segments, &["fn ", "foo()", " {}"])
+        );
+        assert_eq!(
+            vec![(1, "fn foo"), (2, "() {"), (3, "}")],
+            split_segments(&segments, &["", "fn foo() {}"])
+        );
+        assert_eq!(Vec::<(u8, &str)>::new(), split_segments(&segments, &[]));
+    }
+
+    #[test]
+    fn test_highlight_html_context() {
+        let html = "<pre>let a = \"x\n    y\";\nlet b = 1;</pre>";
+        let lines = html2text::parse(html.as_bytes())
+            .render(
+                80,
+                super::RichDecorator::new(|_| false, super::LinkMode::List, false),
+            )
+            .into_lines();
+        let highlighter = Highlighter::new(&args::ViewerArgs::default()).unwrap();
+        let lines: Vec<_> = super::highlight_html(&lines, Some(&highlighter)).collect();
+        let get_style = |line: usize, s: &str| {
+            lines[line]
+                .iter()
+                .find_map(|e| match e {
+                    HighlightedHtmlElement::StyledString(ss) if ss.s.trim() == s => Some(ss.style),
+                    _ => None,
+                })
+                .unwrap_or_else(|| panic!("Could not find {} in line {}", s, line))
+        };
+        // The second line is part of the string literal that starts in the first line.
+        assert_eq!(get_style(0, "x"), get_style(1, "y"));
+        // The third line is highlighted as code again.
+        assert_eq!(get_style(0, "let"), get_style(2, "let"));
+    }
+
+    #[test]
+    fn test_highlight_html_quote_gutter() {
+        let html =
+            "<blockquote><p>Note: a &gt; b</p><blockquote><p>nested</p></blockquote></blockquote>";
+        let lines = html2text::parse(html.as_bytes())
+            .render(
+                80,
+                super::RichDecorator::new(|_| false, super::LinkMode::List, false),
+            )
+            .into_lines();
+        let lines: Vec<_> = super::highlight_html(&lines, None)
+            .map(|line| {
+                line.iter()
+                    .map(|e| match e {
+                        HighlightedHtmlElement::RichString(ts) => ts.s.clone(),
+                        HighlightedHtmlElement::StyledString(ss) => ss.s.to_owned(),
+                        HighlightedHtmlElement::QuoteGutter => "|".to_owned(),
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_owned()
+            })
+            .collect();
+        assert_eq!(vec!["|Note: a > b", "|", "||nested"], lines);
+    }
+
+    #[test]
+    fn test_get_trait_name() {
+        let assert_name = |definition: &str, name: Option<&str>| {
+            assert_eq!(
+                name,
+                get_trait_name(definition),
+                "trait name for '{}'",
+                definition
+            );
+        };
+        assert_name("impl Clone for NodeRef", Some("Clone"));
+        assert_name("impl !Send for NodeRef", Some("Send"));
+        assert_name("impl<T> From<T> for T", Some("From"));
+        assert_name("impl<'a> Iterator for Chain<'a>", Some("Iterator"));
+        assert_name("unsafe impl Send for Error", Some("Send"));
+        assert_name(
+            "impl<F: Fn() -> T, T> std::ops::FnOnce<()> for Lazy<F>",
+            Some("FnOnce"),
+        );
+        assert_name(
+            "impl<T> Borrow<T> for T \nwhere\n    T: ?Sized,",
+            Some("Borrow"),
+        );
+        assert_name("pub struct Foo", None);
+    }
+
+    #[test]
+    fn test_group_impls() {
+        let parent: doc::Fqn = "anyhow::Error".to_owned().into();
+        let impls: Vec<_> = [
+            ("From", "impl<E> From<E> for Error"),
+            ("Debug", "impl Debug for Error"),
+            ("From", "impl From<Error> for Box<dyn StdError>"),
+        ]
+        .iter()
+        .map(|(name, definition)| {
+            let mut doc = doc::Doc::new(parent.child(name), doc::ItemType::Impl);
+            doc.definition = Some(doc::Code::new(definition.to_string()));
+            doc
+        })
+        .collect();
+
+        let groups = group_impls(&impls);
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|g| (g.title.as_deref().unwrap(), g.members.len()))
+            .collect();
+        assert_eq!(vec![("From", 2), ("Debug", 1)], groups);
+    }
+
+    #[test]
+    fn test_platform_status() {
+        let os = std::env::consts::OS;
+        let other = if os == "windows" { "Linux" } else { "Windows" };
+        assert_eq!(PlatformStatus::Supported, PlatformStatus::new(os, true));
+        assert_eq!(
+            PlatformStatus::Unknown,
+            PlatformStatus::new("crate feature std", true)
+        );
+        assert_eq!(
+            PlatformStatus::Unsupported,
+            PlatformStatus::new(other, false)
+        );
+        assert_eq!(PlatformStatus::Warning, PlatformStatus::new(other, true));
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!("", truncate_to_width("", 0, false));
+        assert_eq!("", truncate_to_width("foo", 0, false));
+        assert_eq!("…", truncate_to_width("foo", 1, false));
+        assert_eq!("fo…", truncate_to_width("foobar", 3, false));
+        assert_eq!("foobar", truncate_to_width("foobar", 6, false));
+        assert_eq!("“foo”", truncate_to_width("“foo”", 5, false));
+        // wide characters take two columns
+        assert_eq!("日…", truncate_to_width("日本語", 4, false));
+        assert_eq!("日本語", truncate_to_width("日本語", 6, false));
+        // the ASCII ellipsis takes three columns
+        assert_eq!("", truncate_to_width("foobar", 2, true));
+        assert_eq!("fo...", truncate_to_width("foobar", 5, true));
+        assert_eq!("foobar", truncate_to_width("foobar", 6, true));
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!("foo", to_ascii("foo"));
+        assert_eq!("| quote", to_ascii("│ quote"));
+        assert_eq!("! Deprecated...", to_ascii("⚠ Deprecated…"));
+        assert_eq!("log 0.4.14 - rusty-man", to_ascii("log 0.4.14 — rusty-man"));
+        assert_eq!("-+-", to_ascii("─┼─"));
+        assert_eq!("  where", to_ascii("\u{a0}\u{a0}where"));
+        // other non-ASCII characters are not changed
+        assert_eq!("“Zürich”", to_ascii("“Zürich”"));
+        // the prose characters are only replaced by to_ascii
+        assert_eq!("| a — b…", decorations_to_ascii("│ a — b…"));
+    }
+
+    #[test]
+    fn test_html_to_ascii() {
+        let html = html_to_ascii("<p>a — b… <code>c — d…</code></p><pre>e — f</pre>");
+        assert!(html.contains("<p>a - b... <code>c — d…</code></p><pre>e — f</pre>"));
+    }
+
+    #[test]
+    fn test_string_man_renderer() {
+        let mut doc = doc::Doc::new("kuchiki::NodeRef".to_owned().into(), doc::ItemType::Struct);
+        doc.crate_version = Some("0.8.1".to_owned());
+        doc.definition = Some(doc::Code::new("pub struct NodeRef(_);".to_owned()));
+        doc.description = Some(doc::Text {
+            plain: "A strong reference to a node.".to_owned(),
+            html: "<p>A strong reference to a node.</p>".to_owned(),
+        });
+
+        let mut renderer = StringManRenderer::new();
+        renderer.render_doc(&doc).unwrap();
+        let expected = "\
+kuchiki | Struct kuchiki::NodeRef | kuchiki 0.8.1 — rusty-man
+
+SYNOPSIS
+      pub struct NodeRef(_);
+
+DESCRIPTION
+      A strong reference to a node.
+
+";
+        assert_eq!(expected, renderer.into_string());
+    }
+
+    #[test]
+    fn test_print_notes() {
+        let mut doc = doc::Doc::new("log::Foo".to_owned().into(), doc::ItemType::Struct);
+        doc.deprecated = Some(doc::DeprecatedInfo {
+            since: Some("0.4.0".to_owned()),
+            note: Some("use `Bar` instead".to_owned()),
+            replacement: Some("Bar".to_owned()),
+        });
+
+        let mut renderer = StringManRenderer::new();
+        renderer.options.section_order = vec![args::SectionKind::Notes];
+        renderer.render_doc(&doc).unwrap();
+        let expected = "\
+NOTES
+      ⚠ Deprecated since 0.4.0: use `Bar` instead
+        See: `Bar`
+
+";
+        assert_eq!(expected, renderer.into_string());
+    }
+}
@@ -0,0 +1,164 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: MIT
+
+//! Watches the documentation files of an item for changes, see the `--watch` option.
+//!
+//! `cargo doc` does not modify the existing files but removes and recreates them, so watching
+//! the files themselves would stop after the first rebuild.  Instead, we watch the directories
+//! that contain the HTML file of the item and the search index and filter the events by the file
+//! names.
+
+use std::path;
+use std::sync::mpsc;
+use std::time;
+
+use anyhow::Context as _;
+use notify::Watcher as _;
+
+use crate::doc;
+
+/// The time without further changes to wait for before reporting a change.  A rebuild with
+/// `cargo doc` touches many files, and we only want to render the documentation once.
+const DEBOUNCE_DELAY: time::Duration = time::Duration::from_millis(300);
+
+/// Watches the HTML file of an item and the search index of its crate.
+pub struct Watcher {
+    // The watcher stops when it is dropped, so we have to keep it around.
+    _watcher: notify::RecommendedWatcher,
+    changes: mpsc::Receiver<()>,
+}
+
+impl Watcher {
+    pub fn new(doc: &doc::Doc) -> anyhow::Result<Watcher> {
+        let html_path = get_html_path(doc)?;
+        let html_dir = html_path
+            .parent()
+            .context("The documentation file has no parent directory")?
+            .to_owned();
+        let index_dir = html_dir
+            .ancestors()
+            .find(|dir| has_search_index(dir))
+            .map(ToOwned::to_owned);
+
+        let (tx, changes) = mpsc::channel();
+        let filter_index_dir = index_dir.clone();
+        let mut watcher = notify::recommended_watcher(
+            move |result: notify::Result<notify::Event>| match result {
+                Ok(event) => {
+                    if event.kind.is_access() {
+                        return;
+                    }
+                    let is_relevant = event.paths.iter().any(|path| {
+                        path == &html_path
+                            || (filter_index_dir.as_deref() == path.parent()
+                                && is_search_index(path))
+                    });
+                    if is_relevant {
+                        log::info!("Detected change: {:?}", event);
+                        // The receiver is only dropped together with the watcher.
+                        let _ = tx.send(());
+                    }
+                }
+                Err(err) => log::warn!("Error while watching the documentation: {}", err),
+            },
+        )
+        .context("Could not create the file watcher")?;
+
+        watcher
+            .watch(&html_dir, notify::RecursiveMode::NonRecursive)
+            .with_context(|| format!("Could not watch {}", html_dir.display()))?;
+        if let Some(index_dir) = index_dir.filter(|dir| dir != &html_dir) {
+            watcher
+                .watch(&index_dir, notify::RecursiveMode::NonRecursive)
+                .with_context(|| format!("Could not watch {}", index_dir.display()))?;
+        }
+
+        Ok(Watcher {
+            _watcher: watcher,
+            changes,
+        })
+    }
+
+    /// Blocks until the documentation files have changed and no further changes have been made
+    /// for `DEBOUNCE_DELAY`.
+    pub fn wait(&self) -> anyhow::Result<()> {
+        self.changes
+            .recv()
+            .context("The file watcher stopped unexpectedly")?;
+        while self.changes.recv_timeout(DEBOUNCE_DELAY).is_ok() {}
+        Ok(())
+    }
+}
+
+/// Returns the canonical path of the HTML file for the given item.
+fn get_html_path(doc: &doc::Doc) -> anyhow::Result<path::PathBuf> {
+    let path = doc
+        .url
+        .as_ref()
+        .and_then(|url| url::Url::parse(url).ok())
+        .and_then(|url| url.to_file_path().ok())
+        .with_context(|| format!("The documentation for {} is not local", &doc.name))?;
+    path.canonicalize()
+        .with_context(|| format!("Could not access {}", path.display()))
+}
+
+fn has_search_index(dir: &path::Path) -> bool {
+    dir.read_dir()
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .any(|entry| is_search_index(&entry.path()))
+        })
+        .unwrap_or_default()
+}
+
+/// Whether the given path is a search index file.  Newer rustdoc versions add the version to the
+/// file name, e. g. `search-index1.56.0.js`.
+fn is_search_index(path: &path::Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with("search-index") && name.ends_with(".js"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::thread;
+    use std::time;
+
+    use super::Watcher;
+    use crate::doc;
+
+    #[test]
+    fn test_watcher_recreated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let html_path = root.join("foo").join("struct.Foo.html");
+        fs::create_dir(root.join("foo")).unwrap();
+        fs::write(&html_path, "old").unwrap();
+        fs::write(root.join("search-index.js"), "old").unwrap();
+
+        let mut doc = doc::Doc::new("foo::Foo".to_owned().into(), doc::ItemType::Struct);
+        doc.set_url(&html_path, None);
+        let watcher = Watcher::new(&doc).unwrap();
+
+        // like cargo doc, remove the file and create it again
+        let path = html_path.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(time::Duration::from_millis(100));
+            fs::remove_file(&path).unwrap();
+            fs::write(&path, "new").unwrap();
+        });
+        watcher.wait().unwrap();
+        handle.join().unwrap();
+
+        let path = root.join("search-index.js");
+        let handle = thread::spawn(move || {
+            thread::sleep(time::Duration::from_millis(100));
+            fs::write(&path, "new").unwrap();
+        });
+        watcher.wait().unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -5,20 +5,32 @@
 mod test_utils;
 
 use std::env;
+use std::fs;
+#[cfg(unix)]
+use std::io::Read as _;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt as _;
+#[cfg(unix)]
+use std::os::unix::io::FromRawFd as _;
 use std::path;
 use std::process;
+#[cfg(unix)]
+use std::thread;
 
 use assert_cmd::prelude::*;
 
 use test_utils::{with_rustdoc, Format};
 
 fn run(path: impl AsRef<path::Path>, args: &[&str]) -> assert_cmd::assert::Assert {
+    // The opened items should not be added to the history of the user.
+    let data_dir = tempfile::tempdir().unwrap();
     process::Command::cargo_bin(env!("CARGO_PKG_NAME"))
         .unwrap()
-        .args(&["--no-default-sources", "--source"])
+        .env("XDG_DATA_HOME", data_dir.path())
+        .args(["--no-default-sources", "--source"])
         .arg(path.as_ref())
-        .args(&["--viewer", "plain"])
-        .args(&["--width", "100"])
+        .args(["--viewer", "plain"])
+        .args(["--width", "100"])
         .args(args)
         .assert()
 }
@@ -28,6 +40,76 @@ fn get_stdout(path: impl AsRef<path::Path>, args: &[&str]) -> String {
     String::from_utf8(cmd.get_output().stdout.clone()).unwrap()
 }
 
+/// Runs rusty-man with stdout connected to a pseudo terminal so that the pager is used and returns
+/// the output written to the terminal.
+#[cfg(unix)]
+fn run_in_pty(path: impl AsRef<path::Path>, args: &[&str], envs: &[(&str, &str)]) -> String {
+    let mut master = 0;
+    let mut slave = 0;
+    let size = libc::winsize {
+        ws_row: 40,
+        ws_col: 100,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // Safety: We pass valid pointers, and the terminal name pointer may be null.
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &size,
+        )
+    };
+    assert_eq!(0, result, "Could not open pseudo terminal");
+    // Safety: openpty returned two new file descriptors that we own.
+    let (mut master, slave) =
+        unsafe { (fs::File::from_raw_fd(master), fs::File::from_raw_fd(slave)) };
+
+    let data_dir = tempfile::tempdir().unwrap();
+    let mut cmd = process::Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.env("XDG_DATA_HOME", data_dir.path())
+        .args(["--no-default-sources", "--source"])
+        .arg(path.as_ref())
+        .args(["--viewer", "plain"])
+        .args(args)
+        .envs(envs.iter().copied())
+        .stdin(process::Stdio::null())
+        .stdout(slave);
+    let mut child = cmd.spawn().unwrap();
+    // The command holds our copy of the slave, so we have to drop it to be able to detect the end
+    // of the output.
+    drop(cmd);
+
+    // The output has to be read while the child is running, otherwise it could block on a full
+    // terminal buffer.
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        // Reading fails with EIO once the terminal has been closed by all processes.
+        let _ = master.read_to_end(&mut output);
+        output
+    });
+    let status = child.wait().unwrap();
+    assert!(status.success());
+    let output = reader.join().unwrap();
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Writes a pager script to the given directory that stores its arguments and its input in the
+/// files `args` and `input` in the same directory.
+#[cfg(unix)]
+fn write_pager_script(dir: &path::Path) -> path::PathBuf {
+    let script = dir.join("pager.sh");
+    fs::write(
+        &script,
+        "#!/bin/sh\ndir=$(dirname \"$0\")\necho \"$@\" > \"$dir/args\"\ncat > \"$dir/input\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
 macro_rules! generate_run {
     ($name:ident $version:literal $formats:expr; $args:expr) => {
         #[test]
@@ -89,4 +171,166 @@ assert_examples![
     examples_mod_anyhow("*", Format::all()): "anyhow",
     examples_mod_log(">1.40.0", Format::all()): "log",
     examples_struct_rand_core_rngcore("*", Format::all()): "rand_core::RngCore",
+    examples_fn_rand_core_impls_fill_via_u32_chunks("*", Format::all()): "rand_core::impls::fill_via_u32_chunks",
 ];
+
+generate_run!(
+    section_struct_anyhow_error_trait_implementations "<1.51.0" Format::all();
+    &["--section", "Trait Implementations", "anyhow::Error"]
+);
+
+generate_run!(
+    notable_traits_struct_kuchiki_noderef ">=1.47.0" Format::all();
+    &["--notable-traits", "kuchiki::NodeRef"]
+);
+
+generate_run!(
+    dump_doc_trait_log_log "*" Format::all();
+    &["--dump-doc", "log::Log"]
+);
+
+generate_run!(
+    emit_manpage_trait_log_log "*" Format::all();
+    &["--emit-manpage", "log::Log"]
+);
+
+generate_run!(
+    section_order_trait_log_log "*" Format::all();
+    &["--section-order", "description,members,synopsis", "log::Log"]
+);
+
+generate_run!(
+    show_siblings_struct_kuchiki_noderef "*" Format::all();
+    &["--show-siblings", "--section-order=title", "kuchiki::NodeRef"]
+);
+
+generate_run!(
+    impl_order_source_struct_anyhow_error "<1.51.0" Format::all();
+    &["--impl-order", "source", "--section", "Trait Implementations", "anyhow::Error"]
+);
+
+generate_run!(
+    sort_alpha_enum_log_level "=1.56.0" Format::all();
+    &["--sort", "alpha", "--depth", "2", "log::Level"]
+);
+
+generate_run!(
+    no_separators_enum_log_level "=1.56.0" Format::all();
+    &["--no-separators", "--depth", "1", "log::Level"]
+);
+
+generate_run!(
+    compact_examples_trait_anyhow_context "=1.56.0" Format::all();
+    &["-e", "--compact-examples", "anyhow::Context"]
+);
+
+generate_run!(
+    ascii_trait_anyhow_context "=1.56.0" Format::all();
+    &["--ascii", "anyhow::Context"]
+);
+
+generate_run!(
+    wildcard_enum_log_level "=1.56.0" Format::all();
+    &["log::Level::*"]
+);
+
+generate_run!(
+    depth_trait_log_log "*" Format::all();
+    &["--depth", "2", "log::Log"]
+);
+
+generate_run!(
+    max_definition_lines_trait_log_log "*" Format::all();
+    &["--max-definition-lines", "2", "log::Log"]
+);
+
+generate_run!(
+    compact_mod_log "*" Format::all();
+    &["--compact", "--section-order=members", "log"]
+);
+
+generate_run!(
+    type_prefix_mod_log "=1.56.0" Format::all();
+    &["--type-prefix", "--compact", "--section-order=members", "log"]
+);
+
+generate_run!(
+    all_examples_anyhow "=1.56.0" Format::all();
+    &["--all-examples", "anyhow"]
+);
+
+generate_run!(
+    implementors_of_debug_kuchiki "=1.56.0" Format::all();
+    &["--implementors-of", "Debug", "kuchiki"]
+);
+
+#[test]
+fn emit_plain_roff_log_log() {
+    with_rustdoc("=1.56.0", Format::all(), |_, _, path| {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("log.txt");
+        let roff = dir.path().join("log.1");
+        let stdout = get_stdout(
+            path,
+            &[
+                "--emit",
+                &format!("plain:{}", plain.display()),
+                "--emit",
+                &format!("roff:{}", roff.display()),
+                "log::Log",
+            ],
+        );
+        assert_eq!("", stdout);
+        assert_eq!(
+            get_stdout(path, &["log::Log"]),
+            std::fs::read_to_string(&plain).unwrap()
+        );
+        assert!(std::fs::read_to_string(&roff)
+            .unwrap()
+            .starts_with(".TH \"Trait log::Log\""));
+    });
+}
+
+#[test]
+fn theme_preview() {
+    with_rustdoc("=1.56.0", Format::all(), |_, _, path| {
+        let stdout = get_stdout(path, &["--theme-preview"]);
+        for theme in &[
+            "InspiredGitHub",
+            "Solarized (light)",
+            "base16-eighties.dark",
+        ] {
+            assert!(stdout.contains(theme), "{} is missing", theme);
+        }
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn pager_cat() {
+    with_rustdoc("=1.56.0", Format::all(), |_, _, path| {
+        let dir = tempfile::tempdir().unwrap();
+        let script = write_pager_script(dir.path());
+        let output = run_in_pty(
+            path,
+            &["--pager", "cat", "log::Log"],
+            &[("PAGER", script.to_str().unwrap())],
+        );
+        assert!(output.contains("Trait log::Log"));
+        assert!(!dir.path().join("input").exists());
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn pager_with_arguments() {
+    with_rustdoc("=1.56.0", Format::all(), |_, _, path| {
+        let dir = tempfile::tempdir().unwrap();
+        let script = write_pager_script(dir.path());
+        let pager = format!("{} -S", script.display());
+        run_in_pty(path, &["--pager", &pager, "log::Log"], &[]);
+        assert_eq!("-S\n", fs::read_to_string(dir.path().join("args")).unwrap());
+        let input = fs::read_to_string(dir.path().join("input")).unwrap();
+        assert!(input.contains("Trait log::Log"));
+    });
+}